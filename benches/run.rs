@@ -0,0 +1,40 @@
+// Times the initial pass on a large file, run with `cargo bench --features rayon`
+// to compare the serial and parallel paths with different numbers of threads
+use std::time::{Duration, Instant};
+use synoptic::from_extension;
+
+const ITERATIONS: u32 = 10;
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    // Build up a large rust file out of the library's own source
+    let source = include_str!("../src/lib.rs");
+    let mut lines: Vec<String> = vec![];
+    while lines.len() < 20_000 {
+        lines.extend(source.split('\n').map(|l| l.to_string()));
+    }
+    let mut h = from_extension("rs", 4).unwrap();
+    println!("{} lines", lines.len());
+    let serial = time(|| h.run(&lines));
+    println!("run:                       {serial:?}");
+    #[cfg(feature = "rayon")]
+    {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let mut threads = vec![1, 2, 4, 8];
+        threads.retain(|n| *n < cores);
+        threads.push(cores);
+        for n in threads {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(n).build().unwrap();
+            let parallel = pool.install(|| time(|| h.run_parallel(&lines)));
+            let speedup = serial.as_secs_f64() / parallel.as_secs_f64();
+            println!("run_parallel ({n:>2} threads): {parallel:?} ({speedup:.2}x)");
+        }
+    }
+}
@@ -0,0 +1,5 @@
+fn main() {
+    // Node.js addons need extra linker arguments on some platforms
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
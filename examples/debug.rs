@@ -1,4 +1,4 @@
-use synoptic::{Highlighter, TokOpt, trim_fit};
+use synoptic::TokOpt;
 use lliw::Fg;
 
 pub static CODE: &str = r#"f"""#;
@@ -10,7 +10,7 @@ fn main() {
     // Initial state
     for token in &h.line(0, &code[0]) {
         match token {
-            TokOpt::Some(text, kind) => print!("{}{text}{}", colour(&kind), Fg::Reset),
+            TokOpt::Some(text, kind) => print!("{}{text}{}", colour(kind), Fg::Reset),
             TokOpt::None(text) => print!("{text}"),
         }
     }
@@ -21,7 +21,7 @@ fn main() {
     // Observe incorrect new state
     for token in &h.line(0, &code[0]) {
         match token {
-            TokOpt::Some(text, kind) => print!("{}{text}{}", colour(&kind), Fg::Reset),
+            TokOpt::Some(text, kind) => print!("{}{text}{}", colour(kind), Fg::Reset),
             TokOpt::None(text) => print!("{text}"),
         }
     }
@@ -1,5 +1,6 @@
-use synoptic::{Highlighter, TokOpt};
-use lliw::Fg;
+use synoptic::Highlighter;
+use synoptic::export::{ansi, ColourDepth};
+use synoptic::theme::Theme;
 
 // Let's use some demonstration code
 pub static CODE: &str = "\
@@ -56,41 +57,21 @@ fn main() {
 
     // Now let's run the highlighter on the example code
     // The run method takes a vector of strings (for each line)
-    let code = CODE
+    let code: Vec<String> = CODE
         .split('\n')
         .map(|line| line.to_string())
         .collect();
     // Now we're ready to go
     h.run(&code);
 
+    // Let's pick a theme, which says how each kind of token should look
+    let theme = Theme::dark();
+
     // Let's render the output
     for (line_number, line) in code.iter().enumerate() {
         // Line returns tokens for the corresponding line
-        for token in h.line(line_number, &line) {
-            // Tokens can either require highlighting or not require highlighting
-            match token {
-                // This is some text that needs to be highlighted
-                TokOpt::Some(text, kind) => print!("{}{text}{}", colour(&kind), Fg::Reset),
-                // This is just normal text with no highlighting
-                TokOpt::None(text) => print!("{text}"),
-            }
-        }
-        // Insert a newline at the end of every line
-        println!();
-    }
-}
-
-fn colour(name: &str) -> Fg {
-    // This function will take in the function name
-    // And it will output the correct foreground colour
-    match name {
-        "comment" => Fg::LightBlack,
-        "digit" => Fg::Purple,
-        "string" => Fg::Green,
-        "macros" => Fg::LightPurple,
-        "boolean" => Fg::Blue,
-        "keyword" => Fg::Yellow,
-        "function" => Fg::Red,
-        _ => panic!("unknown token name"),
+        // Tokens can either require highlighting or not require highlighting,
+        // the ansi exporter will colour in tokens that need highlighting for us
+        println!("{}", ansi(&h.line(line_number, line), &theme, ColourDepth::detect()));
     }
 }
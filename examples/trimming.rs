@@ -1,4 +1,4 @@
-use synoptic::{Highlighter, TokOpt, trim};
+use synoptic::{TokOpt, trim_fit};
 use lliw::Fg;
 
 pub static CODE: &str = r#"
@@ -9,18 +9,18 @@ hello world!
 
 fn main() {
     let mut h = synoptic::from_extension("diff", 4).unwrap();
-    let mut code: Vec<String> = CODE.split('\n').map(|x| x.to_string()).collect();
+    let code: Vec<String> = CODE.split('\n').map(|x| x.to_string()).collect();
     h.run(&code);
     // Trim and render
     for length in 0..30 {
         for (line_no, line) in code.iter().enumerate() {
-            let tokens = h.line(line_no, &line);
-            let tokens = trim(&tokens, 0, length, 4);
+            let tokens = h.line(line_no, line);
+            let tokens = trim_fit(&tokens, 0, length, 4);
             for token in &tokens {
                 // Tokens can either require highlighting or not require highlighting
                 match token {
                     // This is some text that needs to be highlighted
-                    TokOpt::Some(text, kind) => print!("{}{text}{}", colour(&kind), Fg::Reset),
+                    TokOpt::Some(text, kind) => print!("{}{text}{}", colour(kind), Fg::Reset),
                     // This is just normal text with no highlighting
                     TokOpt::None(text) => print!("{text}"),
                 }
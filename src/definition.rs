@@ -0,0 +1,303 @@
+//! Loading syntax definitions from files, enabled with the `toml` and `yaml` features.
+//!
+//! This allows highlighting rules to be written as data rather than against the Rust API,
+//! so editors can ship syntax files that their users are able to edit.
+//! A definition written in TOML looks like this:
+//!
+//! ```toml
+//! tab_width = 4
+//! word_chars = ["?"]
+//! brackets = [["(", ")"], ["[", "]"], ["{", "}"], ["<", ">"]]
+//! indent = ['\{\s*$']
+//! outdent = ['^\s*\}']
+//!
+//! [[keyword]]
+//! name = "keyword"
+//! words = ["fn", "let", "pub"]
+//!
+//! [[keyword]]
+//! name = "digit"
+//! pattern = '\b\d+\b'
+//!
+//! [[bounded]]
+//! name = "string"
+//! start = '"'
+//! end = '"'
+//! escapable = true
+//! interpolation = { start = '\{', end = '\}' }
+//!
+//! [[fold]]
+//! name = "block"
+//! start = '\{'
+//! end = '\}'
+//! ```
+//!
+//! Keyword rules take either a regex `pattern` or a list of `words`, and can span up to
+//! a number of `lines` (see [Grammar::multiline_keyword](crate::Grammar::multiline_keyword)).
+//! Bounded rules can have an `interpolation` table, an `open` regex to make them balanced
+//! (see [Grammar::bounded_balanced](crate::Grammar::bounded_balanced)), be `nested`
+//! (see [Grammar::bounded_nested](crate::Grammar::bounded_nested)), or `capture` text in the
+//! start for the end to refer back to (see [Grammar::bounded_capture](crate::Grammar::bounded_capture)).
+//! YAML definitions have exactly the same structure.
+
+use crate::{Highlighter, Pat, SyntaxError};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A declarative description of how to highlight a language
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyntaxDefinition {
+    /// How many spaces a tab character should be
+    #[serde(default = "default_tab_width")]
+    pub tab_width: usize,
+    /// Characters, other than letters, digits and underscores, that can make up an identifier
+    #[serde(default)]
+    pub word_chars: Vec<char>,
+    /// Pairs of opening and closing brackets, when different to the default of (), [] and {}
+    pub brackets: Option<Vec<(char, char)>>,
+    /// Patterns for lines after which the indentation goes up a level,
+    /// see [Grammar::indent](crate::Grammar::indent)
+    #[serde(default)]
+    pub indent: Vec<String>,
+    /// Patterns for lines that are indented a level less than the line before,
+    /// see [Grammar::outdent](crate::Grammar::outdent)
+    #[serde(default)]
+    pub outdent: Vec<String>,
+    /// The keyword rules, in the order they are registered
+    #[serde(default, rename = "keyword")]
+    pub keywords: Vec<KeywordRule>,
+    /// The bounded rules, in the order they are registered
+    #[serde(default)]
+    pub bounded: Vec<BoundedRule>,
+    /// The fold rules, see [Grammar::fold](crate::Grammar::fold)
+    #[serde(default)]
+    pub fold: Vec<FoldRule>,
+    /// The indentation scope rules, see [Grammar::indent_scope](crate::Grammar::indent_scope)
+    #[serde(default)]
+    pub scope: Vec<ScopeRule>,
+}
+
+/// A keyword rule within a [SyntaxDefinition]
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeywordRule {
+    /// The name of the token
+    pub name: String,
+    /// A regex for the keyword
+    pub pattern: Option<String>,
+    /// A list of whole words that make up the keyword (an alternative to a pattern)
+    #[serde(default)]
+    pub words: Vec<String>,
+    /// The number of lines that a match can span, for keywords that span more than one line
+    /// (see [Grammar::multiline_keyword](crate::Grammar::multiline_keyword))
+    pub lines: Option<usize>,
+}
+
+/// A bounded rule within a [SyntaxDefinition]
+#[derive(Debug, Clone, Deserialize)]
+pub struct BoundedRule {
+    /// The name of the token
+    pub name: String,
+    /// A regex for the start of the token
+    pub start: String,
+    /// A regex for the end of the token
+    pub end: String,
+    /// Whether the token can be escaped with a backslash
+    #[serde(default)]
+    pub escapable: bool,
+    /// Markers for interpolation within the token
+    pub interpolation: Option<Interpolation>,
+    /// A regex that must be balanced by an end within the token
+    pub open: Option<String>,
+    /// Whether the token can be nested within itself
+    #[serde(default)]
+    pub nested: bool,
+    /// Whether the end refers back to capture groups of the start (with \1 to \9), e.g. heredocs
+    #[serde(default)]
+    pub capture: bool,
+}
+
+/// A pair of fold markers within a [SyntaxDefinition]
+#[derive(Debug, Clone, Deserialize)]
+pub struct FoldRule {
+    /// The name of the region
+    pub name: String,
+    /// A regex for the start of the region
+    pub start: String,
+    /// A regex for the end of the region
+    pub end: String,
+}
+
+/// An indentation scope rule within a [SyntaxDefinition]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScopeRule {
+    /// The name of the scope
+    pub name: String,
+    /// A regex for the line that starts the scope
+    pub pattern: String,
+}
+
+/// The start and end markers of interpolation within a [BoundedRule]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Interpolation {
+    /// A regex for the start of interpolation
+    pub start: String,
+    /// A regex for the end of interpolation
+    pub end: String,
+}
+
+/// The formats that a syntax definition can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxFormat {
+    /// A TOML document
+    #[cfg(feature = "toml")]
+    Toml,
+    /// A YAML document
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl SyntaxFormat {
+    /// Work out the format of a syntax definition file from its extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            #[cfg(feature = "toml")]
+            "toml" => Some(Self::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// An error encountered while loading a syntax definition
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file couldn't be read
+    Io(std::io::Error),
+    /// The format of the file couldn't be worked out from its extension
+    UnknownFormat,
+    /// The definition isn't valid TOML, or doesn't have the expected structure
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The definition isn't valid YAML, or doesn't have the expected structure
+    #[cfg(feature = "yaml")]
+    Yaml(serde_yaml::Error),
+    /// A rule is missing a pattern, or has options that can't be used together
+    InvalidRule(String),
+    /// A rule contains an invalid regex
+    Syntax(SyntaxError),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read syntax definition: {err}"),
+            Self::UnknownFormat => write!(f, "unknown syntax definition format"),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => write!(f, "invalid syntax definition: {err}"),
+            #[cfg(feature = "yaml")]
+            Self::Yaml(err) => write!(f, "invalid syntax definition: {err}"),
+            Self::InvalidRule(name) => write!(f, "invalid rule for token {name:?}"),
+            Self::Syntax(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => Some(err),
+            #[cfg(feature = "yaml")]
+            Self::Yaml(err) => Some(err),
+            Self::Syntax(err) => Some(err),
+            Self::UnknownFormat | Self::InvalidRule(_) => None,
+        }
+    }
+}
+
+impl From<SyntaxError> for LoadError {
+    fn from(err: SyntaxError) -> Self {
+        Self::Syntax(err)
+    }
+}
+
+fn default_tab_width() -> usize {
+    4
+}
+
+impl SyntaxDefinition {
+    /// Parse a syntax definition
+    pub fn parse(source: &str, format: SyntaxFormat) -> Result<Self, LoadError> {
+        match format {
+            #[cfg(feature = "toml")]
+            SyntaxFormat::Toml => toml::from_str(source).map_err(LoadError::Toml),
+            #[cfg(feature = "yaml")]
+            SyntaxFormat::Yaml => serde_yaml::from_str(source).map_err(LoadError::Yaml),
+        }
+    }
+
+    /// Create a highlighter that uses the rules in this definition
+    pub fn to_highlighter(&self) -> Result<Highlighter, LoadError> {
+        let mut result = Highlighter::new(self.tab_width);
+        result.grammar_mut().word_chars = self.word_chars.clone();
+        if let Some(brackets) = &self.brackets {
+            result.grammar_mut().brackets = brackets.clone();
+        }
+        for rule in &self.keywords {
+            let pattern = match (&rule.pattern, rule.words.is_empty()) {
+                (Some(pattern), true) => pattern.clone(),
+                (None, false) => Pat::words(&rule.words).as_str().to_string(),
+                _ => return Err(LoadError::InvalidRule(rule.name.clone())),
+            };
+            match rule.lines {
+                Some(lines) => result.try_multiline_keyword(&rule.name, &pattern, lines)?,
+                None => result.try_keyword(&rule.name, &pattern)?,
+            }
+        }
+        for rule in &self.bounded {
+            let (name, start, end) = (rule.name.as_str(), rule.start.as_str(), rule.end.as_str());
+            match (&rule.interpolation, &rule.open, rule.nested, rule.capture) {
+                (None, None, false, false) => result.try_bounded(name, start, end, rule.escapable)?,
+                (None, None, true, false) => result.try_bounded_nested(name, start, end, rule.escapable)?,
+                (None, None, false, true) => result.try_bounded_capture(name, start, end, rule.escapable)?,
+                (Some(i), None, false, false) => result.try_bounded_interp(name, start, end, &i.start, &i.end, rule.escapable)?,
+                (None, Some(open), false, false) => result.try_bounded_balanced(name, start, end, open, rule.escapable)?,
+                _ => return Err(LoadError::InvalidRule(rule.name.clone())),
+            }
+        }
+        for rule in &self.fold {
+            result.try_fold(&rule.name, &rule.start, &rule.end)?;
+        }
+        for rule in &self.scope {
+            result.try_indent_scope(&rule.name, &rule.pattern)?;
+        }
+        for exp in &self.indent {
+            result.try_indent(exp)?;
+        }
+        for exp in &self.outdent {
+            result.try_outdent(exp)?;
+        }
+        Ok(result)
+    }
+}
+
+impl Highlighter {
+    /// Create a highlighter from a syntax definition, see the [definition module](crate::definition)
+    pub fn from_str(source: &str, format: SyntaxFormat) -> Result<Self, LoadError> {
+        SyntaxDefinition::parse(source, format)?.to_highlighter()
+    }
+
+    /// Create a highlighter from a syntax definition file,
+    /// with the format worked out from the file's extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let format = path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(SyntaxFormat::from_extension)
+            .ok_or(LoadError::UnknownFormat)?;
+        let source = std::fs::read_to_string(path).map_err(LoadError::Io)?;
+        Self::from_str(&source, format)
+    }
+}
@@ -0,0 +1,412 @@
+//! Turning highlighted tokens into formats that can be displayed directly,
+//! using a [Theme] to decide how each token looks.
+//! Documents can also be encoded as semantic tokens for language servers, see [semantic_tokens].
+//!
+//! ```
+//! # use synoptic::{from_extension, export::{ansi, html, ColourDepth}, theme::Theme};
+//! let mut h = from_extension("rs", 4).unwrap();
+//! let code = vec!["fn main() {}".to_string()];
+//! h.run(&code);
+//! let theme = Theme::dark();
+//! println!("{}", ansi(&h.line(0, &code[0]), &theme, ColourDepth::Ansi256));
+//! println!("{}", html(&h.line(0, &code[0]), &theme));
+//! ```
+
+use crate::theme::{Style, Theme};
+pub use crate::theme::Colour;
+use crate::{Highlighter, TokOpt};
+use std::collections::HashMap;
+
+/// How many colours the output can use.
+/// Colours are downgraded to the closest colour available at this depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColourDepth {
+    /// 24-bit colour
+    #[default]
+    TrueColour,
+    /// The 256-colour palette
+    Ansi256,
+    /// The 16 basic terminal colours
+    Ansi16,
+}
+
+impl ColourDepth {
+    /// Work out how many colours the terminal supports from the COLORTERM and TERM variables
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Self::TrueColour
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+}
+
+/// The RGB values of the 16 basic terminal colours (using the xterm defaults)
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+/// The levels used by each component within the 6x6x6 colour cube of the 256-colour palette
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl Colour {
+    /// Convert this colour into a 24-bit colour
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Self::Rgb(r, g, b) => (r, g, b),
+            Self::Ansi16(n) => ANSI16[n as usize % 16],
+            Self::Ansi256(n @ 0..=15) => ANSI16[n as usize],
+            Self::Ansi256(n @ 16..=231) => {
+                let n = n as usize - 16;
+                (CUBE[n / 36], CUBE[n / 6 % 6], CUBE[n % 6])
+            }
+            Self::Ansi256(n) => {
+                let level = 8 + 10 * (n - 232);
+                (level, level, level)
+            }
+        }
+    }
+
+    /// Convert this colour into the closest colour in the 256-colour palette
+    pub fn to_ansi256(self) -> u8 {
+        let (r, g, b) = match self {
+            Self::Ansi256(n) => return n,
+            Self::Ansi16(n) => return n % 16,
+            Self::Rgb(r, g, b) => (r, g, b),
+        };
+        // Find the closest colour in the cube and the closest grey, and pick the better one
+        let level = |c: u8| CUBE.iter().enumerate().min_by_key(|(_, l)| l.abs_diff(c)).unwrap().0;
+        let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+        let average = (r as usize + g as usize + b as usize) / 3;
+        let grey = 232 + (average.saturating_sub(3) / 10).min(23);
+        [cube as u8, grey as u8]
+            .into_iter()
+            .min_by_key(|n| distance(Self::Ansi256(*n).to_rgb(), (r, g, b)))
+            .unwrap()
+    }
+
+    /// Convert this colour into the closest of the 16 basic terminal colours
+    pub fn to_ansi16(self) -> u8 {
+        if let Self::Ansi16(n) = self { return n % 16; }
+        let rgb = self.to_rgb();
+        (0..16).min_by_key(|n| distance(ANSI16[*n as usize], rgb)).unwrap()
+    }
+
+    /// The escape sequence to set the foreground to this colour, at a certain colour depth
+    pub fn ansi_fg(self, depth: ColourDepth) -> String {
+        format!("\x1b[{}m", self.sgr(depth, false))
+    }
+
+    /// The escape sequence to set the background to this colour, at a certain colour depth
+    pub fn ansi_bg(self, depth: ColourDepth) -> String {
+        format!("\x1b[{}m", self.sgr(depth, true))
+    }
+
+    /// The parameters of the escape sequence that selects this colour
+    fn sgr(self, depth: ColourDepth, bg: bool) -> String {
+        let offset = if bg { 10 } else { 0 };
+        match (self, depth) {
+            (Self::Rgb(r, g, b), ColourDepth::TrueColour) => format!("{};2;{r};{g};{b}", 38 + offset),
+            (Self::Ansi16(_), _) | (_, ColourDepth::Ansi16) => match self.to_ansi16() {
+                n @ 0..=7 => format!("{}", 30 + offset + n),
+                n => format!("{}", 90 + offset + n - 8),
+            },
+            _ => format!("{};5;{}", 38 + offset, self.to_ansi256()),
+        }
+    }
+
+    /// This colour as a CSS hex colour
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.to_rgb();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// The squared distance between two colours
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// The escape sequence that applies a style (empty if the style is plain)
+fn ansi_style(style: &Style, depth: ColourDepth) -> String {
+    let mut params = vec![];
+    if style.bold { params.push("1".to_string()); }
+    if style.italic { params.push("3".to_string()); }
+    if style.underline { params.push("4".to_string()); }
+    if let Some(fg) = style.fg { params.push(fg.sgr(depth, false)); }
+    if let Some(bg) = style.bg { params.push(bg.sgr(depth, true)); }
+    if params.is_empty() { String::new() } else { format!("\x1b[{}m", params.join(";")) }
+}
+
+/// Render the tokens of a line into a string that can be printed to a terminal,
+/// styling each token according to a theme.
+pub fn ansi(tokens: &[TokOpt], theme: &Theme, depth: ColourDepth) -> String {
+    let mut result = String::new();
+    for token in tokens {
+        let style = match token {
+            TokOpt::Some(_, kind) => theme.style(kind),
+            TokOpt::None(_) => &theme.default,
+        };
+        if style.is_plain() {
+            result.push_str(token.text());
+        } else {
+            result.push_str(&ansi_style(style, depth));
+            result.push_str(token.text());
+            result.push_str("\x1b[0m");
+        }
+    }
+    result
+}
+
+/// Render the tokens of a line into HTML, with each styled token wrapped in a span
+/// that has an inline style according to a theme. Text is escaped for you.
+pub fn html(tokens: &[TokOpt], theme: &Theme) -> String {
+    let mut result = String::new();
+    for token in tokens {
+        let style = match token {
+            TokOpt::Some(_, kind) => theme.style(kind),
+            TokOpt::None(_) => &theme.default,
+        };
+        let text = escape_html(token.text());
+        if style.is_plain() {
+            result.push_str(&text);
+        } else {
+            result.push_str(&format!("<span style=\"{}\">{text}</span>", css(style)));
+        }
+    }
+    result
+}
+
+/// Turn a style into inline CSS
+fn css(style: &Style) -> String {
+    let mut rules = vec![];
+    if let Some(fg) = style.fg { rules.push(format!("color:{}", fg.to_hex())); }
+    if let Some(bg) = style.bg { rules.push(format!("background-color:{}", bg.to_hex())); }
+    if style.bold { rules.push("font-weight:bold".to_string()); }
+    if style.italic { rules.push("font-style:italic".to_string()); }
+    if style.underline { rules.push("text-decoration:underline".to_string()); }
+    rules.join(";")
+}
+
+/// Escape the characters that have special meaning in HTML
+fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// How positions within a line are counted in LSP messages,
+/// which is agreed on with the client through its position encodings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// UTF-8 code units (bytes)
+    Utf8,
+    /// UTF-16 code units, which is what LSP uses unless the client says otherwise
+    #[default]
+    Utf16,
+    /// Unicode code points (characters)
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// The length of some text in this encoding
+    fn len(self, text: &str) -> usize {
+        match self {
+            Self::Utf8 => text.len(),
+            Self::Utf16 => text.encode_utf16().count(),
+            Self::Utf32 => text.chars().count(),
+        }
+    }
+}
+
+/// A mapping from token names to the token types and modifiers of an LSP semantic tokens legend,
+/// see [semantic_tokens]. As with themes, names fall back to less specific names,
+/// so `string.interpolated` uses the mapping for `string` if it doesn't have its own.
+/// Tokens with names that aren't mapped are left out.
+///
+/// Token types and modifiers are added to the legend as they are used in the mapping,
+/// so the legend is ready to be sent to the client in the server capabilities.
+/// A legend can have at most 32 modifiers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SemanticLegend {
+    /// The token types of the legend, in the order they are given to the client
+    pub token_types: Vec<String>,
+    /// The token modifiers of the legend, in the order they are given to the client
+    pub token_modifiers: Vec<String>,
+    /// How positions are counted
+    pub encoding: PositionEncoding,
+    /// For each token name, the index of its type and the bits of its modifiers
+    mapping: HashMap<String, (u32, u32)>,
+}
+
+impl SemanticLegend {
+    /// Creates a new, empty legend
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A legend that maps the names used by the built-in highlighters onto
+    /// the standard token types and modifiers of LSP
+    pub fn standard() -> Self {
+        Self::new()
+            .with("comment", "comment", &[])
+            .with("string", "string", &[])
+            .with("character", "string", &[])
+            .with("digit", "number", &[])
+            .with("boolean", "keyword", &[])
+            .with("keyword", "keyword", &[])
+            .with("operator", "operator", &[])
+            .with("function", "function", &[])
+            .with("builtin", "function", &["defaultLibrary"])
+            .with("macro", "macro", &[])
+            .with("macros", "macro", &[])
+            .with("struct", "struct", &[])
+            .with("type", "type", &[])
+            .with("namespace", "namespace", &[])
+            .with("attribute", "decorator", &[])
+            .with("variable", "variable", &[])
+            .with("field", "property", &[])
+            .with("key", "property", &[])
+            .with("regex", "regexp", &[])
+    }
+
+    /// Map a token name onto a token type and modifiers.
+    /// This will panic if the legend would end up with more than 32 modifiers.
+    pub fn set<S: Into<String>>(&mut self, name: S, token_type: &str, modifiers: &[&str]) {
+        self.try_set(name, token_type, modifiers).expect("Too many modifiers!");
+    }
+
+    /// Map a token name onto a token type and modifiers, like [SemanticLegend::set],
+    /// but returns an error instead of panicking if there would be more than 32 modifiers,
+    /// in which case the legend is left as it was
+    /// ```
+    /// # use synoptic::export::SemanticLegend;
+    /// let mut legend = SemanticLegend::new();
+    /// let names: Vec<String> = (0..33).map(|n| format!("m{n}")).collect();
+    /// let modifiers: Vec<&str> = names.iter().map(String::as_str).collect();
+    /// assert!(legend.try_set("keyword", "keyword", &modifiers[..32]).is_ok());
+    /// assert!(legend.try_set("comment", "comment", &modifiers[32..]).is_err());
+    /// assert_eq!(legend.token_modifiers.len(), 32);
+    /// assert_eq!(legend.lookup("comment"), None);
+    /// ```
+    pub fn try_set<S: Into<String>>(&mut self, name: S, token_type: &str, modifiers: &[&str]) -> Result<(), TooManyModifiers> {
+        let mut added: Vec<&str> = vec![];
+        for modifier in modifiers {
+            if !self.token_modifiers.iter().any(|m| m == modifier) && !added.contains(modifier) {
+                added.push(modifier);
+            }
+        }
+        if self.token_modifiers.len() + added.len() > 32 {
+            return Err(TooManyModifiers);
+        }
+        let token_type = index_of(&mut self.token_types, token_type);
+        let modifiers = modifiers.iter()
+            .map(|modifier| index_of(&mut self.token_modifiers, modifier))
+            .fold(0, |bits, idx| bits | 1 << idx);
+        self.mapping.insert(name.into(), (token_type, modifiers));
+        Ok(())
+    }
+
+    /// Map a token name onto a token type and modifiers, for use when building up a legend
+    pub fn with<S: Into<String>>(mut self, name: S, token_type: &str, modifiers: &[&str]) -> Self {
+        self.set(name, token_type, modifiers);
+        self
+    }
+
+    /// Use a different position encoding
+    pub fn with_encoding(mut self, encoding: PositionEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Find the index of the token type and the bits of the modifiers for a token name,
+    /// falling back to less specific names
+    pub fn lookup(&self, name: &str) -> Option<(u32, u32)> {
+        let mut name = name;
+        loop {
+            if let Some(found) = self.mapping.get(name) {
+                return Some(*found);
+            }
+            name = &name[..name.rfind('.')?];
+        }
+    }
+}
+
+/// An error returned when a [SemanticLegend] would need more than 32 modifiers,
+/// as the modifiers of a token are sent to the client as the bits of a 32 bit number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyModifiers;
+
+impl std::fmt::Display for TooManyModifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a semantic token legend can't have more than 32 modifiers")
+    }
+}
+
+impl std::error::Error for TooManyModifiers {}
+
+/// Find where a name is in a list, adding it to the end if it isn't there
+fn index_of(names: &mut Vec<String>, name: &str) -> u32 {
+    let idx = names.iter().position(|n| n == name).unwrap_or_else(|| {
+        names.push(name.to_string());
+        names.len() - 1
+    });
+    idx as u32
+}
+
+/// Encode the tokens of a document as the data of LSP semantic tokens, where each token is
+/// five numbers: the line relative to the previous token, the start (relative to the previous
+/// token if it is on the same line), the length, the token type and the bits of its modifiers.
+/// Tokens never span more than one line, and the highlighter must have been run on the lines.
+/// ```
+/// # use synoptic::{Highlighter, export::{semantic_tokens, SemanticLegend}};
+/// let mut h = Highlighter::new(4);
+/// h.keyword("keyword", r"\blet\b");
+/// h.keyword("comment", "//.*$");
+/// let lines = ["let x; // é", "", "  let y;"];
+/// h.run(&lines.map(String::from));
+/// let legend = SemanticLegend::new()
+///     .with("keyword", "keyword", &[])
+///     .with("comment", "comment", &["documentation"]);
+/// assert_eq!(legend.token_types, vec!["keyword", "comment"]);
+/// assert_eq!(semantic_tokens(&h, &lines, &legend), vec![
+///     0, 0, 3, 0, 0,
+///     0, 7, 4, 1, 1,
+///     2, 2, 3, 0, 0,
+/// ]);
+/// ```
+pub fn semantic_tokens<S: AsRef<str>>(highlighter: &Highlighter, lines: &[S], legend: &SemanticLegend) -> Vec<u32> {
+    let mut result = vec![];
+    let (mut last_y, mut last_start) = (0, 0);
+    for (y, line) in lines.iter().enumerate().take(highlighter.highlighted_lines()) {
+        let mut at = 0;
+        for token in highlighter.line_refs(y, line.as_ref()) {
+            let len = legend.encoding.len(token.text);
+            let found = token.name.and_then(|name| legend.lookup(name));
+            if let (Some((token_type, modifiers)), true) = (found, len > 0) {
+                let start = if y == last_y { at - last_start } else { at };
+                result.extend([(y - last_y) as u32, start as u32, len as u32, token_type, modifiers]);
+                (last_y, last_start) = (y, at);
+            }
+            at += len;
+        }
+    }
+    result
+}
@@ -1,22 +1,47 @@
 use unicode_width::UnicodeWidthStr;
+use unicode_segmentation::UnicodeSegmentation;
 pub use regex::Regex;
 use std::collections::HashMap;
 use std::ops::Range;
-use std::cmp::Ordering;
 use char_index::IndexedChars;
 use nohash_hasher::NoHashHasher;
-use std::hash::BuildHasherDefault;
-use std::sync::OnceLock;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+pub mod pattern;
+pub use pattern::Pat;
+pub mod multi;
+pub use multi::MultiHighlighter;
+pub mod registry;
+pub use registry::{DetectError, LanguageInfo, Registry};
+#[cfg(feature = "watch")]
+pub use registry::GrammarWatcher;
+pub mod theme;
+pub mod export;
+
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub mod definition;
+#[cfg(feature = "yaml")]
+pub mod sublime;
 
 /// Represents a point in a 2d space
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Loc {
-    y: usize,
-    x: usize,
+    /// The line number
+    pub y: usize,
+    /// The index within the line
+    pub x: usize,
 }
 
 /// A definition of an Atom
 /// See [Atom] for more information
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct AtomDef {
     /// Name of the atom
@@ -26,10 +51,52 @@ pub struct AtomDef {
     /// The corresponding bounded token definition
     tok: Option<usize>,
     /// The regex expression that defines this atom
-    exp: Regex,
+    exp: LazyRegex,
+    /// The interned name of the atom
+    id: TokenKind,
+    /// The embedded region this atom definition applies within, if it came from an embedded grammar
+    region: Option<usize>,
+}
+
+/// An interned token name, which is cheap to copy and compare.
+/// Use [Highlighter::kind_name] to get the name back
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct TokenKind(pub u32);
+
+/// A regular expression that remembers its source, and is compiled the first time it is used.
+/// Highlighters that are deserialized don't need to compile their regexes up front
+#[derive(Debug, Clone)]
+struct LazyRegex {
+    source: String,
+    compiled: OnceLock<Regex>,
+}
+
+impl LazyRegex {
+    fn get(&self) -> &Regex {
+        self.compiled.get_or_init(|| Regex::new(&self.source).expect("Invalid regex!"))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for LazyRegex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LazyRegex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        // Only check the syntax here, so compiling the regex can still wait until it is used
+        regex_syntax::Parser::new().parse(&source).map_err(serde::de::Error::custom)?;
+        Ok(Self { source, compiled: OnceLock::new() })
+    }
 }
 
 /// The kind of atom being represented
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum AtomKind {
     /// This is the start atom of a token, for example /* for a multiline comment
@@ -46,12 +113,30 @@ pub enum AtomKind {
     InterpolateStart,
     /// This is an end marker for interpolation
     InterpolateEnd,
+    /// This opens a nested level within a bounded token, which must be balanced by an end atom
+    /// before the token can end, for example [ within a #[...] attribute in Rust
+    Nest,
+    /// This starts a region that can be folded, for example { in Rust
+    FoldStart,
+    /// This ends a region that can be folded, for example } in Rust
+    FoldEnd,
+    /// This starts a scope that covers the lines after it that are indented further,
+    /// for example def in Python
+    Scope,
+    /// This is the indentation at the start of a line,
+    /// which is only present in grammars that have indentation scopes
+    Indent,
+    /// This is a keyword that can span up to the given number of lines
+    /// (see [Grammar::multiline_keyword]). Only atom definitions are of this kind,
+    /// the atoms found are keywords, or starts and ends when they span more than one line
+    Multiline(usize),
 }
 
 /// An atom is a portion of text within a document that is significant. 
 /// An atom only covers one line.
 /// Atoms cover keywords as well as start and end indicators for bounded tokens
 /// E.g., in a string, the atoms would be the starting " and the ending "
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Atom {
     /// Name of the atom
@@ -64,19 +149,35 @@ pub struct Atom {
     x: Range<usize>,
     /// Whether or not there is a preceding backslash
     backslashed: bool,
+    /// The interned name of the atom
+    id: TokenKind,
+    /// The embedded region this atom applies within, if it came from an embedded grammar
+    region: Option<usize>,
+    /// The text captured by the atom, for the start and end of tokens whose end depends on
+    /// their start (an end only closes a token if it captured the same text as the start)
+    captures: Vec<String>,
 }
 
 /// Definition for a bounded token, these are tokens that can cover multiple lines.
 /// Things like multiline comments and strings are examples of this.
 /// They work well for buffering files where you are unaware of where the end indicator may be as
 /// it occurs further down in the file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct BoundedDef {
     /// Whether or not this token can be escaped
     escapable: bool,
+    /// Whether or not this token can be nested within itself
+    nestable: bool,
+    /// Whether this is a region that is highlighted by an embedded grammar, see [Grammar::embed]
+    region: bool,
+    /// For tokens whose end depends on their start (see [Grammar::bounded_capture]),
+    /// the capture group of the start that each back-reference in the end refers to
+    backrefs: Option<Vec<usize>>,
 }
 
 /// This is a TokenRef, which contains detailed information on what a token is
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenRef {
     /// Keyword tokens
@@ -98,6 +199,7 @@ pub enum TokenRef {
 }
 
 /// This is an enum for representing tokens.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub enum TokOpt {
     /// The Some variant represents a token being present in the format Some(TEXT, NAME).
@@ -129,1789 +231,5922 @@ impl TokOpt {
         text
     }
 
-    /// This will remove the first character from the end of this token
-    pub fn nibble_front(&mut self, tab_width: usize) -> Option<char> {
-        let (TokOpt::Some(ref mut text, _) | TokOpt::None(ref mut text)) = self;
-        let ch = text.chars().nth(0)?;
-        text.remove(0);
-        let wid = width(&ch.to_string(), tab_width);
+    /// This will remove the first grapheme cluster (user-perceived character) from this token.
+    /// Wide clusters are replaced with padding so that the width only shrinks by one
+    pub fn nibble_front(&mut self, tab_width: usize) -> Option<String> {
+        let text = self.text_mut();
+        let cluster = text.graphemes(true).next()?.to_string();
+        text.replace_range(..cluster.len(), "");
+        let wid = width(&cluster, tab_width);
         if wid > 1 {
-            *text = format!("{}{text}", " ".repeat(wid.saturating_sub(1)));
+            text.insert_str(0, &" ".repeat(wid - 1));
         }
-        Some(ch)
+        Some(cluster)
     }
 
-    /// This will remove the last character from the end of this token
-    pub fn nibble_back(&mut self, tab_width: usize) -> Option<char> {
-        let (TokOpt::Some(ref mut text, _) | TokOpt::None(ref mut text)) = self;
-        let ch = text.chars().last()?;
-        text.pop();
-        let wid = width(&ch.to_string(), tab_width);
+    /// This will remove the last grapheme cluster (user-perceived character) from this token.
+    /// Wide clusters are replaced with padding so that the width only shrinks by one
+    pub fn nibble_back(&mut self, tab_width: usize) -> Option<String> {
+        let text = self.text_mut();
+        let cluster = text.graphemes(true).next_back()?.to_string();
+        text.truncate(text.len() - cluster.len());
+        let wid = width(&cluster, tab_width);
         if wid > 1 {
-            *text = format!("{text}{}", " ".repeat(wid.saturating_sub(1)));
+            text.push_str(&" ".repeat(wid - 1));
         }
-        Some(ch)
+        Some(cluster)
     }
 
+    /// Remove the first `idx` display columns from the start of this token.
+    /// If a wide character is cut in half, the remaining half is replaced with padding
     pub fn skip(&mut self, idx: usize, tab_width: usize) {
-        let mut at_disp = 0;
-        let mut at_char = 0;
-        let mut padding = 0;
-        for i in self.text().chars() {
-            match at_disp.cmp(&idx) {
-                // Exactly at index, skip up to this point
-                Ordering::Equal => break,
-                // We skipped too much, indicating that padding is needed
-                Ordering::Greater => {
-                    padding = at_disp - idx;
-                    break;
-                }
-                _ => {
-                    at_disp += width(&i.to_string(), tab_width);
-                    at_char += 1;
-                }
-            }
-        }
-        *self.text_mut() = " ".repeat(padding) + &self.text().chars().skip(at_char).collect::<String>();
+        let map = ColumnMap::new(self.text(), tab_width);
+        let at_char = map.col_to_char(idx);
+        let start = map.char_to_col(at_char);
+        let (skip, padding) = if at_char >= map.len() || start == idx {
+            (at_char, 0)
+        } else {
+            // We're part way through a character, skip it entirely and pad the rest
+            (map.cluster(at_char).end, start + map.col_width_of_char(at_char) - idx)
+        };
+        *self.text_mut() = " ".repeat(padding) + &self.text().chars().skip(skip).collect::<String>();
     }
 
+    /// Keep only the first `idx` display columns of this token.
+    /// If a wide character is cut in half, the remaining half is replaced with padding
     pub fn take(&mut self, idx: usize, tab_width: usize) {
-        let mut at_disp = 0;
-        let mut at_char = 0;
-        let mut padding = 0;
-        for i in self.text().chars() {
-            match at_disp.cmp(&idx) {
-                // Exactly at index, take up to this point
-                Ordering::Equal => break,
-                // We took too much, indicating that padding is needed
-                Ordering::Greater => {
-                    padding = at_disp - idx;
-                    at_char -= 1;
-                    break;
-                }
-                _ => {
-                    at_disp += width(&i.to_string(), tab_width);
-                    at_char += 1;
-                }
-            }
-        }
+        let map = ColumnMap::new(self.text(), tab_width);
+        let at_char = map.col_to_char(idx);
+        let start = map.char_to_col(at_char);
+        // If we're part way through a character, leave it out and pad up to idx
+        let padding = if at_char >= map.len() { 0 } else { idx - start };
         *self.text_mut() = self.text().chars().take(at_char).collect::<String>() + &" ".repeat(padding);
     }
 }
 
-/// This is the main struct that will highlight your document
+/// The severity of a diagnostic, ordered from most to least severe
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something that is definitely wrong
+    Error,
+    /// Something that is likely to be wrong
+    Warning,
+    /// Something worth knowing about
+    Information,
+    /// A suggestion
+    Hint,
+}
+
+/// Extra information that is attached to a token, on top of its syntax highlighting.
+/// A token can be, for example, both a "string" and underlined due to an error
+#[derive(Debug, Clone, PartialEq)]
+pub enum Modifier {
+    /// This token is covered by a diagnostic
+    Diagnostic(Severity),
+    /// This token is covered by a match of an overlay with this name, see [Highlighter::overlay]
+    Overlay(String),
+}
+
+/// The rules that define how a language is highlighted.
+///
+/// A grammar is shared between highlighters through an [Arc], so many open documents
+/// of the same language can use one set of compiled regular expressions.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
-pub struct Highlighter {
-    /// The list of atoms, encapsulated within an inner vector for atoms on the same line
-    pub atoms: Vec<Vec<Atom>>,
+pub struct Grammar {
     /// The list of atom definitions to be used at atomization
     pub atom_def: Vec<AtomDef>,
     /// The list of bounded definitions to be used at tokenization
     pub bounded_def: Vec<BoundedDef>,
-    /// A reference to what tokens lie on which line numbers
-    pub line_ref: Vec<Vec<usize>>,
-    /// A list of the resulting tokens generated from run and append
-    pub tokens: Vec<TokenRef>,
-    /// How many spaces a tab character should be
-    pub tab_width: usize,
-    /// For purposes of tokenization
-    tokenize_state: Option<usize>,
-    tokenize_interp: bool,
+    /// Names of tokens that contain human-readable text (e.g. comments and strings)
+    pub prose_kinds: Vec<String>,
+    /// Whether plain text outside of tokens is human-readable (e.g. in Markdown)
+    pub prose_plain: bool,
+    /// Characters, other than letters, digits and underscores, that can make up an identifier
+    /// (e.g. ? and ! in Ruby), useful for word-wise cursor movement and selection
+    pub word_chars: Vec<char>,
+    /// Pairs of opening and closing brackets, see [Highlighter::matching_bracket]
+    pub brackets: Vec<(char, char)>,
+    /// The names of tokens, indexed by [TokenKind]
+    kinds: Vec<String>,
+    /// Patterns for lines that the lines after should be indented further than, see [Grammar::indent]
+    indents: Vec<LazyRegex>,
+    /// Patterns for lines that should be indented less than the line before, see [Grammar::outdent]
+    outdents: Vec<LazyRegex>,
 }
 
-impl Highlighter {
-    /// Creates a new highlighter
-    pub fn new(tab_width: usize) -> Self {
+impl Default for Grammar {
+    fn default() -> Self {
         Self {
-            atoms: vec![],
             atom_def: vec![],
             bounded_def: vec![],
-            line_ref: vec![],
-            tokens: vec![],
-            tab_width,
-            tokenize_state: None,
-            tokenize_interp: false,
+            prose_kinds: vec!["comment".to_string(), "string".to_string()],
+            prose_plain: false,
+            word_chars: vec![],
+            brackets: vec![('(', ')'), ('[', ']'), ('{', '}')],
+            kinds: vec![],
+            indents: vec![],
+            outdents: vec![],
         }
     }
+}
+
+impl Grammar {
+    /// Creates a new, empty grammar
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Determine whether a character can be part of an identifier in this language
+    pub fn is_word_char(&self, c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || self.word_chars.contains(&c)
+    }
+
+    /// Find the interned id of a token name, if any rules produce tokens with that name
+    pub fn kind_id(&self, name: &str) -> Option<TokenKind> {
+        self.kinds.iter().position(|k| k == name).map(|idx| TokenKind(idx as u32))
+    }
+
+    /// Find the name of a token from its interned id,
+    /// or None if the id didn't come from this grammar
+    pub fn kind_name(&self, kind: TokenKind) -> Option<&str> {
+        self.kinds.get(kind.0 as usize).map(String::as_str)
+    }
+
+    /// The names of all the tokens this grammar can produce, indexed by [TokenKind]
+    pub fn kinds(&self) -> &[String] {
+        &self.kinds
+    }
+
+    /// Register an atom definition, interning its name
+    fn push_atom_def(&mut self, name: String, exp: LazyRegex, kind: AtomKind, tok: Option<usize>) {
+        // Fold markers and scopes never become tokens, so their names aren't interned
+        let fold = matches!(kind, AtomKind::FoldStart | AtomKind::FoldEnd | AtomKind::Scope);
+        let id = if fold { TokenKind::default() } else { self.kind_id(&name).unwrap_or_else(|| {
+            self.kinds.push(name.clone());
+            TokenKind(self.kinds.len() as u32 - 1)
+        }) };
+        self.atom_def.push(AtomDef { name, exp, kind, tok, id, region: None });
+    }
+
+    /// Creates a new highlighter for a document that uses this grammar
+    pub fn new_state(self: &Arc<Self>, tab_width: usize) -> HighlightState {
+        Highlighter::with_grammar(self.clone(), tab_width)
+    }
 
     /// Register a new keyword token, provide its name and regex
+    ///
+    /// # Panics
+    /// Panics if the regex is invalid, see [Grammar::try_keyword] for a version that doesn't
     pub fn keyword<S: Into<String>>(&mut self, name: S, exp: &str) {
+        self.try_keyword(name, exp).unwrap();
+    }
+
+    /// Register a new keyword token, returning an error if the regex is invalid
+    pub fn try_keyword<S: Into<String>>(&mut self, name: S, exp: &str) -> Result<(), SyntaxError> {
         let name = name.into();
-        let exp = Regex::new(exp).expect("Invalid regex!");
-        self.atom_def.push(AtomDef { name, exp, kind: AtomKind::Keyword, tok: None });
+        let exp = compile(exp)?;
+        self.push_atom_def(name, exp, AtomKind::Keyword, None);
+        Ok(())
     }
     
     /// Register a new bounded token, with a start and end, 
     /// e.g. a multiline comment having starting /* and an ending */ to delimit it
     /// The last argument is a boolean
     /// when true, tokens can be escaped with a backslash e.g. "\"" would be a string of a quote
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid, see [Grammar::try_bounded] for a version that doesn't
     pub fn bounded<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) {
+        self.try_bounded(name, start, end, escapable).unwrap();
+    }
+
+    /// Register a new bounded token, returning an error if a regex is invalid
+    pub fn try_bounded<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) -> Result<(), SyntaxError> {
         let (name, start, end) = (name.into(), start.into(), end.into());
         // Gather atom information
-        let start_exp = Regex::new(&start).expect("Invalid start regex");
-        let end_exp = Regex::new(&end).expect("Invalid end regex");
+        let start_exp = compile(&start)?;
+        let end_exp = compile(&end)?;
         let hybrid = start == end;
         // Register bounded definition
         let idx = self.bounded_def.len();
         self.bounded_def.push(BoundedDef { 
             escapable,
+            nestable: false,
+            region: false,
+            backrefs: None,
         });
         // Register atom definitions
         if hybrid {
-            self.atom_def.push(AtomDef { 
-                name,
-                exp: start_exp,
-                kind: AtomKind::Hybrid,
-                tok: Some(idx),
-            });
+            self.push_atom_def(name, start_exp, AtomKind::Hybrid, Some(idx));
         } else {
-            self.atom_def.push(AtomDef { 
-                name: name.clone(),
-                exp: start_exp,
-                kind: AtomKind::Start,
-                tok: Some(idx),
-            });
-            self.atom_def.push(AtomDef { 
-                name,
-                exp: end_exp,
-                kind: AtomKind::End,
-                tok: Some(idx),
-            });
+            self.push_atom_def(name.clone(), start_exp, AtomKind::Start, Some(idx));
+            self.push_atom_def(name, end_exp, AtomKind::End, Some(idx));
         }
+        Ok(())
+    }
+
+    /// Register a new nested bounded token, with a start and end,
+    /// where the token can contain further occurances of itself, and only ends once every
+    /// start has been matched by an end, e.g. a multiline comment in Rust, where
+    /// /* a /* b */ c */ is a single comment.
+    /// This has no effect when the start and end are the same
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid, see [Grammar::try_bounded_nested] for a version that doesn't
+    pub fn bounded_nested<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) {
+        self.try_bounded_nested(name, start, end, escapable).unwrap();
+    }
+
+    /// Register a new nested bounded token, returning an error if a regex is invalid
+    pub fn try_bounded_nested<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) -> Result<(), SyntaxError> {
+        let idx = self.bounded_def.len();
+        self.try_bounded(name, start, end, escapable)?;
+        self.bounded_def[idx].nestable = true;
+        Ok(())
+    }
+
+    /// Register a new balanced bounded token, with a start and end,
+    /// where occurances of the open pattern within the token must be matched by an end
+    /// before the token ends, e.g. an attribute in Rust starting with #[ and ending with ]
+    /// where any [ inside the attribute must be balanced by a ]
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid, see [Grammar::try_bounded_balanced] for a version that doesn't
+    pub fn bounded_balanced<S: Into<String>>(&mut self, name: S, start: S, end: S, open: S, escapable: bool) {
+        self.try_bounded_balanced(name, start, end, open, escapable).unwrap();
+    }
+
+    /// Register a new balanced bounded token, returning an error if a regex is invalid
+    pub fn try_bounded_balanced<S: Into<String>>(&mut self, name: S, start: S, end: S, open: S, escapable: bool) -> Result<(), SyntaxError> {
+        let (name, start, end, open) = (name.into(), start.into(), end.into(), open.into());
+        let open_exp = compile(&open)?;
+        let idx = self.bounded_def.len();
+        self.try_bounded(name.clone(), start, end, escapable)?;
+        self.push_atom_def(name, open_exp, AtomKind::Nest, Some(idx));
+        Ok(())
+    }
+
+    /// Register a new bounded token whose end depends on text captured by its start,
+    /// e.g. a heredoc in a shell script, which ends with the word given after the <<.
+    /// The end can refer back to the capture groups of the start with \1 to \9.
+    /// Unlike other atoms, the start and end cover the whole of their match,
+    /// rather than the last capture group
+    ///
+    /// ```rust
+    /// use synoptic::{Highlighter, TokOpt};
+    ///
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded_capture("string", r"<<-?(\w+)", r"^\s*\1$", false);
+    /// let lines: Vec<String> = ["cat <<END", "EOF", "END", "echo"].iter().map(|l| l.to_string()).collect();
+    /// h.run(&lines);
+    /// // The EOF line doesn't end the heredoc, but the END line does
+    /// assert!(matches!(&h.line(1, &lines[1])[..], [TokOpt::Some(_, name)] if name == "string"));
+    /// assert!(matches!(&h.line(3, &lines[3])[..], [TokOpt::None(text)] if text == "echo"));
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid or the end refers to a capture group that the start
+    /// doesn't have, see [Grammar::try_bounded_capture] for a version that doesn't
+    pub fn bounded_capture<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) {
+        self.try_bounded_capture(name, start, end, escapable).unwrap();
+    }
+
+    /// Register a new bounded token whose end depends on its start, returning an error if a
+    /// regex is invalid or the end refers to a capture group that the start doesn't have
+    pub fn try_bounded_capture<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) -> Result<(), SyntaxError> {
+        let (name, start, end) = (name.into(), start.into(), end.into());
+        let start_exp = compile(&start)?;
+        let (end, backrefs) = expand_backrefs(&start, &end)?;
+        let end_exp = compile(&end)?;
+        // Register bounded definition
+        let idx = self.bounded_def.len();
+        self.bounded_def.push(BoundedDef {
+            escapable,
+            nestable: false,
+            region: false,
+            backrefs: Some(backrefs),
+        });
+        // Register atom definitions
+        self.push_atom_def(name.clone(), start_exp, AtomKind::Start, Some(idx));
+        self.push_atom_def(name, end_exp, AtomKind::End, Some(idx));
+        Ok(())
     }
 
     /// Register a new interpolatable bounded token, with a start and end, 
     /// e.g. a string as a bounded token, but allowing substitution between {}
     /// The last argument is a boolean
     /// when true, tokens can be escaped with a backslash e.g. "\"" would be a string of a quote
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid or the interpolation markers are equal,
+    /// see [Grammar::try_bounded_interp] for a version that doesn't
     pub fn bounded_interp<S: Into<String>>(&mut self, name: S, start: S, end: S, i_start: S, i_end: S, escapable: bool) {
+        self.try_bounded_interp(name, start, end, i_start, i_end, escapable).unwrap();
+    }
+
+    /// Register a new interpolatable bounded token, returning an error if a regex is invalid
+    /// or the interpolation markers are equal
+    pub fn try_bounded_interp<S: Into<String>>(&mut self, name: S, start: S, end: S, i_start: S, i_end: S, escapable: bool) -> Result<(), SyntaxError> {
         let (name, start, end, i_start, i_end) = (name.into(), start.into(), end.into(), i_start.into(), i_end.into());
-        if i_start == i_end { panic!("start and end markers for interpolation must not be equal!"); }
+        if i_start == i_end { return Err(SyntaxError::EqualInterpolationMarkers); }
         // Gather atom information
-        let start_exp = Regex::new(&start).expect("Invalid start regex");
-        let end_exp = Regex::new(&end).expect("Invalid end regex");
+        let start_exp = compile(&start)?;
+        let end_exp = compile(&end)?;
         let hybrid = start == end;
-        let i_start_exp = Regex::new(&i_start).expect("Invalid interpolation start regex");
-        let i_end_exp = Regex::new(&i_end).expect("Invalid interpolation end regex");
+        let i_start_exp = compile(&i_start)?;
+        let i_end_exp = compile(&i_end)?;
         // Register bounded definition
         let idx = self.bounded_def.len();
         self.bounded_def.push(BoundedDef { 
             escapable,
+            nestable: false,
+            region: false,
+            backrefs: None,
         });
         // Register atom definitions
         if hybrid {
-            self.atom_def.push(AtomDef { 
-                name: name.clone(),
-                exp: start_exp,
-                kind: AtomKind::Hybrid,
-                tok: Some(idx),
-            });
+            self.push_atom_def(name.clone(), start_exp, AtomKind::Hybrid, Some(idx));
         } else {
-            self.atom_def.push(AtomDef { 
-                name: name.clone(),
-                exp: start_exp,
-                kind: AtomKind::Start,
-                tok: Some(idx),
-            });
-            self.atom_def.push(AtomDef { 
-                name: name.clone(),
-                exp: end_exp,
-                kind: AtomKind::End,
-                tok: Some(idx),
-            });
+            self.push_atom_def(name.clone(), start_exp, AtomKind::Start, Some(idx));
+            self.push_atom_def(name.clone(), end_exp, AtomKind::End, Some(idx));
         }
-        self.atom_def.push(AtomDef { 
-            name: name.clone(),
-            exp: i_start_exp,
-            kind: AtomKind::InterpolateStart,
-            tok: Some(idx),
-        });
-        self.atom_def.push(AtomDef { 
-            name: name.clone(),
-            exp: i_end_exp,
-            kind: AtomKind::InterpolateEnd,
-            tok: Some(idx),
-        });
+        self.push_atom_def(name.clone(), i_start_exp, AtomKind::InterpolateStart, Some(idx));
+        self.push_atom_def(name.clone(), i_end_exp, AtomKind::InterpolateEnd, Some(idx));
+        Ok(())
     }
 
-    /// Do an initial pass on a vector of lines.
+    /// Register a region, with a start and end, that is highlighted using the rules of
+    /// another grammar, e.g. JavaScript between <script> and </script> in HTML.
+    /// The start and end of the region are left to the rules of this grammar, and the
+    /// region ends at the end atom even if a token of the embedded grammar is still open.
+    /// Regions that the embedded grammar itself embeds are not highlighted, and its
+    /// multi-line keywords only match within a single line.
     ///
-    /// Note that this will overwrite any existing information,
-    /// use append to add extra lines to the document.
-    pub fn run(&mut self, lines: &[String]) {
-        // Atomize every line
-        self.atoms = lines.iter().map(|l| self.atomize(l)).collect();
-        self.tokenize();
+    /// # Panics
+    /// Panics if a regex is invalid, see [Grammar::try_embed] for a version that doesn't
+    pub fn embed<S: Into<String>>(&mut self, start: S, end: S, grammar: &Grammar) {
+        self.try_embed(start, end, grammar).unwrap();
     }
 
-    /// Appends a line to the highlighter.
-    pub fn append(&mut self, line: &str) {
-        // Atomize this line
-        self.atoms.push(self.atomize(line));
-        self.line_ref.push(vec![]);
-        self.tokenize_line(self.atoms.len().saturating_sub(1));
+    /// Register an embedded region, returning an error if a regex is invalid
+    pub fn try_embed<S: Into<String>>(&mut self, start: S, end: S, grammar: &Grammar) -> Result<(), SyntaxError> {
+        let (start, end) = (start.into(), end.into());
+        if start == end {
+            self.push_embed(compile(&start)?, AtomKind::Hybrid, None, grammar);
+        } else {
+            self.push_embed(compile(&start)?, AtomKind::Start, Some(compile(&end)?), grammar);
+        }
+        Ok(())
     }
 
-    /// Once you have called the run or append methods, you can use this function
-    /// to retrieve individual lines by providing the original line text and the y index.
+    /// Register a region like [Grammar::embed], but with a start that can span up to the given
+    /// number of lines, e.g. a code block whose language is named on the line before it.
+    /// The start is matched in the same way as the regex of [Grammar::multiline_keyword],
+    /// and the region begins on the line where the match ends.
     ///
-    /// # Example
-    /// ```
-    /// let highlighter = Highlighter::new(4); // Tab ('\t') has a display width of 4
-    /// highlighter.keyword("kw", "keyword"); // All occurances of "keyword" will be classed as a token of "kw"
-    /// highlighter.run(vec![
-    ///     "this is a keyword".to_string(), 
-    ///     "second line!".to_string()
-    /// ]);
-    /// // Get the TokOpt for the first line
-    /// highlighter.line(0, &"this is a keyword".to_string())
-    /// // Get the TokOpt for the second line
-    /// highlighter.line(1, &"second line!".to_string())
+    /// ```rust
+    /// use synoptic::{Highlighter, TokOpt};
+    ///
+    /// let mut rust = Highlighter::new(4);
+    /// rust.keyword("keyword", r"\bfn\b");
+    /// let mut doc = Highlighter::new(4);
+    /// doc.multiline_embed(r"^\[rust\]\n---$", "^---$", 2, &rust);
+    /// let lines: Vec<String> = ["[rust]", "---", "fn main", "---", "fn"]
+    ///     .iter().map(|l| l.to_string()).collect();
+    /// doc.run(&lines);
+    /// assert!(matches!(&doc.line(2, &lines[2])[0], TokOpt::Some(_, name) if name == "keyword"));
+    /// assert!(matches!(&doc.line(4, &lines[4])[..], [TokOpt::None(_)]));
     /// ```
-    pub fn line(&self, y: usize, line: &str) -> Vec<TokOpt> {
-        let line = line.replace("\t", &" ".repeat(self.tab_width));
-        let len = line.chars().count();
-        let mut result = vec![];
-        let mut registry: HashMap<usize, (usize, &TokenRef)> = HashMap::default();
-        // Create token registry for this line
-        for token in self.line_ref[y].iter().map(|t| &self.tokens[*t]) {
-            match token {
-                // Register bounded token
-                TokenRef::Bounded { start, end, .. } => {
-                    let start = if start.y != y { 0 } else { self.atoms[start.y][start.x].x.start };
-                    let end = end.clone()
-                        .map(|end| if end.y != y { len } else { self.atoms[end.y][end.x].x.end })
-                        .unwrap_or(len);
-                    registry.insert(start, (end, token));
-                }
-                // Register keyword token
-                TokenRef::Keyword { atom, .. } => {
-                    //println!("{:?}", self.atoms);
-                    let start = self.atoms[atom.y][atom.x].x.start;
-                    let end = self.atoms[atom.y][atom.x].x.end;
-                    registry.insert(start, (end, token));
-                }
-            }
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid, see [Grammar::try_multiline_embed] for a version that doesn't
+    pub fn multiline_embed<S: Into<String>>(&mut self, start: S, end: S, lines: usize, grammar: &Grammar) {
+        self.try_multiline_embed(start, end, lines, grammar).unwrap();
+    }
+
+    /// Register an embedded region with a multi-line start, returning an error if a regex is invalid
+    pub fn try_multiline_embed<S: Into<String>>(&mut self, start: S, end: S, lines: usize, grammar: &Grammar) -> Result<(), SyntaxError> {
+        let start = compile(&format!("(?m){}", start.into()))?;
+        let end = compile(&end.into())?;
+        self.push_embed(start, AtomKind::Multiline(lines.max(1)), Some(end), grammar);
+        Ok(())
+    }
+
+    /// Register the markers of an embedded region along with the rules of the embedded grammar
+    fn push_embed(&mut self, start_exp: LazyRegex, start_kind: AtomKind, end_exp: Option<LazyRegex>, grammar: &Grammar) {
+        // Register the region itself, its atoms switch between grammars rather than make tokens
+        let idx = self.bounded_def.len();
+        self.bounded_def.push(BoundedDef { escapable: false, nestable: false, region: true, backrefs: None });
+        let marker = |exp, kind| AtomDef {
+            name: String::new(),
+            exp,
+            kind,
+            tok: Some(idx),
+            id: TokenKind::default(),
+            region: None,
+        };
+        self.atom_def.push(marker(start_exp, start_kind));
+        if let Some(end_exp) = end_exp {
+            self.atom_def.push(marker(end_exp, AtomKind::End));
         }
-        // Process tokens into TokOpt format
-        let mut chars = line.chars();
-        let mut x = 0;
-        while x < len {
-            if let Some((end, TokenRef::Bounded { name, .. } | TokenRef::Keyword { name, .. })) = registry.get(&x) {
-                // Process token
-                let text = chars.by_ref().take(end - x).collect::<String>();
-                result.push(TokOpt::Some(text, name.clone()));
-                x = *end;
-            } else {
-                // Process plain text
-                if let Some(TokOpt::None(ref mut s)) = result.last_mut() {
-                    s.push(chars.next().unwrap());
-                } else {
-                    result.push(TokOpt::None(chars.next().unwrap().to_string()));
-                }
-                x += 1;
-            }
+        // Take on the rules of the embedded grammar, which only apply within the region
+        let offset = self.bounded_def.len();
+        self.bounded_def.extend(grammar.bounded_def.iter().cloned());
+        for def in &grammar.atom_def {
+            let nested = def.region.is_some() || def.tok.is_some_and(|t| grammar.bounded_def[t].region);
+            if nested { continue; }
+            // Keep embedded multi-line keywords from making the whole document span lines
+            let kind = match def.kind {
+                AtomKind::Multiline(_) => AtomKind::Multiline(1),
+                ref kind => kind.clone(),
+            };
+            self.push_atom_def(def.name.clone(), def.exp.clone(), kind, def.tok.map(|t| t + offset));
+            self.atom_def.last_mut().unwrap().region = Some(idx);
         }
-        result
     }
 
-    /// Whenever a character is deleted or inserted on a line,
-    /// call this function to update any tokens.
-    pub fn edit(&mut self, y: usize, line: &str) {
-        let old_atoms = self.atoms[y].clone();
-        // Update the atoms on this line
-        self.atoms[y] = self.atomize(line);
-        // Determine whether tokenisation is necessary by checking atomic changes
-        if self.retokenization_needed(&old_atoms, &self.atoms[y]) {
-            self.tokenize();
-        }
+    /// Register a pair of patterns that start and end a region that can be folded,
+    /// e.g. { and } in Rust, see [Highlighter::fold_ranges].
+    /// Fold markers within strings, comments and other bounded tokens are ignored
+    ///
+    /// # Panics
+    /// Panics if a regex is invalid, see [Grammar::try_fold] for a version that doesn't
+    pub fn fold<S: Into<String>>(&mut self, name: S, start: &str, end: &str) {
+        self.try_fold(name, start, end).unwrap();
     }
 
-    /// Takes two lists of atoms and determines if retokenization is required in the first place
-    /// This method will ignore index (as this is expected to change when editing)
-    /// Has been shown to make editing events 500x faster to apply (where no atoms are modified)
-    fn retokenization_needed(&self, old: &[Atom], new: &Vec<Atom>) -> bool {
-        // List lengths differ => atoms have been added or deleted
-        if old.len() != new.len() { return true; }
-        for (o, n) in old.iter().zip(new) {
-            // If there is ever ANY discrepancy between atoms, we must retokenize
-            if !(o.name == n.name && o.kind == n.kind && o.tok == n.tok && o.backslashed == n.backslashed) {
-                return true;
-            }
-        }
-        false
+    /// Register a pair of fold markers, returning an error if a regex is invalid
+    pub fn try_fold<S: Into<String>>(&mut self, name: S, start: &str, end: &str) -> Result<(), SyntaxError> {
+        let name = name.into();
+        let start_exp = compile(start)?;
+        let end_exp = compile(end)?;
+        self.push_atom_def(name.clone(), start_exp, AtomKind::FoldStart, None);
+        self.push_atom_def(name, end_exp, AtomKind::FoldEnd, None);
+        Ok(())
     }
 
-    /// Whenever a line is inserted into the document,
-    /// call this function to update any tokens.
-    pub fn insert_line(&mut self, y: usize, line: &str) {
-        self.atoms.insert(y, self.atomize(line));
-        self.tokenize();
+    /// Register a scope that starts on a line matching a regex, e.g. `def .*:$` in Python,
+    /// and covers the lines after it that are indented further than the line it starts on,
+    /// see [Highlighter::indent_scopes]. Blank lines don't end a scope,
+    /// and neither do lines that carry on a bounded token (e.g. a multiline string)
+    ///
+    /// # Panics
+    /// Panics if the regex is invalid, see [Grammar::try_indent_scope] for a version that doesn't
+    pub fn indent_scope<S: Into<String>>(&mut self, name: S, exp: &str) {
+        self.try_indent_scope(name, exp).unwrap();
     }
 
-    /// Whenever a line is removed from a document,
-    /// call this function to update any tokens.
-    pub fn remove_line(&mut self, y: usize) {
-        self.atoms.remove(y);
-        self.tokenize();
+    /// Register an indentation scope, returning an error if the regex is invalid
+    pub fn try_indent_scope<S: Into<String>>(&mut self, name: S, exp: &str) -> Result<(), SyntaxError> {
+        let exp = compile(exp)?;
+        self.push_atom_def(name.into(), exp, AtomKind::Scope, None);
+        Ok(())
     }
 
-    /// This process will turn a line into a vector of atoms
-    fn atomize(&self, line: &str) -> Vec<Atom> {
-        let line = IndexedChars::new(line);
-        let mut atoms = vec![];
-        // For each atom definition
-        for def in &self.atom_def {
-            let occurances = find_all(&def.exp, line.as_str(), self.tab_width);
-            // Register all occurances of any atom
-            for x in occurances {
-                if !x.is_empty() {
-                    // Work out how many backslashes there are behind this atom (for escaping)
-                    let mut backslash_count = 0;
-                    let range = (0..x.start).rev();
-                    for idx in range {
-                        if let Some('\\') = line.get_char(idx) {
-                            backslash_count += 1;
-                        } else {
-                            break;
-                        }
-                    }
-                    // Push out the atom
-                    atoms.push(Atom {
-                        kind: def.kind.clone(),
-                        name: def.name.clone(),
-                        tok: def.tok,
-                        // An odd number of backslashes = escaped
-                        backslashed: backslash_count % 2 != 0,
-                        x,
-                    });
-                }
-            }
+    /// Register a pattern for lines after which the indentation should go up a level,
+    /// e.g. a line ending with { in Rust or : in Python, see [Highlighter::indent_hint].
+    /// Matches within bounded tokens, and keywords that are comments or strings
+    /// (see [Grammar::prose_kinds]), are ignored
+    ///
+    /// # Panics
+    /// Panics if the regex is invalid, see [Grammar::try_indent] for a version that doesn't
+    pub fn indent(&mut self, exp: &str) {
+        self.try_indent(exp).unwrap();
+    }
+
+    /// Register an indent pattern, returning an error if the regex is invalid
+    pub fn try_indent(&mut self, exp: &str) -> Result<(), SyntaxError> {
+        self.indents.push(compile(exp)?);
+        Ok(())
+    }
+
+    /// Register a pattern for lines that should be indented a level less than the line before,
+    /// e.g. a line starting with } in Rust or else in Python, see [Highlighter::indent_hint].
+    /// Matches within bounded tokens, and keywords that are comments or strings
+    /// (see [Grammar::prose_kinds]), are ignored
+    ///
+    /// # Panics
+    /// Panics if the regex is invalid, see [Grammar::try_outdent] for a version that doesn't
+    pub fn outdent(&mut self, exp: &str) {
+        self.try_outdent(exp).unwrap();
+    }
+
+    /// Register an outdent pattern, returning an error if the regex is invalid
+    pub fn try_outdent(&mut self, exp: &str) -> Result<(), SyntaxError> {
+        self.outdents.push(compile(exp)?);
+        Ok(())
+    }
+
+    /// Register a new keyword token that can span up to the given number of lines,
+    /// e.g. a decorator in Python with its arguments spread over several lines.
+    /// The regex is matched against the lines joined together with \n, where ^ and $ match
+    /// at the start and end of each line, and \A only matches at the start of the document
+    /// (so a rule can pick out the first line of a file). As with keywords, the last capture group is used
+    /// if there is one. Matches that span more than one line are split into one part per line,
+    /// in the same way as bounded tokens.
+    /// Highlighters with these rules keep a copy of the text of each line, as the atoms of a
+    /// line depend on the lines around it, so they should be added before running the highlighter.
+    ///
+    /// ```rust
+    /// use synoptic::{Highlighter, TokOpt};
+    ///
+    /// let mut h = Highlighter::new(4);
+    /// h.multiline_keyword("macro", r"^#define(?:.*\\\n)*.*$", 5);
+    /// let lines: Vec<String> = ["#define MAX(a, b) \\", "    ((a) > (b) ? (a) : (b))", "int x;"]
+    ///     .iter().map(|l| l.to_string()).collect();
+    /// h.run(&lines);
+    /// assert!(matches!(&h.line(1, &lines[1])[..], [TokOpt::Some(_, name)] if name == "macro"));
+    /// assert!(matches!(&h.line(2, &lines[2])[..], [TokOpt::None(_)]));
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the regex is invalid, see [Grammar::try_multiline_keyword] for a version that doesn't
+    pub fn multiline_keyword<S: Into<String>>(&mut self, name: S, exp: &str, lines: usize) {
+        self.try_multiline_keyword(name, exp, lines).unwrap();
+    }
+
+    /// Register a new keyword token that can span several lines,
+    /// returning an error if the regex is invalid
+    pub fn try_multiline_keyword<S: Into<String>>(&mut self, name: S, exp: &str, lines: usize) -> Result<(), SyntaxError> {
+        let exp = compile(&format!("(?m){exp}"))?;
+        // Matches that span several lines are tokenized like bounded tokens
+        let idx = self.bounded_def.len();
+        self.bounded_def.push(BoundedDef {
+            escapable: false,
+            nestable: false,
+            region: false,
+            backrefs: None,
+        });
+        self.push_atom_def(name.into(), exp, AtomKind::Multiline(lines.max(1)), Some(idx));
+        Ok(())
+    }
+
+    /// How many lines either side of a line can change its atoms, through multi-line keywords
+    fn reach(&self) -> usize {
+        self.atom_def.iter()
+            .filter_map(|def| match def.kind {
+                AtomKind::Multiline(lines) => Some(lines - 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Determine whether an atom marks the start or end of an embedded region
+    fn is_region_marker(&self, atom: &Atom) -> bool {
+        atom.tok.is_some_and(|t| self.bounded_def[t].region)
+    }
+}
+
+/// An error in the rules given to a [Grammar]
+#[derive(Debug, Clone)]
+pub enum SyntaxError {
+    /// A regular expression couldn't be compiled
+    InvalidRegex {
+        /// The regular expression that was given
+        pattern: String,
+        /// Why it couldn't be compiled
+        error: regex::Error,
+    },
+    /// The start and end markers for interpolation were equal
+    EqualInterpolationMarkers,
+    /// The end of a token refers back to a capture group that the start doesn't have
+    UnknownCaptureGroup(usize),
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::InvalidRegex { pattern, error } => write!(f, "invalid regex {pattern:?}: {error}"),
+            Self::EqualInterpolationMarkers => write!(f, "start and end markers for interpolation must not be equal"),
+            Self::UnknownCaptureGroup(group) => write!(f, "the start of the token has no capture group {group}"),
         }
-        // Order them based on start index
-        atoms.sort_by(|a, b| a.x.start.cmp(&b.x.start));
-        atoms
     }
+}
 
-    fn tokenize(&mut self) {
-        self.tokenize_state = None;
-        self.tokenize_interp = false;
-        self.line_ref = vec![];
-        self.atoms.iter().enumerate().for_each(|_| self.line_ref.push(vec![]));
-        self.tokens = vec![];
-        for y in 0..self.atoms.len() {
-            self.tokenize_line(y);
+impl std::error::Error for SyntaxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidRegex { error, .. } => Some(error),
+            Self::EqualInterpolationMarkers | Self::UnknownCaptureGroup(_) => None,
         }
     }
+}
 
-    fn tokenize_line(&mut self, y: usize) {
-        let line_ref = self.line_ref.get_mut(y).unwrap();
-        let mut at_x = 0;
-        let atoms = &self.atoms[y];
-        for (x, atom) in atoms.iter().enumerate() {
-            if atom.x.start < at_x { continue; }
-            // Work out if this atom is to be ignored (due to escaping)
-            if let Atom { tok: Some(t), backslashed, .. } = atom {
-                if self.bounded_def[*t].escapable && *backslashed {
-                    continue;
-                }
+/// Turn the back-references (\1 to \9) in the end of a token into copies of the capture groups
+/// of the start that they refer to, so that ends can be found without knowing what was captured.
+/// Returns the new expression, along with the capture group each back-reference referred to
+fn expand_backrefs(start: &str, end: &str) -> Result<(String, Vec<usize>), SyntaxError> {
+    let mut result = String::new();
+    let mut backrefs = vec![];
+    let mut chars = end.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(d @ '1'..='9') => {
+                let group = d as usize - '0' as usize;
+                let inner = capture_group(start, group).ok_or(SyntaxError::UnknownCaptureGroup(group))?;
+                result.push_str(&format!("(?P<backref{}>{inner})", backrefs.len()));
+                backrefs.push(group);
             }
-            // Continue tokenising...
-            match atom {
-                Atom { name, kind: AtomKind::Keyword, .. } => {
-                    if self.tokenize_state.is_none() || self.tokenize_interp {
-                        self.tokens.push(TokenRef::Keyword {
-                            name: name.clone(),
-                            atom: Loc { y, x },
-                        });
-                        line_ref.push(self.tokens.len().saturating_sub(1));
-                        at_x = atom.x.end;
-                    }
-                }
-                Atom { name, kind: AtomKind::Start, tok, .. } => {
-                    if self.tokenize_interp { continue; }
-                    if self.tokenize_state.is_none() {
-                        self.tokenize_state = *tok;
-                        self.tokens.push(TokenRef::Bounded {
-                            name: name.clone(),
-                            start: Loc { y, x },
-                            end: None,
-                        });
-                        at_x = atom.x.end;
-                    }
-                }
-                Atom { kind: AtomKind::End, tok, .. } => {
-                    if self.tokenize_interp { continue; }
-                    if self.tokenize_state == *tok {
-                        self.tokenize_state = None;
-                        if let TokenRef::Bounded { ref mut end, .. } = self.tokens.last_mut().unwrap() {
-                            *end = Some(Loc { y, x });
-                            at_x = atom.x.end;
-                        }
-                        line_ref.push(self.tokens.len().saturating_sub(1));
-                    }
-                }
-                Atom { name, kind: AtomKind::Hybrid, tok, .. } => {
-                    if self.tokenize_interp { continue; }
-                    if self.tokenize_state.is_none() {
-                        // Start registering token
-                        self.tokenize_state = *tok;
-                        self.tokens.push(TokenRef::Bounded {
-                            name: name.clone(),
-                            start: Loc { y, x },
-                            end: None,
-                        });
-                        at_x = atom.x.end;
-                    } else if self.tokenize_state == *tok {
-                        // Stop registering token
-                        self.tokenize_state = None;
-                        if let TokenRef::Bounded { ref mut end, .. } = self.tokens.last_mut().unwrap() {
-                            *end = Some(Loc { y, x });
-                            at_x = atom.x.end;
-                        }
-                        line_ref.push(self.tokens.len().saturating_sub(1));
-                    }
-                }
-                Atom { kind: AtomKind::InterpolateStart, tok, .. } => {
-                    if self.tokenize_state == *tok {
-                        // End the current token
-                        if let TokenRef::Bounded { ref mut end, .. } = self.tokens.last_mut().unwrap() {
-                            *end = Some(Loc { y, x });
-                            at_x = atom.x.end;
-                        }
-                        line_ref.push(self.tokens.len().saturating_sub(1));
-                        // Register interpolation
-                        self.tokenize_interp = true;
-                    }
-                }
-                Atom { name, kind: AtomKind::InterpolateEnd, tok, .. } => {
-                    if self.tokenize_state == *tok {
-                        // Stop interpolating
-                        self.tokenize_interp = false;
-                        // Resume capturing the outer token
-                        self.tokens.push(TokenRef::Bounded {
-                            name: name.clone(),
-                            start: Loc { y, x },
-                            end: None,
-                        });
-                        at_x = atom.x.end;
-                    }
+            Some(d) => {
+                result.push(c);
+                result.push(d);
+            }
+            None => result.push(c),
+        }
+    }
+    Ok((result, backrefs))
+}
+
+/// Find the pattern within a capture group of a regular expression
+fn capture_group(exp: &str, group: usize) -> Option<&str> {
+    let mut count = 0;
+    // Where each open group's pattern starts, and its number if it is a capture group
+    let mut open: Vec<(usize, Option<usize>)> = vec![];
+    // How many character classes we are within
+    let mut class = 0;
+    let mut chars = exp.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => { chars.next(); }
+            '[' => {
+                class += 1;
+                // A ] straight after the [ (or [^) is part of the class
+                chars.next_if(|(_, c)| *c == '^');
+                chars.next_if(|(_, c)| *c == ']');
+            }
+            ']' if class > 0 => class -= 1,
+            '(' if class == 0 => {
+                let rest = &exp[i + 1..];
+                if rest.starts_with("?P<") || rest.starts_with("?<") {
+                    count += 1;
+                    open.push((i + 2 + rest.find('>')?, Some(count)));
+                } else if rest.starts_with('?') {
+                    open.push((i + 1, None));
+                } else {
+                    count += 1;
+                    open.push((i + 1, Some(count)));
                 }
             }
-            if self.tokenize_state.is_some() {
-                line_ref.push(self.tokens.len().saturating_sub(1));
+            ')' if class == 0 => {
+                if let (start, Some(n)) = open.pop()? {
+                    if n == group { return Some(&exp[start..i]); }
+                }
             }
+            _ => (),
         }
-        if self.tokenize_state.is_some() {
-            line_ref.push(self.tokens.len().saturating_sub(1));
-        }
-        line_ref.dedup();
     }
+    None
 }
 
-/// This will find all occurances of a string in a document (and return character indices)
-pub fn find_all(exp: &Regex, target: &str, tab_width: usize) -> Vec<Range<usize>> {
-    let mapping = create_mapping(target, tab_width);
-    exp.captures_iter(target)
-        // Get last capture
-        .map(|c| c.iter().flatten().collect::<Vec<_>>())
-        .map(|mut c| c.pop().unwrap())
-        // Extract end and start values
-        .map(|m| mapping[&m.start()]..mapping[&m.end()])
-        .collect()
+/// Compile a regular expression, turning any failure into a [SyntaxError]
+fn compile(exp: &str) -> Result<LazyRegex, SyntaxError> {
+    let regex = Regex::new(exp)
+        .map_err(|error| SyntaxError::InvalidRegex { pattern: exp.to_string(), error })?;
+    Ok(LazyRegex { source: exp.to_string(), compiled: OnceLock::from(regex) })
 }
 
-/// HashMap<byte_idx, char_idx>
-pub fn create_mapping(target: &str, tab_width: usize) -> HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>> {
-    let mut result: HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>> =
-        HashMap::with_capacity_and_hasher(target.len(), BuildHasherDefault::default());
-    result.insert(0, 0);
-    let mut acc_byte = 0;
-    let mut acc_char = 0;
-    for c in target.chars() {
-        acc_byte += c.len_utf8();
-        acc_char += if c == '\t' { tab_width } else { 1 };
-        result.insert(acc_byte, acc_char);
-    }
-    result
-}
 
-/// Utility function to determine the width of a string, with variable tab width
-#[must_use]
-pub fn width(st: &str, tab_width: usize) -> usize {
-    let tabs = st.matches('\t').count();
-    (st.width() + tabs * tab_width).saturating_sub(tabs)
+/// This is the main struct that will highlight your document.
+///
+/// It holds the per-document highlighting state (atoms and tokens) along with a shared
+/// reference to the [Grammar] being used to highlight the document.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Highlighter {
+    /// The rules used to highlight this document
+    pub grammar: Arc<Grammar>,
+    /// The list of atoms, encapsulated within an inner vector for atoms on the same line.
+    /// Each line is shared separately, so that after a [Highlighter::snapshot]
+    /// an edit only copies the atoms of the lines it changes
+    pub atoms: Arc<Vec<Arc<Vec<Atom>>>>,
+    /// A reference to what tokens lie on which line numbers
+    pub line_ref: Arc<Vec<Vec<usize>>>,
+    /// A list of the resulting tokens generated from run and append
+    pub tokens: Arc<Vec<TokenRef>>,
+    /// How many spaces a tab character should be
+    pub tab_width: usize,
+    /// When enabled, [Highlighter::line] leaves tab characters as they are instead of
+    /// expanding them into spaces. This guarantees that the text of the tokens for a line,
+    /// when joined together, is exactly equal to the original line.
+    /// Use [Highlighter::display_map] to work out display columns in this mode.
+    pub lossless: bool,
+    /// What to do with an empty final line when running the highlighter on a document
+    pub trailing_newline: TrailingNewline,
+    /// Diagnostics for each line, in the form of display ranges and their severity
+    pub diagnostics: Arc<Vec<LineDiagnostics>>,
+    /// Patterns whose matches are shown on top of the syntax highlighting, see [Highlighter::overlay]
+    overlays: Vec<(String, LazyRegex)>,
+    /// For purposes of tokenization
+    tokenize_state: Option<usize>,
+    tokenize_interp: bool,
+    tokenize_depth: usize,
+    tokenize_region: Option<usize>,
+    /// The state of the tokenizer at the start of each line, so edits can resume from there
+    line_states: Arc<Vec<TokenizerState>>,
+    /// The text of each line, only kept when the grammar has multi-line keywords
+    texts: Arc<Vec<String>>,
+    /// The tokens each line was last rendered as, see [Highlighter::line]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cache: LineCache,
 }
 
+/// The tokens of lines that have already been rendered, along with a hash of what they
+/// were rendered from. Entries are cleared whenever the tokens on their line change.
+#[derive(Debug, Default)]
+struct LineCache(Mutex<Vec<CachedLine>>);
 
-/// Trim utility function to trim down a line of tokens to offset text
-pub fn trim(input: &[TokOpt], start: usize) -> Vec<TokOpt> {
-    let mut opt: Vec<TokOpt> = input.to_vec();
-    let mut total_width = 0;
-    for i in &opt {
-        let (TokOpt::Some(txt, _) | TokOpt::None(txt)) = i;
-        total_width += txt.len();
-    }
-    let width = total_width.saturating_sub(start);
-    while total_width != width {
-        if let Some(token) = opt.get_mut(0) {
-            token.nibble_front(4);
-            total_width -= 1;
-            if token.is_empty() {
-                opt.remove(0);
-            }
-        } else {
-            break;
+/// A hash of the text and settings a line was rendered with, along with the result
+type CachedLine = Option<(u64, Vec<TokOpt>)>;
+
+/// The diagnostics on a line, as display ranges and their severity
+type LineDiagnostics = Vec<(Range<usize>, Severity)>;
+
+impl LineCache {
+    fn lock(&self) -> MutexGuard<'_, Vec<CachedLine>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn get(&self, y: usize, key: u64) -> Option<Vec<TokOpt>> {
+        match self.lock().get(y) {
+            Some(Some((k, tokens))) if *k == key => Some(tokens.clone()),
+            _ => None,
         }
     }
-    opt
-}
 
-/// Trim utility function to trim down a line of tokens to offset text (with length)
-pub fn trim_fit(input: &[TokOpt], start: usize, length: usize, tab_width: usize) -> Vec<TokOpt> {
-    // Form a vector of tokens
-    let mut opt: Vec<TokOpt> = input.to_vec();
-    // (1) Find the location of the starting point
-    let start_idx = find_tok_index(input, start, tab_width);
-	// (2) Find the location of the ending point
-    let end_idx = find_tok_index(input, start + length, tab_width);
-    // Trim off start token (ahead of time)
-    if let Some((start_tok, start_rel)) = start_idx {
-        opt.get_mut(start_tok).unwrap().skip(start_rel, tab_width);
-    }
-    // Trim off end token (ahead of time)
-    if let Some((end_tok, mut end_rel)) = end_idx {
-        if start_idx.unwrap().0 == end_tok {
-            // Same token for start and end! Adjust (to account for start trim)
-            end_rel -= start_idx.unwrap().1;
-        }
-        opt.get_mut(end_tok).unwrap().take(end_rel, tab_width);
-	}
-    // Blitz all tokens firmly behind start
-	if let Some((start_tok, _)) = start_idx {
-        opt.drain(..start_tok);
-    }
-    // Blitz all tokens firmly ahead of length
-    if let Some((mut end_tok, _)) = end_idx {
-        if let Some((start_tok, _)) = start_idx {
-            // Adjust end_tok after draining of start tokens
-            end_tok -= start_tok;
-        }
-        if end_tok + 1 < opt.len() {
-            opt.drain(end_tok + 1..);
-        }
-    }
-    // If we can't satisfy start or end, then just return empty handed
-    if start_idx.is_none() && end_idx.is_none() {
-        opt = vec![];
+    fn set(&self, y: usize, key: u64, tokens: &[TokOpt]) {
+        let mut cache = self.lock();
+        if cache.len() <= y {
+            cache.resize(y + 1, None);
+        }
+        cache[y] = Some((key, tokens.to_vec()));
     }
-    // Apply padding if applicable
-    let mut total_width: usize = opt.iter().map(|tok| width(tok.text(), tab_width)).sum();
-    while total_width < length {
-        if let Some(TokOpt::None(ref mut text)) = opt.last_mut() {
-            *text += " ";
-            total_width += 1;
-        } else {
-            // No tokens left, discontinue
-            opt.push(TokOpt::None("".to_string()));
+
+    fn invalidate(&mut self, y: usize) {
+        if let Some(entry) = self.0.get_mut().unwrap_or_else(PoisonError::into_inner).get_mut(y) {
+            *entry = None;
         }
     }
-    // Return the result
-    opt
+
+    /// Make room for `added` lines in place of a range of lines
+    fn splice(&mut self, range: Range<usize>, added: usize) {
+        let cache = self.0.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if range.start < cache.len() {
+            let end = range.end.min(cache.len());
+            cache.splice(range.start..end, (0..added).map(|_| None));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
+    }
 }
 
-/// Find the token index within a tokopt given a display index
-/// Returns (token_index, index_within_that_token)
-pub fn find_tok_index(input: &[TokOpt], disp_idx: usize, tab_width: usize) -> Option<(usize, usize)> {
-    let mut total_width = 0;
-    for (idx, token) in input.iter().enumerate() {
-        let this_width = width(token.text(), tab_width);
-        total_width += this_width;
-        // Check if we've passed the display index
-        if total_width > disp_idx {
-            // We have, this token contains disp_idx, work out relative idx
-            let rel_idx = this_width - (total_width - disp_idx);
-            return Some((idx, rel_idx));
+impl Clone for LineCache {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.lock().clone()))
+    }
+}
+
+/// The state of the tokenizer between two atoms
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct TokenizerState {
+    state: Option<usize>,
+    interp: bool,
+    depth: usize,
+    region: Option<usize>,
+}
+
+/// A copy of the highlighting state of a document at a point in time.
+/// The grammar isn't included, so taking a snapshot doesn't copy any regular expressions.
+/// See [Highlighter::snapshot] and [Highlighter::restore]
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    atoms: Arc<Vec<Arc<Vec<Atom>>>>,
+    line_ref: Arc<Vec<Vec<usize>>>,
+    tokens: Arc<Vec<TokenRef>>,
+    diagnostics: Arc<Vec<LineDiagnostics>>,
+    tokenize_state: Option<usize>,
+    tokenize_interp: bool,
+    tokenize_depth: usize,
+    tokenize_region: Option<usize>,
+    line_states: Arc<Vec<TokenizerState>>,
+    texts: Arc<Vec<String>>,
+}
+
+/// What to do with the empty line that follows a document's final newline.
+///
+/// Splitting "a\nb\n" at each newline gives three lines, the last of which is empty.
+/// Some editors treat this as a real line and some don't.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingNewline {
+    /// Treat the final empty line as a real line (this is the default)
+    #[default]
+    Keep,
+    /// Leave the final empty line out, so "a\nb\n" is two lines
+    Drop,
+}
+
+/// A summary of what a line contains, see [Highlighter::line_summary]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineSummary {
+    /// Whether a comment (of kind `comment` or `comment.*`) on this line contains a TODO or FIXME marker
+    pub todo: bool,
+    /// The most severe diagnostic on this line, if there are any
+    pub diagnostic: Option<Severity>,
+    /// Whether a bounded token (e.g. a multiline string) starts on this line and continues past it
+    pub starts_multiline: bool,
+    /// The names of the overlays (see [Highlighter::overlay]) that match on this line,
+    /// such as search results, sorted by name
+    pub overlays: Vec<String>,
+}
+
+/// Where a token lies within the original text of a line, see [Highlighter::line_spans]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// The byte range of the token within the line
+    pub range: Range<usize>,
+    /// The character range of the token within the line
+    pub char_range: Range<usize>,
+    /// The name of the token, or None if this is plain text
+    pub name: Option<String>,
+}
+
+/// A token of a line that borrows its text and name rather than owning them,
+/// see [Highlighter::line_refs]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokSpan<'a> {
+    /// The raw text of the token, as it appears in the line
+    pub text: &'a str,
+    /// The name of the token, or None if this is plain text
+    pub name: Option<&'a str>,
+    /// The interned name of the token, or None if this is plain text
+    pub kind: Option<TokenKind>,
+}
+
+/// A region of a document that can be folded away, see [Highlighter::fold_ranges]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fold {
+    /// The line the region starts on, which stays visible when the region is folded
+    pub start: usize,
+    /// The last line of the region
+    pub end: usize,
+    /// The name of the bounded token or fold markers that make up the region
+    pub name: String,
+}
+
+/// How the indentation of the lines around a line should change, see [Highlighter::indent_hint]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IndentChange {
+    /// Whether the line after this one should be indented a level further than this one,
+    /// e.g. when this line ends with {
+    pub indent: bool,
+    /// Whether this line should be indented a level less than the line before it,
+    /// e.g. when this line starts with }
+    pub outdent: bool,
+}
+
+/// A single cell of a minimap, see [Highlighter::minimap]
+#[derive(Debug, Clone, PartialEq)]
+pub enum MinimapCell {
+    /// There is nothing but whitespace in this cell
+    Empty,
+    /// This cell is mostly plain text
+    Text,
+    /// This cell is mostly made up of a token with this name
+    Token(String),
+}
+
+/// The per-document highlighting state, see [Grammar::new_state]
+pub type HighlightState = Highlighter;
+
+/// The rules for a language, which can be shared between many documents, see [Grammar]
+pub type Syntax = Grammar;
+
+impl Highlighter {
+    /// Creates a new highlighter
+    pub fn new(tab_width: usize) -> Self {
+        Self::with_grammar(Arc::new(Grammar::new()), tab_width)
+    }
+
+    /// Creates a new highlighter that uses an existing (shared) grammar
+    pub fn with_grammar(grammar: Arc<Grammar>, tab_width: usize) -> Self {
+        Self {
+            grammar,
+            atoms: Arc::default(),
+            line_ref: Arc::default(),
+            tokens: Arc::default(),
+            tab_width,
+            lossless: false,
+            trailing_newline: TrailingNewline::Keep,
+            diagnostics: Arc::default(),
+            overlays: vec![],
+            tokenize_state: None,
+            tokenize_interp: false,
+            tokenize_depth: 0,
+            tokenize_region: None,
+            line_states: Arc::default(),
+            texts: Arc::default(),
+            cache: LineCache::default(),
         }
     }
-    None
+
+    /// Creates an empty highlighter for another document, which shares the grammar
+    /// and settings of this one, so no regular expressions are copied or compiled
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// let rust = synoptic::from_extension("rs", 4).unwrap();
+    /// let (mut a, mut b) = (rust.new_document(), rust.new_document());
+    /// a.run(&["fn main() {}".to_string()]);
+    /// b.run(&["let x = 1;".to_string()]);
+    /// assert!(Arc::ptr_eq(&a.grammar, &b.grammar));
+    /// ```
+    pub fn new_document(&self) -> HighlightState {
+        let mut result = Self::with_grammar(self.grammar.clone(), self.tab_width);
+        result.lossless = self.lossless;
+        result.trailing_newline = self.trailing_newline;
+        result
+    }
+
+    /// Get mutable access to the grammar of this highlighter.
+    /// If the grammar is shared with other highlighters, this highlighter will get its own copy
+    pub fn grammar_mut(&mut self) -> &mut Grammar {
+        Arc::make_mut(&mut self.grammar)
+    }
+
+    /// Register a new keyword token, provide its name and regex
+    pub fn keyword<S: Into<String>>(&mut self, name: S, exp: &str) {
+        self.grammar_mut().keyword(name, exp);
+    }
+
+    /// Register a new keyword token, returning an error if the regex is invalid
+    pub fn try_keyword<S: Into<String>>(&mut self, name: S, exp: &str) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_keyword(name, exp)
+    }
+
+    /// Register a new keyword token using a [Pat] rather than a regex
+    pub fn keyword_pat<S: Into<String>>(&mut self, name: S, pat: &Pat) {
+        self.keyword(name, pat.as_str());
+    }
+
+    /// Register a new bounded token using a [Pat] for the start and end rather than regexes
+    pub fn bounded_pat<S: Into<String>>(&mut self, name: S, start: &Pat, end: &Pat, escapable: bool) {
+        self.bounded(name.into(), start.to_string(), end.to_string(), escapable);
+    }
+
+    /// Register a new bounded token, with a start and end, 
+    /// e.g. a multiline comment having starting /* and an ending */ to delimit it
+    /// The last argument is a boolean
+    /// when true, tokens can be escaped with a backslash e.g. "\"" would be a string of a quote
+    pub fn bounded<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) {
+        self.grammar_mut().bounded(name, start, end, escapable);
+    }
+
+    /// Register a new bounded token, returning an error if a regex is invalid
+    pub fn try_bounded<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_bounded(name, start, end, escapable)
+    }
+
+    /// Register a new nested bounded token, see [Grammar::bounded_nested]
+    pub fn bounded_nested<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) {
+        self.grammar_mut().bounded_nested(name, start, end, escapable);
+    }
+
+    /// Register a new nested bounded token, returning an error if a regex is invalid
+    pub fn try_bounded_nested<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_bounded_nested(name, start, end, escapable)
+    }
+
+    /// Register a new balanced bounded token, see [Grammar::bounded_balanced]
+    pub fn bounded_balanced<S: Into<String>>(&mut self, name: S, start: S, end: S, open: S, escapable: bool) {
+        self.grammar_mut().bounded_balanced(name, start, end, open, escapable);
+    }
+
+    /// Register a new balanced bounded token, returning an error if a regex is invalid
+    pub fn try_bounded_balanced<S: Into<String>>(&mut self, name: S, start: S, end: S, open: S, escapable: bool) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_bounded_balanced(name, start, end, open, escapable)
+    }
+
+    /// Register a new bounded token whose end depends on its start, see [Grammar::bounded_capture]
+    pub fn bounded_capture<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) {
+        self.grammar_mut().bounded_capture(name, start, end, escapable);
+    }
+
+    /// Register a new bounded token whose end depends on its start, returning an error if a
+    /// regex is invalid or the end refers to a capture group that the start doesn't have
+    pub fn try_bounded_capture<S: Into<String>>(&mut self, name: S, start: S, end: S, escapable: bool) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_bounded_capture(name, start, end, escapable)
+    }
+
+    /// Register a new interpolatable bounded token, with a start and end, 
+    /// e.g. a string as a bounded token, but allowing substitution between {}
+    /// The last argument is a boolean
+    /// when true, tokens can be escaped with a backslash e.g. "\"" would be a string of a quote
+    pub fn bounded_interp<S: Into<String>>(&mut self, name: S, start: S, end: S, i_start: S, i_end: S, escapable: bool) {
+        self.grammar_mut().bounded_interp(name, start, end, i_start, i_end, escapable);
+    }
+
+    /// Register a new interpolatable bounded token, returning an error if a regex is invalid
+    /// or the interpolation markers are equal
+    pub fn try_bounded_interp<S: Into<String>>(&mut self, name: S, start: S, end: S, i_start: S, i_end: S, escapable: bool) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_bounded_interp(name, start, end, i_start, i_end, escapable)
+    }
+
+    /// Register a pair of patterns that start and end a region that can be folded, see [Grammar::fold]
+    pub fn fold<S: Into<String>>(&mut self, name: S, start: &str, end: &str) {
+        self.grammar_mut().fold(name, start, end);
+    }
+
+    /// Register a pair of fold markers, returning an error if a regex is invalid
+    pub fn try_fold<S: Into<String>>(&mut self, name: S, start: &str, end: &str) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_fold(name, start, end)
+    }
+
+    /// Register a pattern for lines after which the indentation should go up a level, see [Grammar::indent]
+    pub fn indent(&mut self, exp: &str) {
+        self.grammar_mut().indent(exp);
+    }
+
+    /// Register an indent pattern, returning an error if the regex is invalid
+    pub fn try_indent(&mut self, exp: &str) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_indent(exp)
+    }
+
+    /// Register a pattern for lines that should be indented a level less than the line before,
+    /// see [Grammar::outdent]
+    pub fn outdent(&mut self, exp: &str) {
+        self.grammar_mut().outdent(exp);
+    }
+
+    /// Register an outdent pattern, returning an error if the regex is invalid
+    pub fn try_outdent(&mut self, exp: &str) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_outdent(exp)
+    }
+
+    /// Register a scope that covers the lines indented further than the line it starts on,
+    /// see [Grammar::indent_scope]
+    pub fn indent_scope<S: Into<String>>(&mut self, name: S, exp: &str) {
+        self.grammar_mut().indent_scope(name, exp);
+    }
+
+    /// Register an indentation scope, returning an error if the regex is invalid
+    pub fn try_indent_scope<S: Into<String>>(&mut self, name: S, exp: &str) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_indent_scope(name, exp)
+    }
+
+    /// Register a new keyword token that can span several lines, see [Grammar::multiline_keyword]
+    pub fn multiline_keyword<S: Into<String>>(&mut self, name: S, exp: &str, lines: usize) {
+        self.grammar_mut().multiline_keyword(name, exp, lines);
+    }
+
+    /// Register a new keyword token that can span several lines,
+    /// returning an error if the regex is invalid
+    pub fn try_multiline_keyword<S: Into<String>>(&mut self, name: S, exp: &str, lines: usize) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_multiline_keyword(name, exp, lines)
+    }
+
+    /// Register a region, with a start and end, that is highlighted using the rules of another
+    /// highlighter, e.g. JavaScript between <script> and </script> in HTML, see [Grammar::embed]
+    ///
+    /// ```
+    /// use synoptic::{Highlighter, TokOpt};
+    ///
+    /// let mut js = Highlighter::new(4);
+    /// js.keyword("keyword", r"\blet\b");
+    /// let mut html = Highlighter::new(4);
+    /// html.keyword("tag", "</?script>");
+    /// html.embed("<script>", "</script>", &js);
+    /// let line = "let x; <script>let y;</script>".to_string();
+    /// html.run(&[line.clone()]);
+    /// let names: Vec<_> = html.line(0, &line).into_iter()
+    ///     .filter_map(|t| if let TokOpt::Some(text, name) = t { Some((text, name)) } else { None })
+    ///     .collect();
+    /// assert_eq!(names, vec![
+    ///     ("<script>".to_string(), "tag".to_string()),
+    ///     ("let".to_string(), "keyword".to_string()),
+    ///     ("</script>".to_string(), "tag".to_string()),
+    /// ]);
+    /// ```
+    pub fn embed<S: Into<String>>(&mut self, start: S, end: S, other: &Highlighter) {
+        self.grammar_mut().embed(start, end, &other.grammar);
+    }
+
+    /// Register an embedded region, returning an error if a regex is invalid
+    pub fn try_embed<S: Into<String>>(&mut self, start: S, end: S, other: &Highlighter) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_embed(start, end, &other.grammar)
+    }
+
+    /// Register an embedded region with a start that can span up to the given number of lines,
+    /// see [Grammar::multiline_embed]
+    pub fn multiline_embed<S: Into<String>>(&mut self, start: S, end: S, lines: usize, other: &Highlighter) {
+        self.grammar_mut().multiline_embed(start, end, lines, &other.grammar);
+    }
+
+    /// Register an embedded region with a multi-line start, returning an error if a regex is invalid
+    pub fn try_multiline_embed<S: Into<String>>(&mut self, start: S, end: S, lines: usize, other: &Highlighter) -> Result<(), SyntaxError> {
+        self.grammar_mut().try_multiline_embed(start, end, lines, &other.grammar)
+    }
+
+    /// Take a snapshot of the current highlighting state.
+    /// This can later be given to [Highlighter::restore] to undo large operations
+    /// or speculative edits without having to run the highlighter again.
+    ///
+    /// The snapshot shares its state with the highlighter, so taking one is cheap enough to
+    /// do before every edit. The first edit afterwards copies the list of tokens, but only
+    /// the atoms of the lines that it changes.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            atoms: self.atoms.clone(),
+            line_ref: self.line_ref.clone(),
+            tokens: self.tokens.clone(),
+            diagnostics: self.diagnostics.clone(),
+            tokenize_state: self.tokenize_state,
+            tokenize_interp: self.tokenize_interp,
+            tokenize_depth: self.tokenize_depth,
+            tokenize_region: self.tokenize_region,
+            line_states: self.line_states.clone(),
+            texts: self.texts.clone(),
+        }
+    }
+
+    /// Restore the highlighting state from a snapshot taken with [Highlighter::snapshot]
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.atoms = snapshot.atoms;
+        self.line_ref = snapshot.line_ref;
+        self.tokens = snapshot.tokens;
+        self.diagnostics = snapshot.diagnostics;
+        self.tokenize_state = snapshot.tokenize_state;
+        self.tokenize_interp = snapshot.tokenize_interp;
+        self.tokenize_depth = snapshot.tokenize_depth;
+        self.tokenize_region = snapshot.tokenize_region;
+        self.line_states = snapshot.line_states;
+        self.texts = snapshot.texts;
+        self.cache.clear();
+    }
+
+    /// Do an initial pass on a vector of lines.
+    ///
+    /// Line index `y` in the highlighter refers to `lines[y]`, unless the final line is empty
+    /// and `trailing_newline` is set to drop it (see [TrailingNewline]).
+    ///
+    /// Note that this will overwrite any existing information,
+    /// use append to add extra lines to the document.
+    pub fn run(&mut self, lines: &[String]) {
+        self.run_iter(lines);
+    }
+
+    /// Do an initial pass on any iterator of lines,
+    /// this avoids having to collect lines from e.g. a rope into a vector first.
+    ///
+    /// Note that this will overwrite any existing information,
+    /// use append or append_lines to add extra lines to the document.
+    pub fn run_iter<I: IntoIterator<Item = S>, S: AsRef<str>>(&mut self, lines: I) {
+        let mut lines = lines.into_iter().peekable();
+        let (mut atoms, mut texts) = (vec![], vec![]);
+        let multiline = self.grammar.reach() > 0;
+        // Atomize every line
+        while let Some(line) = lines.next() {
+            if lines.peek().is_none() && self.drops_line(line.as_ref()) { break; }
+            if multiline {
+                texts.push(line.as_ref().to_string());
+            } else {
+                atoms.push(Arc::new(self.atomize(line.as_ref())));
+            }
+        }
+        if multiline {
+            atoms = (0..texts.len()).map(|y| Arc::new(self.atomize_within(&texts, y))).collect();
+        }
+        self.atoms = Arc::new(atoms);
+        self.texts = Arc::new(texts);
+        self.tokenize();
+    }
+
+    /// Do an initial pass on a whole document, which is split up into lines at each \n.
+    ///
+    /// Line index `y` in the highlighter refers to the text following the `y`th \n
+    /// (or the start of the document when `y` is 0). When the document ends in \n, the empty
+    /// line after it is kept or dropped depending on `trailing_newline` (see [TrailingNewline]).
+    pub fn run_text(&mut self, text: &str) {
+        self.run_iter(text.split('\n'));
+    }
+
+    /// The same as [Highlighter::run], but lines are atomized in parallel (using rayon)
+    /// before the bounded tokens are resolved in a single pass.
+    /// On machines with more than one core, this makes the initial pass on large files
+    /// much faster. When rayon only has one thread to work with, this falls back to
+    /// [Highlighter::run], as splitting up the work would only slow it down.
+    #[cfg(feature = "rayon")]
+    pub fn run_parallel(&mut self, lines: &[String]) {
+        use rayon::prelude::*;
+        if rayon::current_num_threads() == 1 {
+            self.run(lines);
+            return;
+        }
+        let lines = match lines.split_last() {
+            Some((last, rest)) if self.drops_line(last) => rest,
+            _ => lines,
+        };
+        let atoms = (0..lines.len()).into_par_iter().map(|y| Arc::new(self.atomize_within(lines, y))).collect();
+        self.atoms = Arc::new(atoms);
+        self.texts = Arc::new(if self.grammar.reach() > 0 { lines.to_vec() } else { vec![] });
+        self.tokenize();
+    }
+
+    /// Works out if a final line should be left out, as per the trailing newline policy
+    fn drops_line(&self, line: &str) -> bool {
+        self.trailing_newline == TrailingNewline::Drop && line.is_empty()
+    }
+
+    /// Appends a line to the highlighter.
+    pub fn append<S: AsRef<str>>(&mut self, line: S) {
+        let line = line.as_ref();
+        // The lines before this one can have multi-line keywords that continue onto it
+        if self.grammar.reach() > 0 {
+            self.insert_line(self.atoms.len(), line);
+            return;
+        }
+        // Atomize this line
+        let atoms = Arc::new(self.atomize(line));
+        Arc::make_mut(&mut self.atoms).push(atoms);
+        Arc::make_mut(&mut self.line_ref).push(vec![]);
+        let state = self.tokenizer_state();
+        Arc::make_mut(&mut self.line_states).push(state);
+        self.tokenize_line(self.atoms.len().saturating_sub(1));
+    }
+
+    /// Appends several lines to the highlighter, from any iterator of lines.
+    /// As with run, a final empty line is dropped if `trailing_newline` says so.
+    pub fn append_lines<I: IntoIterator<Item = S>, S: AsRef<str>>(&mut self, lines: I) {
+        let mut lines = lines.into_iter().peekable();
+        while let Some(line) = lines.next() {
+            if lines.peek().is_none() && self.drops_line(line.as_ref()) { break; }
+            self.append(line);
+        }
+    }
+
+    /// Highlight just enough of a document to display the lines in a viewport,
+    /// so that opening a huge file doesn't have to wait for the whole file to be highlighted.
+    ///
+    /// Whether a line starts inside a bounded token (e.g. a multi-line comment) depends on
+    /// every line before it, so lines are highlighted from the top of the document down to the
+    /// end of the viewport. Lines that have already been highlighted are kept, so calling this
+    /// again as the viewport scrolls down only highlights the lines that are newly visible,
+    /// and scrolling back up costs nothing. Use [Highlighter::run] to start again on a new document.
+    ///
+    /// Lines below the highlighted part of the document are displayed as plain text,
+    /// and edits to them are ignored until they are highlighted.
+    ///
+    /// ```
+    /// # use synoptic::from_extension;
+    /// let mut h = from_extension("rs", 4).unwrap();
+    /// let code: Vec<String> = (0..100_000).map(|i| format!("let x = {i};")).collect();
+    /// h.run_viewport(&code, 0..50);
+    /// assert_eq!(h.highlighted_lines(), 50);
+    /// h.run_viewport(&code, 20..70);
+    /// assert_eq!(h.highlighted_lines(), 70);
+    /// ```
+    pub fn run_viewport(&mut self, lines: &[String], viewport: Range<usize>) {
+        let end = viewport.end.min(lines.len());
+        while self.atoms.len() < end {
+            let y = self.atoms.len();
+            if y + 1 == lines.len() && self.drops_line(&lines[y]) { break; }
+            self.append(&lines[y]);
+        }
+    }
+
+    /// The number of lines, from the top of the document, that have been highlighted
+    pub fn highlighted_lines(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// Once you have called the run or append methods, you can use this function
+    /// to retrieve individual lines by providing the original line text and the y index.
+    /// The line text can be anything that can be referenced as a str (e.g. String, &str, Cow<str>).
+    ///
+    /// # Example
+    /// ```
+    /// # use synoptic::Highlighter;
+    /// let mut highlighter = Highlighter::new(4); // Tab ('\t') has a display width of 4
+    /// highlighter.keyword("kw", "keyword"); // All occurances of "keyword" will be classed as a token of "kw"
+    /// highlighter.run(&vec![
+    ///     "this is a keyword".to_string(), 
+    ///     "second line!".to_string()
+    /// ]);
+    /// // Get the TokOpt for the first line
+    /// highlighter.line(0, "this is a keyword");
+    /// // Get the TokOpt for the second line
+    /// highlighter.line(1, "second line!");
+    /// ```
+    ///
+    /// Tabs are expanded into spaces, unless `lossless` is enabled (see [Highlighter::lossless]).
+    /// A byte order mark at the start of the line and a carriage return at the end of the line
+    /// are left out, unless `lossless` is enabled, where they are included as plain text.
+    ///
+    /// Matches of any overlays (see [Highlighter::overlay]) are shown on top of the syntax tokens.
+    /// Diagnostics (see [Highlighter::set_diagnostics]) are not shown here,
+    /// use [Highlighter::line_with_modifiers] to get them along with the tokens.
+    ///
+    /// The tokens of each line are cached until the line or the tokens on it change,
+    /// so redrawing lines that haven't changed is cheap.
+    pub fn line<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<TokOpt> {
+        let line = line.as_ref();
+        let tokens = self.syntax_line(y, line);
+        if self.overlays.is_empty() { return tokens; }
+        let mut result: Vec<TokOpt> = vec![];
+        for (tok, names) in overlay(&tokens, &self.overlay_ranges(line), self.tab_width) {
+            match (names.last(), result.last_mut()) {
+                // Join up the pieces of an overlay match that covers more than one token
+                (Some(name), Some(TokOpt::Some(text, last))) if last == name => text.push_str(tok.text()),
+                (Some(name), _) => result.push(TokOpt::Some(tok.text().clone(), name.clone())),
+                (None, _) => result.push(tok),
+            }
+        }
+        result
+    }
+
+    /// The same as [Highlighter::line], but without any overlays
+    fn syntax_line(&self, y: usize, line: &str) -> Vec<TokOpt> {
+        let mut hasher = DefaultHasher::new();
+        (line, self.tab_width, self.lossless).hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(tokens) = self.cache.get(y, key) { return tokens; }
+        let tokens = self.render(y, line, self.lossless);
+        self.cache.set(y, key, &tokens);
+        tokens
+    }
+
+    /// Add an overlay, which shows matches of a regex on top of the syntax highlighting
+    /// in [Highlighter::line], for example to show search results or misspelled words.
+    /// Overlays added later take priority over earlier ones where they overlap.
+    /// ```
+    /// # use synoptic::{Highlighter, TokOpt};
+    /// let mut h = Highlighter::new(4);
+    /// h.keyword("keyword", r"\bfn\b");
+    /// h.run(&vec!["fn main".to_string()]);
+    /// h.overlay("search", "n ma");
+    /// let names: Vec<_> = h.line(0, "fn main").iter().map(|t| match t {
+    ///     TokOpt::Some(text, name) => format!("{name}:{text}"),
+    ///     TokOpt::None(text) => text.clone(),
+    /// }).collect();
+    /// assert_eq!(names, vec!["keyword:f", "search:n ma", "in"]);
+    /// h.clear_overlays();
+    /// assert_eq!(h.line(0, "fn main").len(), 2);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the regex is invalid, see [Highlighter::try_overlay] for a version that doesn't
+    pub fn overlay<S: Into<String>>(&mut self, name: S, exp: &str) {
+        self.try_overlay(name, exp).unwrap();
+    }
+
+    /// Add an overlay, returning an error if the regex is invalid
+    pub fn try_overlay<S: Into<String>>(&mut self, name: S, exp: &str) -> Result<(), SyntaxError> {
+        self.overlays.push((name.into(), compile(exp)?));
+        Ok(())
+    }
+
+    /// Remove all overlays
+    pub fn clear_overlays(&mut self) {
+        self.overlays.clear();
+    }
+
+    /// Find the display ranges of overlay matches on a line, along with the overlay names
+    fn overlay_ranges(&self, line: &str) -> Vec<(Range<usize>, String)> {
+        let (_, line, _) = split_markers(line);
+        let mut result = vec![];
+        for (name, exp) in &self.overlays {
+            for m in exp.get().find_iter(line).filter(|m| !m.is_empty()) {
+                let start = width(&line[..m.start()], self.tab_width);
+                let end = start + width(m.as_str(), self.tab_width);
+                result.push((start..end, name.clone()));
+            }
+        }
+        result
+    }
+
+    /// The same as [Highlighter::line], but tokens always contain the raw text of the line
+    /// (as if `lossless` were enabled), which is what you want when copying text or feeding it
+    /// back into edits. Each token comes with the display width it takes up when rendered.
+    pub fn line_raw<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<(TokOpt, usize)> {
+        self.render(y, line.as_ref(), true)
+            .into_iter()
+            .map(|tok| {
+                // Carriage returns aren't displayed
+                let wid = width(tok.text().trim_end_matches('\r'), self.tab_width);
+                (tok, wid)
+            })
+            .collect()
+    }
+
+    /// Find where each token of a line lies within the original text of the line,
+    /// as both byte and character ranges. Plain text is included with a name of None,
+    /// so the spans cover the whole line, which is handy for filling in style buffers.
+    /// ```
+    /// # use synoptic::Highlighter;
+    /// let mut h = Highlighter::new(4);
+    /// h.keyword("keyword", r"\bfn\b");
+    /// h.run(&vec!["\tfn é".to_string()]);
+    /// let spans = h.line_spans(0, "\tfn é");
+    /// assert_eq!(spans[1].range, 1..3);
+    /// assert_eq!(spans[2].range, 3..6);
+    /// assert_eq!(spans[2].char_range, 3..5);
+    /// ```
+    pub fn line_spans<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<Span> {
+        let mut result = vec![];
+        let (mut byte, mut chr) = (0, 0);
+        for tok in self.render(y, line.as_ref(), true) {
+            let (text, name) = match tok {
+                TokOpt::Some(text, name) => (text, Some(name)),
+                TokOpt::None(text) => (text, None),
+            };
+            let (bytes, chars) = (text.len(), text.chars().count());
+            result.push(Span { range: byte..byte + bytes, char_range: chr..chr + chars, name });
+            byte += bytes;
+            chr += chars;
+        }
+        result
+    }
+
+    /// The same as [Highlighter::line], but rather than allocating text and names for each token,
+    /// this gives the byte range of each token within the line along with its interned name
+    /// (None for plain text), which is cheaper for renderers that redraw often.
+    /// Use [Highlighter::kind_name] to turn ids back into names.
+    /// ```
+    /// # use synoptic::Highlighter;
+    /// let mut h = Highlighter::new(4);
+    /// h.keyword("keyword", r"\bfn\b");
+    /// h.run(&vec!["fn main".to_string()]);
+    /// let ids = h.line_ids(0, "fn main");
+    /// assert_eq!(ids[0].0, 0..2);
+    /// assert_eq!(h.kind_name(ids[0].1.unwrap()), Some("keyword"));
+    /// assert_eq!(ids[1], (2..7, None));
+    /// ```
+    pub fn line_ids<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<(Range<usize>, Option<TokenKind>)> {
+        let full = line.as_ref();
+        let (bom, line, _) = split_markers(full);
+        let len: usize = line.chars().map(|c| self.expanded_len(c)).sum();
+        let registry = self.registry(y, len);
+        let mut result: Vec<(Range<usize>, Option<TokenKind>)> = vec![];
+        let mut chars = line.char_indices().map(|(i, c)| (i + bom.len(), c)).peekable();
+        let mut x = 0;
+        while let Some(&(start, c)) = chars.peek() {
+            if let Some((end, token)) = registry.get(&x).filter(|(end, _)| *end > x) {
+                // Process token
+                let mut stop = start;
+                while x < *end {
+                    let Some((i, c)) = chars.next() else { break };
+                    stop = i + c.len_utf8();
+                    x += self.expanded_len(c);
+                }
+                result.push((start..stop, Some(self.token_kind(token))));
+            } else {
+                // Process plain text
+                chars.next();
+                let stop = start + c.len_utf8();
+                match result.last_mut() {
+                    Some((range, None)) => range.end = stop,
+                    _ => result.push((start..stop, None)),
+                }
+                x += self.expanded_len(c);
+            }
+        }
+        // Line markers count as plain text
+        let end = bom.len() + line.len();
+        match result.first_mut() {
+            Some((range, None)) => range.start = 0,
+            _ if !bom.is_empty() => result.insert(0, (0..bom.len(), None)),
+            _ => (),
+        }
+        match result.last_mut() {
+            Some((range, None)) => range.end = full.len(),
+            _ if end < full.len() => result.push((end..full.len(), None)),
+            _ => (),
+        }
+        result
+    }
+
+    /// The same as [Highlighter::line], but tokens borrow their text from the line and their
+    /// names from the highlighter, so no strings are allocated, which suits render loops that
+    /// redraw often. Like [Highlighter::line_raw], the text is left as it is, tabs included,
+    /// and overlays aren't applied.
+    /// ```
+    /// # use synoptic::{Highlighter, TokSpan};
+    /// let mut h = Highlighter::new(4);
+    /// h.keyword("keyword", r"\bfn\b");
+    /// h.run(&vec!["\tfn main".to_string()]);
+    /// let tokens: Vec<TokSpan> = h.line_refs(0, "\tfn main").collect();
+    /// assert_eq!(tokens[1].text, "fn");
+    /// assert_eq!(tokens[1].name, Some("keyword"));
+    /// assert_eq!(tokens[2].name, None);
+    /// ```
+    pub fn line_refs<'a>(&'a self, y: usize, line: &'a str) -> impl Iterator<Item = TokSpan<'a>> + 'a {
+        self.line_ids(y, line).into_iter().map(move |(range, kind)| TokSpan {
+            text: &line[range],
+            name: kind.and_then(|kind| self.kind_name(kind)),
+            kind,
+        })
+    }
+
+    /// Find the interned name of a token
+    fn token_kind(&self, token: &TokenRef) -> TokenKind {
+        let loc = token_start(token);
+        self.atoms[loc.y][loc.x].id
+    }
+
+    /// Find the interned id of a token name, see [Grammar::kind_id]
+    pub fn kind_id(&self, name: &str) -> Option<TokenKind> {
+        self.grammar.kind_id(name)
+    }
+
+    /// Find the name of a token from its interned id, see [Grammar::kind_name]
+    pub fn kind_name(&self, kind: TokenKind) -> Option<&str> {
+        self.grammar.kind_name(kind)
+    }
+
+    /// Work out where each token on a line starts, along with where it ends.
+    /// `len` is the length of the line once tabs are expanded
+    fn registry(&self, y: usize, len: usize) -> HashMap<usize, (usize, &TokenRef)> {
+        let mut registry: HashMap<usize, (usize, &TokenRef)> = HashMap::default();
+        // Lines that haven't been highlighted yet are plain text
+        for token in self.line_ref.get(y).into_iter().flatten().map(|t| &self.tokens[*t]) {
+            match token {
+                // Register bounded token
+                TokenRef::Bounded { start, end, .. } => {
+                    let start = if start.y != y { 0 } else { self.atoms[start.y][start.x].x.start };
+                    let end = end.clone()
+                        .map(|end| if end.y != y { len } else { self.atoms[end.y][end.x].x.end })
+                        .unwrap_or(len);
+                    registry.insert(start, (end, token));
+                }
+                // Register keyword token
+                TokenRef::Keyword { atom, .. } => {
+                    let start = self.atoms[atom.y][atom.x].x.start;
+                    let end = self.atoms[atom.y][atom.x].x.end;
+                    registry.insert(start, (end, token));
+                }
+            }
+        }
+        registry
+    }
+
+    /// Turn a line into tokens, optionally keeping the line's text exactly as it is
+    fn render(&self, y: usize, line: &str, lossless: bool) -> Vec<TokOpt> {
+        let (bom, line, cr) = split_markers(line);
+        let len: usize = line.chars().map(|c| self.expanded_len(c)).sum();
+        let mut result = vec![];
+        let registry = self.registry(y, len);
+        // Process tokens into TokOpt format
+        let mut chars = line.chars().peekable();
+        let mut x = 0;
+        while let Some(&c) = chars.peek() {
+            let token = registry.get(&x).filter(|(end, _)| *end > x);
+            if let Some((end, TokenRef::Bounded { name, .. } | TokenRef::Keyword { name, .. })) = token {
+                // Process token
+                let mut text = String::new();
+                while x < *end {
+                    let Some(c) = chars.next() else { break };
+                    self.push_char(&mut text, c, lossless);
+                    x += self.expanded_len(c);
+                }
+                result.push(TokOpt::Some(text, name.clone()));
+            } else {
+                // Process plain text
+                chars.next();
+                if let Some(TokOpt::None(ref mut s)) = result.last_mut() {
+                    self.push_char(s, c, lossless);
+                } else {
+                    let mut s = String::new();
+                    self.push_char(&mut s, c, lossless);
+                    result.push(TokOpt::None(s));
+                }
+                x += self.expanded_len(c);
+            }
+        }
+        // Restore line markers as plain text
+        if lossless {
+            match result.first_mut() {
+                Some(TokOpt::None(text)) => text.insert_str(0, bom),
+                _ if !bom.is_empty() => result.insert(0, TokOpt::None(bom.to_string())),
+                _ => (),
+            }
+            match result.last_mut() {
+                Some(TokOpt::None(text)) => text.push_str(cr),
+                _ if !cr.is_empty() => result.push(TokOpt::None(cr.to_string())),
+                _ => (),
+            }
+        }
+        result
+    }
+
+    /// The number of characters a character takes up once tabs are expanded
+    /// (this is the unit that atom indices are measured in)
+    fn expanded_len(&self, c: char) -> usize {
+        if c == '\t' { self.tab_width } else { 1 }
+    }
+
+    /// Push a character onto token text, expanding tabs unless in lossless mode
+    fn push_char(&self, text: &mut String, c: char, lossless: bool) {
+        if c == '\t' && !lossless {
+            text.push_str(&" ".repeat(self.tab_width));
+        } else {
+            text.push(c);
+        }
+    }
+
+    /// Works out which display columns of a line have changed (in either text or kind) compared
+    /// to a previous render of the line, allowing renderers to only repaint what has changed.
+    pub fn line_diff<S: AsRef<str>>(&self, y: usize, line: S, previous: &[TokOpt]) -> Vec<Range<usize>> {
+        let line = line.as_ref();
+        diff_tokens(previous, &self.line(y, line), self.tab_width)
+    }
+
+    /// Find the name of the bounded token (e.g. a block comment or multiline string) that
+    /// is still open at the start of a line, if there is one. This is handy for showing
+    /// indicators in the gutter, or for working out whether a line is already commented out.
+    /// A line that starts within an interpolation is still within the token around it.
+    /// ```
+    /// # use synoptic::Highlighter;
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded("comment", r"/\*", r"\*/", false);
+    /// h.run(&vec!["a /* b".to_string(), "c".to_string(), "d */ e".to_string(), "f".to_string()]);
+    /// assert_eq!(h.state_at(0), None);
+    /// assert_eq!(h.state_at(1), Some("comment"));
+    /// assert_eq!(h.state_at(2), Some("comment"));
+    /// assert_eq!(h.state_at(3), None);
+    /// ```
+    pub fn state_at(&self, y: usize) -> Option<&str> {
+        self.line_states.get(y)?.state?;
+        // Tokens can't start within the open token, other than keywords within interpolation
+        let before = self.tokens.partition_point(|t| token_start(t).y < y);
+        self.tokens[..before].iter().rev().find_map(|token| match token {
+            TokenRef::Bounded { name, .. } => Some(name.as_str()),
+            TokenRef::Keyword { .. } => None,
+        })
+    }
+
+    /// Summarises each line of the document, allowing editors to draw annotations
+    /// (e.g. in the scrollbar) without querying every line on every frame
+    /// ```
+    /// # use synoptic::Highlighter;
+    /// let mut h = Highlighter::new(4);
+    /// h.keyword("comment", "(#.*)$");
+    /// h.keyword("comment.doc", "(//.*)$");
+    /// h.overlay("search", "needle");
+    /// h.overlay("cursor", "a");
+    /// let lines = vec!["# TODO".to_string(), "needle a needle".to_string(), "b // FIXME".to_string()];
+    /// h.run(&lines);
+    /// let summary = h.line_summary(&lines);
+    /// assert!(summary[0].todo);
+    /// assert!(summary[2].todo);
+    /// assert_eq!(summary[1].overlays, vec!["cursor".to_string(), "search".to_string()]);
+    /// assert!(summary[2].overlays.is_empty());
+    /// ```
+    pub fn line_summary(&self, lines: &[String]) -> Vec<LineSummary> {
+        let mut result: Vec<LineSummary> = lines.iter()
+            .take(self.line_ref.len())
+            .enumerate()
+            .map(|(y, line)| LineSummary {
+                todo: self.syntax_line(y, line).iter().any(|tok| matches!(
+                    tok,
+                    TokOpt::Some(text, kind) if (kind == "comment" || kind.starts_with("comment.")) && (text.contains("TODO") || text.contains("FIXME"))
+                )),
+                diagnostic: self.diagnostics.get(y).and_then(|d| d.iter().map(|(_, s)| *s).min()),
+                starts_multiline: false,
+                overlays: {
+                    let mut names: Vec<String> = self.overlay_ranges(line).into_iter().map(|(_, name)| name).collect();
+                    names.sort();
+                    names.dedup();
+                    names
+                },
+            })
+            .collect();
+        for token in self.tokens.iter() {
+            if let TokenRef::Bounded { start, end, .. } = token {
+                let multiline = end.as_ref().is_none_or(|end| end.y != start.y);
+                if let Some(summary) = result.get_mut(start.y).filter(|_| multiline) {
+                    summary.starts_multiline = true;
+                }
+            }
+        }
+        result
+    }
+
+    /// Works out the regions of the document that can be folded, which are bounded tokens
+    /// (e.g. multiline comments) that span more than one line, and regions between fold
+    /// markers (see [Grammar::fold]) that span more than one line.
+    /// Regions are ordered by the line they start on, with outer regions first
+    /// ```
+    /// # use synoptic::{Highlighter, Fold};
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded("comment", r"/\*", r"\*/", false);
+    /// h.bounded("string", "\"", "\"", true);
+    /// h.fold("block", r"\{", r"\}");
+    /// h.run(&["/* a".to_string(), "b */ fn main() {".to_string(), "\"}\"".to_string(), "}".to_string()]);
+    /// assert_eq!(h.fold_ranges(), vec![
+    ///     Fold { start: 0, end: 1, name: "comment".to_string() },
+    ///     Fold { start: 1, end: 3, name: "block".to_string() },
+    /// ]);
+    /// ```
+    pub fn fold_ranges(&self) -> Vec<Fold> {
+        let mut result = vec![];
+        // Bounded tokens that span lines, those that never end carry on for as long as they appear
+        for (idx, token) in self.tokens.iter().enumerate() {
+            if let TokenRef::Bounded { name, start, end } = token {
+                let end = end.as_ref().map_or_else(
+                    || (start.y..self.line_ref.len()).take_while(|y| self.line_ref[*y].contains(&idx)).last().unwrap_or(start.y),
+                    |end| end.y,
+                );
+                if end > start.y {
+                    result.push(Fold { start: start.y, end, name: name.clone() });
+                }
+            }
+        }
+        // Regions between fold markers that aren't within bounded tokens
+        let mut open: Vec<(&str, usize)> = vec![];
+        for (y, atoms) in self.atoms.iter().enumerate() {
+            let covered = self.bounded_ranges(y);
+            for atom in atoms.iter() {
+                if !self.atom_applies(y, atom, &covered) { continue; }
+                match atom.kind {
+                    AtomKind::FoldStart => open.push((&atom.name, y)),
+                    AtomKind::FoldEnd => {
+                        // Close the innermost region with the same name, and any left open within it
+                        if let Some(idx) = open.iter().rposition(|(name, _)| *name == atom.name) {
+                            let start = open[idx].1;
+                            open.truncate(idx);
+                            if y > start {
+                                result.push(Fold { start, end: y, name: atom.name.clone() });
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        result.extend(self.indent_scopes());
+        result.sort_by_key(|f| (f.start, std::cmp::Reverse(f.end)));
+        result
+    }
+
+    /// Works out the indentation scopes in the document (see [Grammar::indent_scope])
+    /// that cover at least one line after the one they start on, ordered by where they start
+    /// ```
+    /// # use synoptic::{Highlighter, Fold};
+    /// let mut h = Highlighter::new(4);
+    /// h.indent_scope("block", r"^\s*def .*:$");
+    /// let lines = ["def f():", "    x = 1", "", "    return x", "y = f()"];
+    /// h.run(&lines.map(String::from));
+    /// assert_eq!(h.indent_scopes(), vec![Fold { start: 0, end: 3, name: "block".to_string() }]);
+    /// ```
+    pub fn indent_scopes(&self) -> Vec<Fold> {
+        // Blank lines and lines that carry on a bounded token don't have an indentation level
+        let level = |y: usize| {
+            let continued = self.line_states.get(y).is_some_and(|s| s.state.is_some() && !s.interp);
+            let indent = self.atoms[y].iter().find(|a| a.kind == AtomKind::Indent);
+            indent.filter(|_| !continued).map(|a| a.x.end)
+        };
+        let mut result = vec![];
+        for (y, atoms) in self.atoms.iter().enumerate() {
+            let covered = self.bounded_ranges(y);
+            let scope = atoms.iter().find(|a| a.kind == AtomKind::Scope && self.atom_applies(y, a, &covered));
+            let indent = atoms.iter().find(|a| a.kind == AtomKind::Indent).map(|a| a.x.end);
+            let (Some(scope), Some(indent)) = (scope, indent) else { continue };
+            // The scope carries on until a line that is indented no further than where it starts
+            let mut end = y;
+            for k in y + 1..self.atoms.len() {
+                match level(k) {
+                    Some(level) if level <= indent => break,
+                    Some(_) => end = k,
+                    None => (),
+                }
+            }
+            if end > y {
+                result.push(Fold { start: y, end, name: scope.name.clone() });
+            }
+        }
+        result
+    }
+
+    /// Works out how indentation should change around a line, using the indent and outdent
+    /// patterns of the grammar (see [Grammar::indent] and [Grammar::outdent]).
+    /// Editors can use this to indent a new line after the user presses enter,
+    /// or to outdent a line as soon as the user types a closing bracket at the start of it.
+    /// ```
+    /// # use synoptic::{Highlighter, IndentChange};
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded("string", "\"", "\"", true);
+    /// h.keyword("comment", "//.*$");
+    /// h.indent(r"\{\s*$");
+    /// h.outdent(r"^\s*\}");
+    /// let lines = ["fn main() {", "    let x = \"{\"; // {", "} else {"];
+    /// h.run(&lines.map(String::from));
+    /// assert_eq!(h.indent_hint(0, lines[0]), IndentChange { indent: true, outdent: false });
+    /// assert_eq!(h.indent_hint(1, lines[1]), IndentChange::default());
+    /// assert_eq!(h.indent_hint(2, lines[2]), IndentChange { indent: true, outdent: true });
+    /// ```
+    pub fn indent_hint<S: AsRef<str>>(&self, y: usize, line: S) -> IndentChange {
+        let (_, line, _) = split_markers(line.as_ref());
+        // Leave out anything within a comment or string, such as a commented out {
+        let covered: Vec<Range<usize>> = self.registry(y, usize::MAX).into_iter()
+            .filter(|(_, (_, token))| match token {
+                TokenRef::Bounded { .. } => true,
+                TokenRef::Keyword { name, .. } => self.grammar.prose_kinds.contains(name),
+            })
+            .map(|(start, (end, _))| start..end)
+            .collect();
+        let mapping = create_mapping(line, self.tab_width);
+        let matches = |rules: &[LazyRegex]| rules.iter().any(|exp| {
+            find_all_mapped(exp.get(), line, &mapping).iter().any(|m| !covered.iter().any(|r| r.contains(&m.start)))
+        });
+        IndentChange {
+            indent: matches(&self.grammar.indents),
+            outdent: matches(&self.grammar.outdents),
+        }
+    }
+
+    /// The ranges of a line that are covered by bounded tokens
+    fn bounded_ranges(&self, y: usize) -> Vec<Range<usize>> {
+        self.registry(y, usize::MAX).into_iter()
+            .filter(|(_, (_, token))| matches!(token, TokenRef::Bounded { .. }))
+            .map(|(start, (end, _))| start..end)
+            .collect()
+    }
+
+    /// Determine whether a structural atom (e.g. a fold marker) counts, which it doesn't if it
+    /// is within a bounded token, or belongs to an embedded grammar that isn't in use on its line
+    fn atom_applies(&self, y: usize, atom: &Atom, covered: &[Range<usize>]) -> bool {
+        let region = self.line_states.get(y).and_then(|s| s.region);
+        let foreign = atom.region.is_some() && atom.region != region;
+        !foreign && !covered.iter().any(|r| r.contains(&atom.x.start))
+    }
+
+    /// Downsamples the document into a grid of at most `rows` by `cols` cells,
+    /// where each cell holds the dominant kind of token within it.
+    /// This is suitable for rendering a minimap strip next to a document.
+    ///
+    /// When there are more lines than rows, lines are grouped into buckets,
+    /// and when lines are wider than `cols`, display columns are grouped together.
+    pub fn minimap(&self, lines: &[String], rows: usize, cols: usize) -> Vec<Vec<MinimapCell>> {
+        if rows == 0 || cols == 0 {
+            return vec![];
+        }
+        let rendered: Vec<Vec<TokOpt>> = lines.iter()
+            .take(self.line_ref.len())
+            .enumerate()
+            .map(|(y, line)| self.line(y, line))
+            .collect();
+        let grid: Vec<_> = rendered.iter().map(|tokens| cells(tokens, self.tab_width)).collect();
+        let max_width = grid.iter().map(Vec::len).max().unwrap_or(0);
+        let row_scale = grid.len().div_ceil(rows).max(1);
+        let col_scale = max_width.div_ceil(cols).max(1);
+        grid.chunks(row_scale).map(|bucket| {
+            (0..cols).map(|col| {
+                // Count up the kinds of non-whitespace text in this cell
+                let mut counts: Vec<(Option<&str>, usize)> = vec![];
+                for line in bucket {
+                    for (text, kind) in line.iter().skip(col * col_scale).take(col_scale) {
+                        if text.trim().is_empty() { continue; }
+                        match counts.iter_mut().find(|(k, _)| k == kind) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((*kind, 1)),
+                        }
+                    }
+                }
+                // Find the most common kind (favouring the first to appear)
+                let dominant = counts.iter()
+                    .fold(None, |best: Option<&(Option<&str>, usize)>, c| match best {
+                        Some(b) if b.1 >= c.1 => Some(b),
+                        _ => Some(c),
+                    });
+                match dominant {
+                    None => MinimapCell::Empty,
+                    Some((None, _)) => MinimapCell::Text,
+                    Some((Some(kind), _)) => MinimapCell::Token(kind.to_string()),
+                }
+            }).collect()
+        }).collect()
+    }
+
+    /// Creates a mapping between the character indices of a line and display columns,
+    /// taking into account tab width and wide characters.
+    /// This is useful for indexing back into the original text from rendered tokens.
+    pub fn display_map<S: AsRef<str>>(&self, line: S) -> ColumnMap {
+        let line = line.as_ref();
+        ColumnMap::new(line, self.tab_width)
+    }
+
+    /// Finds the regions of a line that contain human-readable text, such as comments and strings.
+    /// This is useful for running a spell checker over only the prose in a document.
+    ///
+    /// Returns a list of display column ranges along with the kind of text they contain,
+    /// plain text is reported with the kind "text" when `prose_plain` is enabled.
+    pub fn prose_regions<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<(Range<usize>, &str)> {
+        let line = line.as_ref();
+        let mut result = vec![];
+        let mut at = 0;
+        for token in self.syntax_line(y, line) {
+            let wid = width(token.text(), self.tab_width);
+            let kind = match &token {
+                TokOpt::Some(_, name) => self.grammar.prose_kinds.iter().find(|k| *k == name).map(String::as_str),
+                TokOpt::None(_) => if self.grammar.prose_plain { Some("text") } else { None },
+            };
+            if let Some(kind) = kind {
+                result.push((at..at + wid, kind));
+            }
+            at += wid;
+        }
+        result
+    }
+
+    /// Works out the tokens for each line covered by a selection, where each token is
+    /// flagged with whether or not it lies within the selection.
+    ///
+    /// The x values of the start and end locations are display columns (as in [Highlighter::line])
+    /// and the end location is exclusive. Tokens that are only partially selected are split
+    /// so that renderers can apply a background colour while keeping the syntax colours.
+    /// The result contains one entry for each line from start.y to end.y
+    pub fn selection(&self, lines: &[String], start: &Loc, end: &Loc) -> Vec<Vec<(TokOpt, bool)>> {
+        (start.y..=end.y)
+            .filter_map(|y| lines.get(y).map(|line| (y, line)))
+            .map(|(y, line)| {
+                let tokens = self.line(y, line);
+                let from = if y == start.y { start.x } else { 0 };
+                let to = if y == end.y { end.x } else { usize::MAX };
+                select(&tokens, from..to, self.tab_width)
+            })
+            .collect()
+    }
+
+    /// Finds the bracket that pairs up with the bracket at a location, if there is one.
+    ///
+    /// Brackets are the pairs in [Grammar::brackets], and x values are display columns
+    /// (as in [Highlighter::line]). Brackets within strings and comments (or any other token
+    /// in [Grammar::prose_kinds]) only pair up with brackets in the same kind of token,
+    /// so that a ")" in a string doesn't close a bracket in the code around it.
+    /// ```
+    /// # use synoptic::{Highlighter, Loc};
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded("string", "\"", "\"", true);
+    /// let lines = vec!["f(\")\", (1))".to_string()];
+    /// h.run(&lines);
+    /// assert_eq!(h.matching_bracket(&lines, &Loc { y: 0, x: 1 }), Some(Loc { y: 0, x: 10 }));
+    /// assert_eq!(h.matching_bracket(&lines, &Loc { y: 0, x: 9 }), Some(Loc { y: 0, x: 7 }));
+    /// ```
+    pub fn matching_bracket(&self, lines: &[String], at: &Loc) -> Option<Loc> {
+        let here = self.brackets_on_line(at.y, lines.get(at.y)?);
+        let (_, c, context) = here.iter().find(|(x, _, _)| *x == at.x)?.clone();
+        // Work out which way to search, and which brackets to look out for
+        let (forward, (start, stop)) = match self.grammar.brackets.iter().find(|(open, _)| *open == c) {
+            Some(&pair) => (true, pair),
+            None => {
+                let &(open, close) = self.grammar.brackets.iter().find(|(_, close)| *close == c)?;
+                (false, (close, open))
+            }
+        };
+        let ys: Vec<usize> = if forward { (at.y..lines.len()).collect() } else { (0..=at.y).rev().collect() };
+        let mut depth = 0;
+        for y in ys {
+            let mut brackets = if y == at.y { here.clone() } else { self.brackets_on_line(y, &lines[y]) };
+            if y == at.y {
+                brackets.retain(|(x, _, _)| if forward { *x > at.x } else { *x < at.x });
+            }
+            if !forward { brackets.reverse(); }
+            for (x, c, ctx) in brackets {
+                if ctx != context { continue; }
+                if c == start {
+                    depth += 1;
+                } else if c == stop {
+                    if depth == 0 { return Some(Loc { y, x }); }
+                    depth -= 1;
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the brackets on a line, along with their display column,
+    /// and the kind of prose token they are in (if any)
+    fn brackets_on_line(&self, y: usize, line: &str) -> Vec<(usize, char, Option<String>)> {
+        let mut result = vec![];
+        let mut x = 0;
+        for token in self.syntax_line(y, line) {
+            let context = match &token {
+                TokOpt::Some(_, name) if self.grammar.prose_kinds.contains(name) => Some(name.clone()),
+                _ => None,
+            };
+            for cluster in token.text().graphemes(true) {
+                let mut chars = cluster.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    if self.grammar.brackets.iter().any(|(open, close)| c == *open || c == *close) {
+                        result.push((x, c, context.clone()));
+                    }
+                }
+                x += width(cluster, self.tab_width);
+            }
+        }
+        result
+    }
+
+    /// The same as [Highlighter::line], but each token is flagged with whether or not it lies
+    /// within any of the given selection ranges, which are display columns with exclusive ends.
+    /// Tokens are split at the edges of each selection, so renderers can draw a selection
+    /// background while keeping the syntax colours, including with multiple cursors.
+    /// ```
+    /// # use synoptic::{Highlighter, TokOpt};
+    /// let mut h = Highlighter::new(4);
+    /// h.keyword("keyword", r"\bfn\b");
+    /// h.run(&vec!["fn main".to_string()]);
+    /// let tokens = h.line_with_selection(0, "fn main", &[1..4]);
+    /// assert_eq!(tokens[0].0.text(), "f");
+    /// assert!(!tokens[0].1);
+    /// assert!(matches!(&tokens[1], (TokOpt::Some(text, _), true) if text == "n"));
+    /// assert!(matches!(&tokens[2], (TokOpt::None(text), true) if text == " m"));
+    /// assert!(matches!(&tokens[3], (TokOpt::None(text), false) if text == "ain"));
+    /// ```
+    pub fn line_with_selection<S: AsRef<str>>(&self, y: usize, line: S, ranges: &[Range<usize>]) -> Vec<(TokOpt, bool)> {
+        let tokens = self.line(y, line);
+        let ranges: Vec<(Range<usize>, ())> = ranges.iter().map(|r| (r.clone(), ())).collect();
+        overlay(&tokens, &ranges, self.tab_width)
+            .into_iter()
+            .map(|(tok, covered)| (tok, !covered.is_empty()))
+            .collect()
+    }
+
+    /// Set the diagnostics for a line, replacing any that were there before.
+    /// Ranges are display indices (as in [Highlighter::line]).
+    /// Diagnostics don't change the output of [Highlighter::line],
+    /// they are given as modifiers by [Highlighter::line_with_modifiers].
+    pub fn set_diagnostics(&mut self, y: usize, diagnostics: Vec<(Range<usize>, Severity)>) {
+        let all = Arc::make_mut(&mut self.diagnostics);
+        if all.len() <= y {
+            all.resize(y + 1, vec![]);
+        }
+        all[y] = diagnostics;
+    }
+
+    /// Remove all diagnostics from the document
+    pub fn clear_diagnostics(&mut self) {
+        self.diagnostics = Arc::default();
+    }
+
+    /// The same as [Highlighter::line], but each token comes with a list of modifiers
+    /// (such as diagnostics and overlays) that apply to it, and keeps its syntax name
+    /// where overlays match. Tokens are split where a modifier starts or ends part way through them.
+    pub fn line_with_modifiers<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<(TokOpt, Vec<Modifier>)> {
+        let line = line.as_ref();
+        let tokens = self.syntax_line(y, line);
+        let ranges: Vec<(Range<usize>, Modifier)> = self.diagnostics.get(y)
+            .into_iter()
+            .flatten()
+            .map(|(range, severity)| (range.clone(), Modifier::Diagnostic(*severity)))
+            .chain(self.overlay_ranges(line).into_iter().map(|(range, name)| (range, Modifier::Overlay(name))))
+            .collect();
+        overlay(&tokens, &ranges, self.tab_width)
+    }
+
+    /// Whenever a character is deleted or inserted on a line,
+    /// call this function to update any tokens.
+    pub fn edit<S: AsRef<str>>(&mut self, y: usize, line: S) {
+        self.edit_batch(&[(y, line)]);
+    }
+
+    /// Apply edits to several lines at once, such as those made by multiple cursors,
+    /// where each edit is a line number along with the new text of that line.
+    /// This does the same as calling [Highlighter::edit] for each line,
+    /// but the tokens are only worked out once, in a single pass over the affected lines.
+    /// ```
+    /// # use synoptic::Highlighter;
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded("comment", r"/\*", r"\*/", false);
+    /// h.run(&vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    /// h.edit_batch(&[(0, "/* a"), (2, "c */")]);
+    /// assert_eq!(h.line(1, "b")[0].text(), "b");
+    /// assert_eq!(h.tokens.len(), 1);
+    /// ```
+    pub fn edit_batch<S: AsRef<str>>(&mut self, edits: &[(usize, S)]) {
+        let edits: Vec<(usize, &str)> = edits.iter()
+            .map(|(y, line)| (*y, line.as_ref()))
+            .filter(|(y, _)| *y < self.atoms.len())
+            .collect();
+        // The lines whose atoms changed, which need tokenizing again
+        let mut affected: Option<Range<usize>> = None;
+        if self.grammar.reach() > 0 {
+            // Multi-line keywords look at nearby lines, so all the text must be in place first
+            let texts = Arc::make_mut(&mut self.texts);
+            for (y, line) in &edits {
+                texts[*y] = line.to_string();
+            }
+            for (y, _) in &edits {
+                let (around, changed) = self.atomize_around(*y..*y + 1);
+                if changed {
+                    affected = Some(union(affected, around));
+                }
+            }
+        } else {
+            for (y, line) in &edits {
+                let atoms = self.atomize(line);
+                // Determine whether tokenisation is necessary by checking atomic changes
+                if self.retokenization_needed(&self.atoms[*y], &atoms) {
+                    affected = Some(union(affected, *y..*y + 1));
+                }
+                Arc::make_mut(&mut self.atoms)[*y] = Arc::new(atoms);
+            }
+        }
+        if let Some(range) = affected {
+            self.retokenize(range.start, range.len(), range.len());
+        }
+    }
+
+    /// Takes two lists of atoms and determines if retokenization is required in the first place
+    /// This method will ignore index (as this is expected to change when editing)
+    /// Has been shown to make editing events 500x faster to apply (where no atoms are modified)
+    fn retokenization_needed(&self, old: &[Atom], new: &Vec<Atom>) -> bool {
+        // List lengths differ => atoms have been added or deleted
+        if old.len() != new.len() { return true; }
+        for (o, n) in old.iter().zip(new) {
+            // If there is ever ANY discrepancy between atoms, we must retokenize
+            if !(o.name == n.name && o.kind == n.kind && o.tok == n.tok && o.backslashed == n.backslashed && o.region == n.region && o.captures == n.captures) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whenever a line is inserted into the document,
+    /// call this function to update any tokens.
+    pub fn insert_line<S: AsRef<str>>(&mut self, y: usize, line: S) {
+        if y > self.atoms.len() { return; }
+        self.replace_lines(y..y, &[line]);
+    }
+
+    /// Whenever a line is removed from a document,
+    /// call this function to update any tokens.
+    pub fn remove_line(&mut self, y: usize) {
+        if y >= self.atoms.len() { return; }
+        self.replace_lines::<&str>(y..y + 1, &[]);
+    }
+
+    /// Replace a range of lines with other lines, for example when pasting over a selection.
+    /// This does the same as removing and inserting each line with [Highlighter::remove_line]
+    /// and [Highlighter::insert_line], but the tokens are only worked out once.
+    /// ```
+    /// # use synoptic::{Highlighter, TokOpt};
+    /// let mut h = Highlighter::new(4);
+    /// h.bounded("comment", r"/\*", r"\*/", false);
+    /// h.run(&vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    /// h.replace_lines(1..2, &["/* x", "y", "z */"]);
+    /// assert!(matches!(&h.line(2, "y")[0], TokOpt::Some(_, name) if name == "comment"));
+    /// assert_eq!(h.highlighted_lines(), 5);
+    /// ```
+    pub fn replace_lines<S: AsRef<str>>(&mut self, range: Range<usize>, lines: &[S]) {
+        if range.start > self.atoms.len() { return; }
+        let range = range.start..range.end.clamp(range.start, self.atoms.len());
+        let (removed, added) = (range.len(), lines.len());
+        let multiline = self.grammar.reach() > 0;
+        let atoms: Vec<Arc<Vec<Atom>>> = if multiline {
+            // These lines are atomized below, once the text around them is in place
+            let texts = lines.iter().map(|line| line.as_ref().to_string());
+            Arc::make_mut(&mut self.texts).splice(range.clone(), texts);
+            vec![Arc::default(); added]
+        } else {
+            lines.iter().map(|line| Arc::new(self.atomize(line.as_ref()))).collect()
+        };
+        Arc::make_mut(&mut self.atoms).splice(range.clone(), atoms);
+        self.cache.splice(range.clone(), added);
+        if range.start < self.diagnostics.len() {
+            let end = range.end.min(self.diagnostics.len());
+            Arc::make_mut(&mut self.diagnostics).splice(range.start..end, (0..added).map(|_| vec![]));
+        }
+        if multiline {
+            let (around, _) = self.atomize_around(range.start..range.start + added);
+            self.retokenize(around.start, around.len() + removed - added, around.len());
+            return;
+        }
+        self.retokenize(range.start, removed, added);
+    }
+
+    /// This process will turn a line into a vector of atoms
+    fn atomize(&self, line: &str) -> Vec<Atom> {
+        self.atomize_within(&[line], 0)
+    }
+
+    /// Turn line y of a document into a vector of atoms,
+    /// the lines around it are needed to find the matches of multi-line keywords
+    fn atomize_within<S: AsRef<str>>(&self, lines: &[S], y: usize) -> Vec<Atom> {
+        let (_, line, _) = split_markers(lines[y].as_ref());
+        let line = IndexedChars::new(line);
+        let mut atoms = vec![];
+        // For each atom definition
+        let mapping = create_mapping(line.as_str(), self.tab_width);
+        for def in &self.grammar.atom_def {
+            if let AtomKind::Multiline(count) = def.kind {
+                atoms.extend(self.multiline_atoms(def, count, lines, y, &mapping));
+                continue;
+            }
+            if !def.exp.get().is_match(line.as_str()) { continue; }
+            let backrefs = def.tok.and_then(|t| self.grammar.bounded_def[t].backrefs.as_ref());
+            let occurances = match backrefs {
+                Some(backrefs) => find_captures(def, backrefs, line.as_str(), &mapping),
+                None => find_all_mapped(def.exp.get(), line.as_str(), &mapping)
+                    .into_iter()
+                    .map(|x| (x, vec![]))
+                    .collect(),
+            };
+            // Register all occurances of any atom
+            for (x, captures) in occurances {
+                if !x.is_empty() {
+                    // Work out how many backslashes there are behind this atom (for escaping)
+                    let mut backslash_count = 0;
+                    let range = (0..x.start).rev();
+                    for idx in range {
+                        if let Some('\\') = line.get_char(idx) {
+                            backslash_count += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    // Push out the atom
+                    atoms.push(Atom {
+                        kind: def.kind.clone(),
+                        name: def.name.clone(),
+                        tok: def.tok,
+                        // An odd number of backslashes = escaped
+                        backslashed: backslash_count % 2 != 0,
+                        x,
+                        id: def.id,
+                        region: def.region,
+                        captures,
+                    });
+                }
+            }
+        }
+        // Keep track of the indentation of non-blank lines for indentation scopes
+        let scoped = self.grammar.atom_def.iter().any(|def| def.kind == AtomKind::Scope);
+        if scoped && !line.as_str().trim().is_empty() {
+            let indent = line.as_str().chars().take_while(|c| c.is_whitespace()).map(|c| self.expanded_len(c)).sum();
+            atoms.push(Atom {
+                name: String::new(),
+                kind: AtomKind::Indent,
+                tok: None,
+                x: 0..indent,
+                backslashed: false,
+                id: TokenKind::default(),
+                region: None,
+                captures: vec![],
+            });
+        }
+        // Order them based on start index, with region markers first so that the rules of the
+        // grammar being switched to apply to anything else starting at the same place
+        atoms.sort_by_key(|a| (a.x.start, !self.grammar.is_region_marker(a)));
+        atoms
+    }
+
+    /// Find the atoms on line y for the matches of a multi-line keyword, which can start on
+    /// this line or on any of the lines before it that are within reach. Matches are found by
+    /// searching from the start of the line they start on, so the same matches are found
+    /// whichever of their lines is being atomized
+    fn multiline_atoms<S: AsRef<str>>(&self, def: &AtomDef, count: usize, lines: &[S], y: usize, mapping: &HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>>) -> Vec<Atom> {
+        let mut atoms = vec![];
+        let region = def.tok.is_some_and(|t| self.grammar.bounded_def[t].region);
+        // The lines that matches touching line y can cover, joined together
+        let first = y.saturating_sub(count - 1);
+        let around: Vec<&str> = lines[first..(y + count).min(lines.len())].iter()
+            .map(|line| split_markers(line.as_ref()).1)
+            .collect();
+        // When there are lines before these, the text starts with a newline so that
+        // \A only ever matches at the start of the document
+        let lead = if first > 0 { "\n" } else { "" };
+        let text = format!("{lead}{}", around.join("\n"));
+        // Where each line starts and ends within the text
+        let starts: Vec<usize> = around.iter()
+            .scan(lead.len(), |at, line| { let start = *at; *at += line.len() + 1; Some(start) })
+            .collect();
+        let ends: Vec<usize> = starts.iter().zip(&around).map(|(start, line)| start + line.len()).collect();
+        let line_of = |idx: usize| starts.partition_point(|start| *start <= idx) - 1;
+        let y = y - first;
+        for a in 0..=y {
+            // Search the lines that a match starting on line a can cover
+            let window = &text[..ends[(a + count - 1).min(around.len() - 1)]];
+            let mut at = starts[a];
+            while let Some(caps) = def.exp.get().captures_at(window, at) {
+                // Carry on after this match, stepping over a character if it is empty
+                let whole = caps.get(0).unwrap();
+                at = whole.end() + window[whole.end()..].chars().next().filter(|_| whole.is_empty()).map_or(0, char::len_utf8);
+                let m = caps.iter().flatten().last().unwrap();
+                // Only the matches that start on line a belong to this search
+                if m.start() >= ends[a] { break; }
+                let matched = m.as_str().trim_end_matches('\n');
+                if matched.is_empty() {
+                    if at >= window.len() { break; }
+                    continue;
+                }
+                let (start, end) = (m.start(), m.start() + matched.len());
+                if region {
+                    // Embedded regions begin on the line where the match ends
+                    if line_of(end - 1) != y { continue; }
+                    let from = if a == y { mapping[&(start - starts[y])] } else { 0 };
+                    atoms.push(Atom {
+                        name: def.name.clone(),
+                        kind: AtomKind::Start,
+                        tok: def.tok,
+                        x: from..mapping[&(end - starts[y])],
+                        backslashed: false,
+                        id: def.id,
+                        region: def.region,
+                        captures: vec![],
+                    });
+                    continue;
+                }
+                let (kind, x) = match (a == y, line_of(end - 1) == y) {
+                    (true, true) => (AtomKind::Keyword, mapping[&(start - starts[y])]..mapping[&(end - starts[y])]),
+                    (true, false) => (AtomKind::Start, mapping[&(start - starts[y])]..mapping[&around[y].len()]),
+                    (false, true) => (AtomKind::End, 0..mapping[&(end - starts[y])]),
+                    (false, false) => continue,
+                };
+                let spans = kind != AtomKind::Keyword;
+                atoms.push(Atom {
+                    name: def.name.clone(),
+                    kind,
+                    tok: if spans { def.tok } else { None },
+                    x,
+                    backslashed: false,
+                    id: def.id,
+                    region: def.region,
+                    // The text of the match makes sure that its start and end are paired up
+                    captures: if spans { vec![matched.to_string()] } else { vec![] },
+                });
+            }
+        }
+        atoms
+    }
+
+    /// With multi-line keywords, the atoms of a line depend on the lines around it,
+    /// so after lines have changed, atomize them again along with the lines around them.
+    /// Returns the lines that were atomized, and whether any of their atoms are different
+    fn atomize_around(&mut self, lines: Range<usize>) -> (Range<usize>, bool) {
+        let reach = self.grammar.reach();
+        let around = lines.start.saturating_sub(reach)..(lines.end + reach).min(self.atoms.len());
+        let mut changed = false;
+        for y in around.clone() {
+            let atoms = self.atomize_within(&self.texts, y);
+            changed |= self.retokenization_needed(&self.atoms[y], &atoms);
+            Arc::make_mut(&mut self.atoms)[y] = Arc::new(atoms);
+        }
+        (around, changed)
+    }
+
+    fn tokenize(&mut self) {
+        self.set_tokenizer_state(TokenizerState::default());
+        self.line_ref = Arc::new(vec![vec![]; self.atoms.len()]);
+        self.line_states = Arc::new(Vec::with_capacity(self.atoms.len()));
+        self.tokens = Arc::default();
+        self.cache.clear();
+        for y in 0..self.atoms.len() {
+            let state = self.tokenizer_state();
+            Arc::make_mut(&mut self.line_states).push(state);
+            self.tokenize_line(y);
+        }
+    }
+
+    /// Retokenize after `removed` lines starting at line y were replaced by `added` lines
+    /// (the atoms must already be up to date). Tokenization resumes from line y and stops
+    /// as soon as the tokenizer is back in the state it was in before the edit, outside of
+    /// any token, after which the existing tokens are reused.
+    fn retokenize(&mut self, y: usize, removed: usize, added: usize) {
+        let end_state = self.tokenizer_state();
+        let start_state = self.line_states.get(y).copied().unwrap_or(end_state);
+        // Keep the tokens that start before the affected lines
+        let kept = self.tokens.partition_point(|t| token_start(t).y < y);
+        let mut old_tokens = Arc::make_mut(&mut self.tokens).split_off(kept);
+        let mut old_line_ref = Arc::make_mut(&mut self.line_ref).split_off(y);
+        let mut old_states = Arc::make_mut(&mut self.line_states).split_off(y);
+        // A token that was still open at line y will be closed again
+        if start_state.state.is_some() && !start_state.interp {
+            if let Some(TokenRef::Bounded { end, .. }) = Arc::make_mut(&mut self.tokens).last_mut() {
+                *end = None;
+            }
+        }
+        self.set_tokenizer_state(start_state);
+        let mut k = y;
+        loop {
+            if k >= y + added {
+                // Line k corresponds to line j before the edit
+                let j = k + removed - added;
+                let old_state = old_states.get(j - y).copied().unwrap_or(end_state);
+                if old_state == self.tokenizer_state() && old_state.state.is_none() {
+                    // Converged, reuse the tokens from here on, shifting them into place
+                    let skip = old_tokens.partition_point(|t| token_start(t).y < j);
+                    let (from, to) = (kept + skip, self.tokens.len());
+                    for mut token in old_tokens.drain(skip..) {
+                        match &mut token {
+                            TokenRef::Keyword { atom, .. } => atom.y = atom.y + added - removed,
+                            TokenRef::Bounded { start, end, .. } => {
+                                start.y = start.y + added - removed;
+                                if let Some(end) = end {
+                                    end.y = end.y + added - removed;
+                                }
+                            }
+                        }
+                        Arc::make_mut(&mut self.tokens).push(token);
+                    }
+                    for refs in old_line_ref.drain(j - y..) {
+                        Arc::make_mut(&mut self.line_ref).push(refs.into_iter().map(|t| t + to - from).collect());
+                    }
+                    Arc::make_mut(&mut self.line_states).extend(old_states.drain(j - y..));
+                    self.set_tokenizer_state(end_state);
+                    return;
+                }
+            }
+            if k >= self.atoms.len() { return; }
+            Arc::make_mut(&mut self.line_ref).push(vec![]);
+            let state = self.tokenizer_state();
+            Arc::make_mut(&mut self.line_states).push(state);
+            self.tokenize_line(k);
+            k += 1;
+        }
+    }
+
+    fn tokenizer_state(&self) -> TokenizerState {
+        TokenizerState {
+            state: self.tokenize_state,
+            interp: self.tokenize_interp,
+            depth: self.tokenize_depth,
+            region: self.tokenize_region,
+        }
+    }
+
+    fn set_tokenizer_state(&mut self, state: TokenizerState) {
+        self.tokenize_state = state.state;
+        self.tokenize_interp = state.interp;
+        self.tokenize_depth = state.depth;
+        self.tokenize_region = state.region;
+    }
+
+    fn tokenize_line(&mut self, y: usize) {
+        self.cache.invalidate(y);
+        let line_ref = &mut Arc::make_mut(&mut self.line_ref)[y];
+        let tokens = Arc::make_mut(&mut self.tokens);
+        let mut at_x = 0;
+        // Where the current embedded region starts on this line
+        let mut region_x = 0;
+        let atoms = &self.atoms[y];
+        for (x, atom) in atoms.iter().enumerate() {
+            if atom.x.start < at_x { continue; }
+            // Switch between grammars at the start and end of embedded regions
+            if self.grammar.is_region_marker(atom) {
+                let opens = matches!(atom.kind, AtomKind::Start | AtomKind::Hybrid);
+                let closes = matches!(atom.kind, AtomKind::End | AtomKind::Hybrid);
+                // A region can't start again where one has just ended
+                let fresh = atom.x.start >= region_x;
+                if self.tokenize_region.is_none() && self.tokenize_state.is_none() && opens && fresh {
+                    self.tokenize_region = atom.tok;
+                    region_x = atom.x.end;
+                } else if self.tokenize_region == atom.tok && fresh && closes {
+                    if self.tokenize_state.is_some() && !self.tokenize_interp {
+                        // Cut short a token of the embedded grammar that is still open
+                        let last = tokens.len() - 1;
+                        if let TokenRef::Bounded { start, end, .. } = &mut tokens[last] {
+                            if start.y == y {
+                                *end = Some(start.clone());
+                            } else {
+                                line_ref.retain(|t| *t != last);
+                            }
+                        }
+                    }
+                    self.tokenize_state = None;
+                    self.tokenize_interp = false;
+                    self.tokenize_depth = 0;
+                    self.tokenize_region = None;
+                    region_x = atom.x.end;
+                }
+                continue;
+            }
+            // Only use the rules of the grammar for the region this atom is in
+            let region = if atom.x.start >= region_x { self.tokenize_region } else { None };
+            if atom.region != region { continue; }
+            // Work out if this atom is to be ignored (due to escaping)
+            if let Atom { tok: Some(t), backslashed, .. } = atom {
+                if self.grammar.bounded_def[*t].escapable && *backslashed {
+                    continue;
+                }
+            }
+            // Continue tokenising...
+            match atom {
+                Atom { name, kind: AtomKind::Keyword, .. } => {
+                    if self.tokenize_state.is_none() || self.tokenize_interp {
+                        tokens.push(TokenRef::Keyword {
+                            name: name.clone(),
+                            atom: Loc { y, x },
+                        });
+                        line_ref.push(tokens.len().saturating_sub(1));
+                        at_x = atom.x.end;
+                    }
+                }
+                Atom { name, kind: AtomKind::Start, tok, .. } => {
+                    if self.tokenize_interp { continue; }
+                    if self.tokenize_state.is_none() {
+                        self.tokenize_state = *tok;
+                        tokens.push(TokenRef::Bounded {
+                            name: name.clone(),
+                            start: Loc { y, x },
+                            end: None,
+                        });
+                        at_x = atom.x.end;
+                    } else if self.tokenize_state == *tok && tok.is_some_and(|t| self.grammar.bounded_def[t].nestable) {
+                        // Go a level deeper
+                        self.tokenize_depth += 1;
+                        at_x = atom.x.end;
+                    }
+                }
+                Atom { kind: AtomKind::Nest, tok, .. } => {
+                    if self.tokenize_interp { continue; }
+                    if self.tokenize_state == *tok {
+                        // Go a level deeper
+                        self.tokenize_depth += 1;
+                        at_x = atom.x.end;
+                    }
+                }
+                Atom { kind: AtomKind::End, tok, captures, .. } => {
+                    if self.tokenize_interp { continue; }
+                    if self.tokenize_state == *tok && self.tokenize_depth > 0 {
+                        // Come back out of a nested level
+                        self.tokenize_depth -= 1;
+                        at_x = atom.x.end;
+                    } else if self.tokenize_state == *tok {
+                        // The end of a token that depends on its start must capture the same text
+                        if let Some(TokenRef::Bounded { start, .. }) = tokens.last() {
+                            if self.atoms[start.y][start.x].captures != *captures { continue; }
+                        }
+                        self.tokenize_state = None;
+                        if let TokenRef::Bounded { ref mut end, .. } = tokens.last_mut().unwrap() {
+                            *end = Some(Loc { y, x });
+                            at_x = atom.x.end;
+                        }
+                        line_ref.push(tokens.len().saturating_sub(1));
+                    }
+                }
+                Atom { name, kind: AtomKind::Hybrid, tok, .. } => {
+                    if self.tokenize_interp { continue; }
+                    if self.tokenize_state.is_none() {
+                        // Start registering token
+                        self.tokenize_state = *tok;
+                        tokens.push(TokenRef::Bounded {
+                            name: name.clone(),
+                            start: Loc { y, x },
+                            end: None,
+                        });
+                        at_x = atom.x.end;
+                    } else if self.tokenize_state == *tok {
+                        // Stop registering token
+                        self.tokenize_state = None;
+                        if let TokenRef::Bounded { ref mut end, .. } = tokens.last_mut().unwrap() {
+                            *end = Some(Loc { y, x });
+                            at_x = atom.x.end;
+                        }
+                        line_ref.push(tokens.len().saturating_sub(1));
+                    }
+                }
+                Atom { kind: AtomKind::InterpolateStart, tok, .. } => {
+                    if self.tokenize_state == *tok {
+                        // End the current token
+                        if let TokenRef::Bounded { ref mut end, .. } = tokens.last_mut().unwrap() {
+                            *end = Some(Loc { y, x });
+                            at_x = atom.x.end;
+                        }
+                        line_ref.push(tokens.len().saturating_sub(1));
+                        // Register interpolation
+                        self.tokenize_interp = true;
+                    }
+                }
+                // Fold markers and scopes are only used when working out fold ranges
+                Atom { kind: AtomKind::FoldStart | AtomKind::FoldEnd | AtomKind::Scope | AtomKind::Indent | AtomKind::Multiline(_), .. } => (),
+                Atom { name, kind: AtomKind::InterpolateEnd, tok, .. } => {
+                    if self.tokenize_state == *tok {
+                        // Stop interpolating
+                        self.tokenize_interp = false;
+                        // Resume capturing the outer token
+                        tokens.push(TokenRef::Bounded {
+                            name: name.clone(),
+                            start: Loc { y, x },
+                            end: None,
+                        });
+                        at_x = atom.x.end;
+                    }
+                }
+            }
+            if self.tokenize_state.is_some() {
+                line_ref.push(tokens.len().saturating_sub(1));
+            }
+        }
+        if self.tokenize_state.is_some() {
+            line_ref.push(tokens.len().saturating_sub(1));
+        }
+        line_ref.dedup();
+    }
+}
+
+/// Find the smallest range that covers both a range (if there is one) and another range
+fn union(a: Option<Range<usize>>, b: Range<usize>) -> Range<usize> {
+    match a {
+        Some(a) => a.start.min(b.start)..a.end.max(b.end),
+        None => b,
+    }
+}
+
+/// Find where a token starts
+fn token_start(token: &TokenRef) -> &Loc {
+    match token {
+        TokenRef::Keyword { atom, .. } => atom,
+        TokenRef::Bounded { start, .. } => start,
+    }
+}
+
+/// This will find all occurances of a string in a document (and return character indices)
+pub fn find_all(exp: &Regex, target: &str, tab_width: usize) -> Vec<Range<usize>> {
+    find_all_mapped(exp, target, &create_mapping(target, tab_width))
+}
+
+/// Like [find_all], but reusing a mapping from [create_mapping], so that many expressions
+/// can be searched for in the same text without working it out each time
+fn find_all_mapped(exp: &Regex, target: &str, mapping: &HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>>) -> Vec<Range<usize>> {
+    exp.captures_iter(target)
+        // Get last capture
+        .map(|c| c.iter().flatten().collect::<Vec<_>>())
+        .map(|mut c| c.pop().unwrap())
+        // Extract end and start values
+        .map(|m| mapping[&m.start()]..mapping[&m.end()])
+        .collect()
+}
+
+/// Find the occurances of the start or end of a token whose end depends on its start,
+/// along with the text of the capture groups that the end refers back to
+fn find_captures(def: &AtomDef, backrefs: &[usize], target: &str, mapping: &HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>>) -> Vec<(Range<usize>, Vec<String>)> {
+    def.exp.get().captures_iter(target)
+        .map(|c| {
+            let whole = c.get(0).unwrap();
+            let captures = (0..backrefs.len())
+                .map(|i| match def.kind {
+                    AtomKind::Start => c.get(backrefs[i]),
+                    _ => c.name(&format!("backref{i}")),
+                })
+                .map(|m| m.map_or(String::new(), |m| m.as_str().to_string()))
+                .collect();
+            (mapping[&whole.start()]..mapping[&whole.end()], captures)
+        })
+        .collect()
+}
+
+/// HashMap<byte_idx, char_idx>
+pub fn create_mapping(target: &str, tab_width: usize) -> HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>> {
+    let mut result: HashMap::<usize, usize, BuildHasherDefault<NoHashHasher<usize>>> =
+        HashMap::with_capacity_and_hasher(target.len(), BuildHasherDefault::default());
+    result.insert(0, 0);
+    let mut acc_byte = 0;
+    let mut acc_char = 0;
+    for c in target.chars() {
+        acc_byte += c.len_utf8();
+        acc_char += if c == '\t' { tab_width } else { 1 };
+        result.insert(acc_byte, acc_char);
+    }
+    result
+}
+
+/// A mapping between the character indices of a line and the display columns they occupy.
+/// Characters are grouped into grapheme clusters (user-perceived characters, such as an emoji
+/// made up of several characters joined together), which are never split across columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    /// The display column that each character starts at,
+    /// characters in the same grapheme cluster share a column
+    cols: Vec<usize>,
+    /// The range of characters in the grapheme cluster that each character is part of
+    clusters: Vec<Range<usize>>,
+    /// The total display width of the line
+    width: usize,
+}
+
+impl ColumnMap {
+    /// Create a column map for a line, with variable tab width
+    pub fn new(line: &str, tab_width: usize) -> Self {
+        let mut cols = Vec::with_capacity(line.len());
+        let mut clusters = Vec::with_capacity(line.len());
+        let (mut at, mut idx) = (0, 0);
+        for cluster in line.graphemes(true) {
+            let len = cluster.chars().count();
+            for _ in 0..len {
+                cols.push(at);
+                clusters.push(idx..idx + len);
+            }
+            idx += len;
+            at += width(cluster, tab_width);
+        }
+        Self { cols, clusters, width: at }
+    }
+
+    /// Find the display column that a character starts at.
+    /// Indices past the end of the line map to the width of the line
+    pub fn char_to_col(&self, idx: usize) -> usize {
+        self.cols.get(idx).copied().unwrap_or(self.width)
+    }
+
+    /// Find the index of the character that covers a display column
+    /// (the first character of its grapheme cluster).
+    /// Columns past the end of the line map to the number of characters in the line
+    pub fn col_to_char(&self, col: usize) -> usize {
+        if col >= self.width {
+            return self.cols.len();
+        }
+        let idx = self.cols.partition_point(|&c| c <= col).saturating_sub(1);
+        self.clusters[idx].start
+    }
+
+    /// Find the display width of the grapheme cluster that a character is part of
+    /// (0 for indices past the end of the line)
+    pub fn col_width_of_char(&self, idx: usize) -> usize {
+        if idx >= self.cols.len() {
+            return 0;
+        }
+        self.char_to_col(self.clusters[idx].end) - self.cols[idx]
+    }
+
+    /// Find the range of characters in the grapheme cluster that a character is part of
+    /// (an empty range at the end of the line for indices past the end of the line)
+    pub fn cluster(&self, idx: usize) -> Range<usize> {
+        self.clusters.get(idx).cloned().unwrap_or(self.cols.len()..self.cols.len())
+    }
+
+    /// The total display width of the line
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of characters in the line
+    pub fn len(&self) -> usize {
+        self.cols.len()
+    }
+
+    /// Whether the line has no characters
+    pub fn is_empty(&self) -> bool {
+        self.cols.is_empty()
+    }
+}
+
+/// Separates a line from a byte order mark at its start and a carriage return at its end
+/// (as found in files from Windows), returning (bom, content, carriage_return).
+/// The bom and carriage return will be empty when they are not present.
+pub fn split_markers(line: &str) -> (&str, &str, &str) {
+    let bom_len = if line.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 };
+    let (bom, rest) = line.split_at(bom_len);
+    let cr_len = usize::from(rest.ends_with('\r'));
+    let (content, cr) = rest.split_at(rest.len() - cr_len);
+    (bom, content, cr)
+}
+
+/// Utility function to determine the width of a string, with variable tab width
+#[must_use]
+pub fn width(st: &str, tab_width: usize) -> usize {
+    let tabs = st.matches('\t').count();
+    (st.width() + tabs * tab_width).saturating_sub(tabs)
+}
+
+
+/// Trim utility function to trim down a line of tokens to offset text,
+/// removing the first `start` display columns.
+/// Grapheme clusters are never split, wide ones (including tabs) that are cut in half are
+/// replaced with padding, and tokens keep their kind
+/// ```
+/// # use synoptic::{trim, TokOpt};
+/// let text = |tokens: Vec<TokOpt>| tokens.iter().map(|t| t.text().clone()).collect::<Vec<_>>();
+/// // A tab (two columns wide here) followed by a token of two CJK characters
+/// let tokens = vec![TokOpt::None("\t".to_string()), TokOpt::Some("你好".to_string(), "string".to_string())];
+/// assert_eq!(text(trim(&tokens, 1, 2)), vec![" ", "你好"]);
+/// assert_eq!(text(trim(&tokens, 2, 2)), vec!["你好"]);
+/// assert_eq!(text(trim(&tokens, 3, 2)), vec![" 好"]);
+/// assert_eq!(text(trim(&tokens, 3, 4)), vec![" ", "你好"]);
+/// assert!(matches!(&trim(&tokens, 3, 2)[0], TokOpt::Some(_, kind) if kind == "string"));
+/// assert!(trim(&tokens, 6, 2).is_empty());
+/// ```
+pub fn trim(input: &[TokOpt], start: usize, tab_width: usize) -> Vec<TokOpt> {
+    split_tokens(input, start, tab_width).1
+}
+
+/// Trim utility function to trim down a line of tokens to offset text (with length).
+/// Grapheme clusters are never split, wide ones that are cut in half are replaced with padding,
+/// and the result is padded with spaces so that it is always exactly `length` columns wide
+/// ```
+/// # use synoptic::{trim_fit, TokOpt};
+/// let fit = |text: &str, start, length| trim_fit(&[TokOpt::None(text.to_string())], start, length, 4)[0].text().clone();
+/// // Emoji joined with zero width joiners take up two columns
+/// assert_eq!(fit("a👨\u{200d}👩\u{200d}👧b", 1, 2), "👨\u{200d}👩\u{200d}👧");
+/// assert_eq!(fit("a👨\u{200d}👩\u{200d}👧b", 2, 2), " b");
+/// // Combining accents stay with the letter they are on
+/// assert_eq!(fit("ae\u{301}b", 1, 1), "e\u{301}");
+/// // CJK characters are two columns wide
+/// assert_eq!(fit("你好世界", 1, 4), " 好 ");
+/// // Tabs take up the tab width
+/// assert_eq!(fit("\tx", 2, 4), "  x ");
+/// // Tokens keep their kind, and padding is added as plain text
+/// let text = |tokens: Vec<TokOpt>| tokens.iter().map(|t| t.text().clone()).collect::<Vec<_>>();
+/// let tokens = vec![TokOpt::None("\t".to_string()), TokOpt::Some("你好".to_string(), "string".to_string())];
+/// assert_eq!(text(trim_fit(&tokens, 1, 3, 2)), vec![" ", "你"]);
+/// assert_eq!(text(trim_fit(&tokens, 3, 4, 2)), vec![" 好", " "]);
+/// assert_eq!(text(trim_fit(&tokens, 8, 2, 2)), vec!["  "]);
+/// ```
+pub fn trim_fit(input: &[TokOpt], start: usize, length: usize, tab_width: usize) -> Vec<TokOpt> {
+    let (_, rest) = split_tokens(input, start, tab_width);
+    let (mut opt, _) = split_tokens(&rest, length, tab_width);
+    // Apply padding if applicable
+    let total_width: usize = opt.iter().map(|tok| width(tok.text(), tab_width)).sum();
+    if total_width < length {
+        let padding = " ".repeat(length - total_width);
+        match opt.last_mut() {
+            Some(TokOpt::None(text)) => text.push_str(&padding),
+            _ => opt.push(TokOpt::None(padding)),
+        }
+    }
+    opt
+}
+
+/// Split a line of tokens into rows that are at most `length` display columns wide,
+/// for soft-wrapping long lines. Tokens that cross the end of a row are split in two and
+/// keep their kind on both rows. Grapheme clusters are never split: a wide character
+/// (or tab) that doesn't fit at the end of a row is moved onto the next row, leaving the
+/// row short. An empty line is a single empty row.
+/// ```
+/// # use synoptic::{wrap, TokOpt};
+/// let tokens = vec![TokOpt::Some("let".to_string(), "keyword".to_string()), TokOpt::None(" 你好".to_string())];
+/// let rows = wrap(&tokens, 4, 4);
+/// let text: Vec<Vec<&str>> = rows.iter().map(|row| row.iter().map(|t| t.text().as_str()).collect()).collect();
+/// assert_eq!(text, vec![vec!["let", " "], vec!["你好"]]);
+/// ```
+pub fn wrap(input: &[TokOpt], length: usize, tab_width: usize) -> Vec<Vec<TokOpt>> {
+    let mut rows: Vec<Vec<TokOpt>> = vec![vec![]];
+    let mut used = 0;
+    for token in input {
+        // Whether the next cluster has to start a new piece of this token
+        let mut fresh = true;
+        for cluster in token.text().graphemes(true) {
+            let wid = width(cluster, tab_width);
+            // Clusters that don't fit go on the next row, unless they would never fit
+            if used + wid > length && used > 0 {
+                rows.push(vec![]);
+                used = 0;
+                fresh = true;
+            }
+            let row = rows.last_mut().unwrap();
+            if fresh {
+                let mut piece = token.clone();
+                piece.text_mut().clear();
+                row.push(piece);
+                fresh = false;
+            }
+            row.last_mut().unwrap().text_mut().push_str(cluster);
+            used += wid;
+        }
+    }
+    rows
+}
+
+/// Find the token index within a tokopt given a display index
+/// Returns (token_index, index_within_that_token)
+pub fn find_tok_index(input: &[TokOpt], disp_idx: usize, tab_width: usize) -> Option<(usize, usize)> {
+    let mut total_width = 0;
+    for (idx, token) in input.iter().enumerate() {
+        let this_width = width(token.text(), tab_width);
+        total_width += this_width;
+        // Check if we've passed the display index
+        if total_width > disp_idx {
+            // We have, this token contains disp_idx, work out relative idx
+            let rel_idx = this_width - (total_width - disp_idx);
+            return Some((idx, rel_idx));
+        }
+    }
+    None
+}
+
+/// Split a line of tokens into two at a display index, tokens that straddle the
+/// index are cut in two (with padding applied where wide characters are split)
+pub fn split_tokens(input: &[TokOpt], disp_idx: usize, tab_width: usize) -> (Vec<TokOpt>, Vec<TokOpt>) {
+    let Some((tok, rel)) = find_tok_index(input, disp_idx, tab_width) else {
+        return (input.to_vec(), vec![]);
+    };
+    let mut left = input[..tok].to_vec();
+    let mut right = vec![];
+    if rel > 0 {
+        let mut before = input[tok].clone();
+        before.take(rel, tab_width);
+        left.push(before);
+    }
+    let mut after = input[tok].clone();
+    after.skip(rel, tab_width);
+    right.push(after);
+    right.extend_from_slice(&input[tok + 1..]);
+    left.retain(|t| !t.is_empty());
+    right.retain(|t| !t.is_empty());
+    (left, right)
+}
+
+/// Flag the tokens in a line that fall within a range of display indices,
+/// splitting any tokens that lie on the boundary of the range
+pub fn select(input: &[TokOpt], range: Range<usize>, tab_width: usize) -> Vec<(TokOpt, bool)> {
+    let (rest, after) = split_tokens(input, range.end, tab_width);
+    let (before, within) = split_tokens(&rest, range.start, tab_width);
+    let before = before.into_iter().map(|t| (t, false));
+    let within = within.into_iter().map(|t| (t, true));
+    let after = after.into_iter().map(|t| (t, false));
+    before.chain(within).chain(after).collect()
+}
+
+/// Attach information to the tokens in a line that fall within ranges of display indices.
+/// Tokens are split at the boundaries of each range, and each resulting token
+/// is returned alongside the information of every range that covers it
+pub fn overlay<M: Clone>(input: &[TokOpt], ranges: &[(Range<usize>, M)], tab_width: usize) -> Vec<(TokOpt, Vec<M>)> {
+    // Work out where tokens need to be cut
+    let mut cuts: Vec<usize> = ranges.iter().flat_map(|(r, _)| [r.start, r.end]).collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+    // Cut tokens, keeping track of which display indices each one covers
+    let mut pieces = vec![];
+    let mut rest = input.to_vec();
+    let mut at = 0;
+    let mut x = 0;
+    for cut in cuts {
+        if cut <= at || rest.is_empty() { continue; }
+        let (left, right) = split_tokens(&rest, cut - at, tab_width);
+        for tok in left {
+            let wid = width(tok.text(), tab_width);
+            pieces.push((tok, x..x + wid));
+            x += wid;
+        }
+        at = cut;
+        rest = right;
+    }
+    for tok in rest {
+        let wid = width(tok.text(), tab_width);
+        pieces.push((tok, x..x + wid));
+        x += wid;
+    }
+    // Attach the information of each range that covers a token
+    pieces.into_iter()
+        .map(|(tok, span)| {
+            let info = ranges.iter()
+                .filter(|(r, _)| r.start < span.end && span.start < r.end)
+                .map(|(_, m)| m.clone())
+                .collect();
+            (tok, info)
+        })
+        .collect()
+}
+
+/// Find the display column ranges that differ (in either text or kind) between two lines of tokens
+pub fn diff_tokens(old: &[TokOpt], new: &[TokOpt], tab_width: usize) -> Vec<Range<usize>> {
+    let (old, new) = (cells(old, tab_width), cells(new, tab_width));
+    let mut result: Vec<Range<usize>> = vec![];
+    for x in 0..old.len().max(new.len()) {
+        if old.get(x) != new.get(x) {
+            match result.last_mut() {
+                Some(range) if range.end == x => range.end += 1,
+                _ => result.push(x..x + 1),
+            }
+        }
+    }
+    result
+}
+
+/// Break a line of tokens up into display cells of (text, kind),
+/// where the cells after a wide character have empty text
+fn cells(input: &[TokOpt], tab_width: usize) -> Vec<(String, Option<&str>)> {
+    let mut result: Vec<(String, Option<&str>)> = vec![];
+    for token in input {
+        let kind = match token {
+            TokOpt::Some(_, kind) => Some(kind.as_str()),
+            TokOpt::None(_) => None,
+        };
+        for cluster in token.text().graphemes(true) {
+            let wid = width(cluster, tab_width);
+            if wid == 0 {
+                // Zero width clusters (e.g. a lone joiner) belong to the previous cell
+                if let Some((text, _)) = result.last_mut() {
+                    text.push_str(cluster);
+                    continue;
+                }
+            }
+            result.push((cluster.to_string(), kind));
+            for _ in 1..wid {
+                result.push((String::new(), kind));
+            }
+        }
+    }
+    result
+}
+
+/// A language with a provided syntax highlighter
+pub(crate) struct Builtin {
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) extensions: &'static [&'static str],
+    pub(crate) filenames: &'static [&'static str],
+    /// Programs that run the language, for recognising shebangs
+    pub(crate) interpreters: &'static [&'static str],
+    pub(crate) first_lines: &'static [&'static str],
+    pub(crate) highlighter: fn() -> &'static Highlighter,
+}
+
+/// The languages with provided syntax highlighters
+pub(crate) static BUILTINS: &[Builtin] = &[
+    Builtin { id: "rust", name: "Rust", extensions: &["rs"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: rust_syntax_highlighter },
+    Builtin { id: "asm", name: "Assembly", extensions: &["asm", "s"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: asm_syntax_highlighter },
+    Builtin { id: "python", name: "Python", extensions: &["py", "pyw"], filenames: &[], interpreters: &["python", "pypy"], first_lines: &[], highlighter: python_syntax_highlighter },
+    Builtin { id: "ruby", name: "Ruby", extensions: &["rb", "ruby"], filenames: &[], interpreters: &["ruby"], first_lines: &[], highlighter: ruby_syntax_highlighter },
+    Builtin { id: "perl", name: "Perl", extensions: &["cgi", "pm"], filenames: &[], interpreters: &["perl"], first_lines: &[], highlighter: cgi_syntax_highlighter },
+    Builtin { id: "lua", name: "Lua", extensions: &["lua"], filenames: &[], interpreters: &["lua", "luajit"], first_lines: &[], highlighter: lua_syntax_highlighter },
+    Builtin { id: "r", name: "R", extensions: &["r", "rproj"], filenames: &[], interpreters: &["Rscript"], first_lines: &[], highlighter: r_syntax_highlighter },
+    Builtin { id: "go", name: "Go", extensions: &["go"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: go_syntax_highlighter },
+    Builtin { id: "javascript", name: "JavaScript", extensions: &["js", "mjs", "cjs"], filenames: &[], interpreters: &["node", "nodejs"], first_lines: &[], highlighter: js_syntax_highlighter },
+    Builtin { id: "jsx", name: "JSX", extensions: &["jsx"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: jsx_syntax_highlighter },
+    Builtin { id: "typescript", name: "TypeScript", extensions: &["ts", "mts", "cts"], filenames: &[], interpreters: &["ts-node", "deno"], first_lines: &[], highlighter: ts_syntax_highlighter },
+    Builtin { id: "tsx", name: "TSX", extensions: &["tsx"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: tsx_syntax_highlighter },
+    Builtin { id: "dart", name: "Dart", extensions: &["dart"], filenames: &[], interpreters: &["dart"], first_lines: &[], highlighter: dart_syntax_highlighter },
+    Builtin { id: "c", name: "C", extensions: &["c", "h"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: c_syntax_highlighter },
+    Builtin { id: "cpp", name: "C++", extensions: &["cpp", "hpp", "c++", "cxx", "cc"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: cpp_syntax_highlighter },
+    Builtin { id: "csharp", name: "C#", extensions: &["cs", "csproj"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: cs_syntax_highlighter },
+    Builtin { id: "swift", name: "Swift", extensions: &["swift"], filenames: &[], interpreters: &["swift"], first_lines: &[], highlighter: swift_syntax_highlighter },
+    Builtin { id: "json", name: "JSON", extensions: &["json"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: json_syntax_highlighter },
+    Builtin { id: "kotlin", name: "Kotlin", extensions: &["kt"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: kotlin_syntax_highlighter },
+    Builtin { id: "java", name: "Java", extensions: &["class", "java"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: java_syntax_highlighter },
+    Builtin { id: "vb", name: "Visual Basic", extensions: &["vb"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: vb_syntax_highlighter },
+    Builtin { id: "matlab", name: "MATLAB", extensions: &["m"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: m_syntax_highlighter },
+    Builtin { id: "php", name: "PHP", extensions: &["php"], filenames: &[], interpreters: &["php"], first_lines: &[r"^<\?php"], highlighter: php_syntax_highlighter },
+    Builtin { id: "scala", name: "Scala", extensions: &["scala"], filenames: &[], interpreters: &["scala"], first_lines: &[], highlighter: scala_syntax_highlighter },
+    Builtin { id: "prolog", name: "Prolog", extensions: &["pl", "prolog"], filenames: &[], interpreters: &["swipl"], first_lines: &[], highlighter: prolog_syntax_highlighter },
+    Builtin { id: "haskell", name: "Haskell", extensions: &["hs"], filenames: &[], interpreters: &["runhaskell", "runghc"], first_lines: &[], highlighter: haskell_syntax_highlighter },
+    Builtin { id: "css", name: "CSS", extensions: &["css"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: css_syntax_highlighter },
+    Builtin { id: "html", name: "HTML", extensions: &["html", "htm", "xhtml"], filenames: &[], interpreters: &[], first_lines: &[r"(?i)^\s*<!doctype\s+html\b"], highlighter: html_syntax_highlighter },
+    Builtin { id: "markdown", name: "Markdown", extensions: &["md", "markdown"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: markdown_syntax_highlighter },
+    Builtin { id: "toml", name: "TOML", extensions: &["toml"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: toml_syntax_highlighter },
+    Builtin { id: "yaml", name: "YAML", extensions: &["yaml", "yml"], filenames: &[], interpreters: &[], first_lines: &[r"^%YAML\b"], highlighter: yaml_syntax_highlighter },
+    Builtin { id: "csv", name: "CSV", extensions: &["csv"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: csv_syntax_highlighter },
+    Builtin { id: "shell", name: "Shell", extensions: &["sh", "bash", "bash_profile", "bashrc"], filenames: &[], interpreters: &["sh", "bash", "dash", "ksh", "ash"], first_lines: &[], highlighter: shell_syntax_highlighter },
+    Builtin { id: "sql", name: "SQL", extensions: &["sql", "sqlproj"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: sql_syntax_highlighter },
+    Builtin { id: "xml", name: "XML", extensions: &["xml"], filenames: &[], interpreters: &[], first_lines: &[r"^\s*<\?xml\b"], highlighter: xml_syntax_highlighter },
+    Builtin { id: "nushell", name: "Nushell", extensions: &["nu"], filenames: &[], interpreters: &["nu"], first_lines: &[], highlighter: nushell_syntax_highlighter },
+    Builtin { id: "tex", name: "TeX", extensions: &["tex"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: tex_syntax_highlighter },
+    Builtin { id: "diff", name: "Diff", extensions: &["diff"], filenames: &[], interpreters: &[], first_lines: &[r"^diff --git "], highlighter: diff_syntax_highlighter },
+    Builtin { id: "d", name: "D", extensions: &["d", "di"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: d_syntax_highlighter },
+    Builtin { id: "ada", name: "Ada", extensions: &["adb", "ads", "ada"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: ada_syntax_highlighter },
+    Builtin { id: "pascal", name: "Pascal", extensions: &["pas", "pp", "dpr", "lpr", "dpk"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: pascal_syntax_highlighter },
+    Builtin { id: "groovy", name: "Groovy", extensions: &["groovy", "gradle", "gvy"], filenames: &[], interpreters: &["groovy"], first_lines: &[], highlighter: groovy_syntax_highlighter },
+    Builtin { id: "gleam", name: "Gleam", extensions: &["gleam"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: gleam_syntax_highlighter },
+    Builtin { id: "scheme", name: "Scheme", extensions: &["scm", "ss", "sld", "rkt", "rktl"], filenames: &[], interpreters: &["guile", "racket", "csi"], first_lines: &[], highlighter: scheme_syntax_highlighter },
+    Builtin { id: "powershell", name: "PowerShell", extensions: &["ps1", "psm1", "psd1"], filenames: &[], interpreters: &["pwsh"], first_lines: &[], highlighter: powershell_syntax_highlighter },
+    Builtin { id: "batch", name: "Batch", extensions: &["bat", "cmd"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: batch_syntax_highlighter },
+    Builtin { id: "dockerfile", name: "Dockerfile", extensions: &["dockerfile", "containerfile"], filenames: &["Dockerfile", "Containerfile", "Dockerfile.*", "Containerfile.*"], interpreters: &[], first_lines: &[], highlighter: dockerfile_syntax_highlighter },
+    Builtin { id: "cmake", name: "CMake", extensions: &["cmake"], filenames: &["CMakeLists.txt"], interpreters: &[], first_lines: &[], highlighter: cmake_syntax_highlighter },
+    Builtin { id: "graphql", name: "GraphQL", extensions: &["graphql", "graphqls", "gql"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: graphql_syntax_highlighter },
+    Builtin { id: "ini", name: "INI", extensions: &["ini", "cfg", "conf", "editorconfig"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: ini_syntax_highlighter },
+    Builtin { id: "env", name: "Environment", extensions: &["env", "properties"], filenames: &[".env.*"], interpreters: &[], first_lines: &[], highlighter: env_syntax_highlighter },
+    Builtin { id: "gitconfig", name: "Git Config", extensions: &["gitconfig", "gitmodules"], filenames: &[".git/config"], interpreters: &[], first_lines: &[], highlighter: gitconfig_syntax_highlighter },
+    Builtin { id: "gitignore", name: "Git Ignore", extensions: &["gitignore", "dockerignore"], filenames: &[".git/info/exclude"], interpreters: &[], first_lines: &[], highlighter: gitignore_syntax_highlighter },
+    Builtin { id: "gitattributes", name: "Git Attributes", extensions: &["gitattributes"], filenames: &[".git/info/attributes"], interpreters: &[], first_lines: &[], highlighter: gitattributes_syntax_highlighter },
+    Builtin { id: "scss", name: "SCSS", extensions: &["scss", "sass"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: scss_syntax_highlighter },
+    Builtin { id: "less", name: "Less", extensions: &["less"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: less_syntax_highlighter },
+    Builtin { id: "cuda", name: "CUDA", extensions: &["cu", "cuh"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: cuda_syntax_highlighter },
+    Builtin { id: "vhdl", name: "VHDL", extensions: &["vhd", "vhdl"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: vhdl_syntax_highlighter },
+    Builtin { id: "verilog", name: "Verilog", extensions: &["v", "vh", "sv", "svh"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: verilog_syntax_highlighter },
+    Builtin { id: "llvm", name: "LLVM IR", extensions: &["ll"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: llvm_syntax_highlighter },
+    Builtin { id: "riscv", name: "RISC-V Assembly", extensions: &[], filenames: &[], interpreters: &[], first_lines: &[], highlighter: riscv_syntax_highlighter },
+    Builtin { id: "arm", name: "ARM Assembly", extensions: &[], filenames: &[], interpreters: &[], first_lines: &[], highlighter: arm_syntax_highlighter },
+    Builtin { id: "handlebars", name: "Handlebars", extensions: &["hbs", "handlebars", "mustache"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: handlebars_syntax_highlighter },
+    Builtin { id: "rst", name: "reStructuredText", extensions: &["rst", "restx"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: rst_syntax_highlighter },
+    Builtin { id: "asciidoc", name: "AsciiDoc", extensions: &["adoc", "asciidoc", "asc"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: asciidoc_syntax_highlighter },
+    Builtin { id: "org", name: "Org", extensions: &["org"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: org_syntax_highlighter },
+    Builtin { id: "bibtex", name: "BibTeX", extensions: &["bib"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: bibtex_syntax_highlighter },
+    Builtin { id: "vim", name: "Vim Script", extensions: &["vim", "vimrc"], filenames: &["vimrc", "_vimrc", "gvimrc", "_gvimrc", "exrc", ".exrc"], interpreters: &[], first_lines: &[], highlighter: vim_syntax_highlighter },
+    Builtin { id: "tcl", name: "Tcl", extensions: &["tcl", "tk"], filenames: &[], interpreters: &["tclsh", "wish"], first_lines: &[], highlighter: tcl_syntax_highlighter },
+    Builtin { id: "awk", name: "AWK", extensions: &["awk", "gawk"], filenames: &[], interpreters: &["awk", "gawk", "mawk", "nawk"], first_lines: &[], highlighter: awk_syntax_highlighter },
+    Builtin { id: "http", name: "HTTP", extensions: &["http", "rest"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: http_syntax_highlighter },
+    Builtin { id: "xquery", name: "XQuery", extensions: &["xpath", "xq", "xql", "xqm", "xquery", "xqy"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: xquery_syntax_highlighter },
+    Builtin { id: "regex", name: "Regex", extensions: &["regex", "regexp"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: regex_syntax_highlighter },
+    Builtin { id: "mermaid", name: "Mermaid", extensions: &["mmd", "mermaid"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: mermaid_syntax_highlighter },
+    Builtin { id: "plantuml", name: "PlantUML", extensions: &["puml", "plantuml", "pu", "iuml"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: plantuml_syntax_highlighter },
+    Builtin { id: "zsh", name: "Zsh", extensions: &["zsh", "zshrc", "zprofile", "zshenv", "zlogin", "zlogout"], filenames: &[], interpreters: &["zsh"], first_lines: &[], highlighter: zsh_syntax_highlighter },
+    Builtin { id: "zig", name: "Zig", extensions: &["zig", "zon"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: zig_syntax_highlighter },
+    Builtin { id: "objc", name: "Objective-C", extensions: &["mm", "objc", "objcpp"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: objc_syntax_highlighter },
+    Builtin { id: "elixir", name: "Elixir", extensions: &["ex", "exs"], filenames: &[], interpreters: &["elixir"], first_lines: &[], highlighter: elixir_syntax_highlighter },
+    Builtin { id: "erlang", name: "Erlang", extensions: &["erl", "hrl", "escript"], filenames: &[], interpreters: &["escript"], first_lines: &[], highlighter: erlang_syntax_highlighter },
+    Builtin { id: "ocaml", name: "OCaml", extensions: &["ml", "mli", "mll", "mly"], filenames: &[], interpreters: &["ocaml"], first_lines: &[], highlighter: ocaml_syntax_highlighter },
+    Builtin { id: "fsharp", name: "F#", extensions: &["fs", "fsi", "fsx", "fsscript"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: fsharp_syntax_highlighter },
+    Builtin { id: "elm", name: "Elm", extensions: &["elm"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: elm_syntax_highlighter },
+    Builtin { id: "makefile", name: "Makefile", extensions: &["mk", "mak", "make"], filenames: &["Makefile", "makefile", "GNUmakefile", "BSDmakefile"], interpreters: &["make"], first_lines: &[], highlighter: makefile_syntax_highlighter },
+    Builtin { id: "vue", name: "Vue", extensions: &["vue"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: vue_syntax_highlighter },
+    Builtin { id: "svelte", name: "Svelte", extensions: &["svelte"], filenames: &[], interpreters: &[], first_lines: &[], highlighter: svelte_syntax_highlighter },
+    Builtin { id: "git-commit", name: "Git Commit Message", extensions: &[], filenames: &["COMMIT_EDITMSG", "MERGE_MSG", "TAG_EDITMSG", "SQUASH_MSG"], interpreters: &[], first_lines: &[], highlighter: git_commit_syntax_highlighter },
+    Builtin { id: "git-rebase", name: "Git Rebase Todo", extensions: &[], filenames: &["git-rebase-todo"], interpreters: &[], first_lines: &[], highlighter: git_rebase_syntax_highlighter },
+];
+
+/// Function to obtain a syntax highlighter based on a file name, for files that are
+/// recognised by their whole name rather than their extension (e.g. `Dockerfile`).
+/// A path can be given, in which case only the last component is used.
+/// Falls back to [from_extension] for any other file name, so the contents of files
+/// with a shared extension aren't looked at, use [from_extension_with_content] for those.
+pub fn from_filename(name: &str, tab_width: usize) -> Option<Highlighter> {
+    let result = try_from_filename(name, tab_width).map(|(result, _)| result);
+    Some(result.unwrap_or_else(|_| Highlighter::new(tab_width)))
+}
+
+/// Function to obtain a syntax highlighter based on a file name, along with information
+/// about the language it highlights. Like [from_filename], but returns an error rather
+/// than a plain text highlighter if the language isn't known.
+pub fn try_from_filename(name: &str, tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+    registry::builtins().from_filename(name, tab_width)
+}
+
+/// Function to obtain a syntax highlighter based on a file extension, using the first
+/// few lines of the file to choose between languages that share the extension.
+/// Like [from_extension], unknown extensions get a plain text highlighter.
+/// See [Registry::from_extension_with_content] for more details.
+pub fn from_extension_with_content(ext: &str, lines: &[String], tab_width: usize) -> Option<Highlighter> {
+    let result = registry::builtins().from_extension_with_content(ext, lines, tab_width).map(|(result, _)| result);
+    Some(result.unwrap_or_else(|_| Highlighter::new(tab_width)))
+}
+
+/// Function to obtain a syntax highlighter from the first few lines of a file,
+/// using shebangs (e.g. `#!/usr/bin/env python3`), `<?php`, XML declarations and vim or
+/// emacs modelines. This is useful for scripts that don't have an extension.
+/// See [Registry::detect] for more details.
+pub fn detect(first_lines: &[String], tab_width: usize) -> Option<Highlighter> {
+    registry::builtins().detect(first_lines, tab_width).ok().map(|(result, _)| result)
+}
+
+/// Function to obtain a syntax highlighter based on a file extension.
+/// Unknown extensions get a highlighter with no rules (plain text),
+/// use [try_from_extension] to tell the two apart.
+/// Extensions shared by several languages get the most common one, e.g. `.s` and `.S` files
+/// are always x86 assembly, use [from_extension_with_content] to pick RISC-V or ARM assembly.
+pub fn from_extension(ext: &str, tab_width: usize) -> Option<Highlighter> {
+    let result = try_from_extension(ext, tab_width).map(|(result, _)| result);
+    Some(result.unwrap_or_else(|_| Highlighter::new(tab_width)))
+}
+
+/// Function to obtain a syntax highlighter based on a file extension, along with
+/// information about the language it highlights.
+/// Returns an error rather than a plain text highlighter if the extension isn't known.
+///
+/// ```rust
+/// use synoptic::{try_from_extension, DetectError};
+///
+/// let (_, info) = try_from_extension("HPP", 4).unwrap();
+/// assert_eq!((info.id.as_str(), info.name.as_str()), ("cpp", "C++"));
+/// assert!(info.extensions.contains(&"hpp".to_string()));
+///
+/// let err = try_from_extension("xyz", 4).unwrap_err();
+/// assert_eq!(err, DetectError::UnknownExtension("xyz".to_string()));
+/// ```
+pub fn try_from_extension(ext: &str, tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+    registry::builtins().from_extension(ext, tab_width)
+}
+
+/// Indent after a line that ends with an opening bracket, and outdent lines that start with a closing one
+fn add_bracket_indents(h: &mut Highlighter) {
+    h.indent(r"[\{\[\(]\s*$");
+    h.outdent(r"^\s*[\}\]\)]");
+}
+
+fn add_html_keywords(h: &mut Highlighter, kw: &[&str]) {
+    h.keyword("keyword", &format!(r"(?:<|</|<!)({})\b", kw.join("|")));
+}
+
+fn add_keywords_no_boundary(h: &mut Highlighter, kw: &[&str]) {
+    h.keyword("keyword", &format!(r"({})", kw.join("|")));
+}
+
+fn add_keywords(h: &mut Highlighter, kw: &[&str]) {
+    h.keyword("keyword", &format!(r"\b({})\b", kw.join("|")));
+}
+
+fn add_keywords_case_indep(h: &mut Highlighter, kw: &[&str]) {
+    h.keyword("keyword", &format!(r"\b({})\b", kw.join("|")));
+    h.keyword(
+        "keyword",
+        &format!(
+            r"\b({})\b",
+            kw.iter()
+                .map(|x| x.to_uppercase())
+                .collect::<Vec<_>>()
+                .join("|")
+        ),
+    );
+}
+
+fn add_keywords_ignore_case(h: &mut Highlighter, kw: &[&str]) {
+    h.keyword("keyword", &format!(r"(?i)\b({})\b", kw.join("|")));
+}
+
+fn bulk_add(h: &mut Highlighter, name: &str, kw: &[&str]) {
+    h.keyword(name, &format!(r"({})", kw.join("|")));
+}
+
+fn rust_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded_nested("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "r#\"", "\"#", true);
+        result.bounded("string", "r\"", "\"", true);
+        result.bounded("string", "#\"", "\"#", true);
+        result.bounded("string", "\"", "\"", true);
+        result.bounded_balanced("attribute", r"\#\[", r"\]", r"\[", false);
+        result.bounded_balanced("attribute", r"\#!\[", r"\]", r"\[", false);
+        result.keyword("namespace", "([a-z_][A-Za-z0-9_]*)::");
+        add_keywords(&mut result, &[
+            "as", "break", "const", "continue", "char", "crate", "else", "enum", "extern",
+            "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+            "pub", "ref", "return", "self", "static", "struct", "super", "trait", "type",
+            "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become",
+            "box", "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual",
+            "yield", "try", "'static", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
+            "i32", "i64", "i128", "isize", "f32", "f64", "String", "Vec", "str", "Some",
+            "bool", "None", "Box", "Result", "Option", "Ok", "Err", "Self", "std",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            "&&", r"\|\|", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=",
+            "\\-=", "\\*=", "\\\\=", "==", "!=", "\\?", ">=", "<=", "<", ">", "!",
+        ]);
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f32|f64))"]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "fn\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
+            "fn\\s+([a-z_][A-Za-z0-9_]*)\\s*<.*>\\s*\\(",
+            "\\.([a-z_][A-Za-z0-9_]*)\\s*\\(",
+            "([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        ]);
+        bulk_add(&mut result, "struct", &[
+            "(?:trait|enum|struct|impl)\\s+([A-Z][A-Za-z0-9_]*)\\s*",
+            "impl(?:<.*?>|)\\s+([A-Z][A-Za-z0-9_]*)",
+            "([A-Z][A-Za-z0-9_]*)::",
+            "([A-Z][A-Za-z0-9_]*)\\s*\\(",
+            "impl.*for\\s+([A-Z][A-Za-z0-9_]*)",
+            "::\\s*([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        ]);
+        bulk_add(&mut result, "macro", &["\\b([a-z_][a-zA-Z0-9_]*!)", "(\\$[a-z_][A-Za-z0-9_]*)"]);
+        bulk_add(&mut result, "reference", &[
+            "&", "&str", "&mut", "&self", "&i8", "&i16", "&i32", "&i64", "&i128", "&isize",
+            "&u8", "&u16", "&u32", "&u64", "&u128", "&usize", "&f32", "&f64",
+        ]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn asm_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("function", "([a-zA-Z_]+)\\:$");
+        result.keyword("comment", "(;.*)$");
+        result.keyword("digit", "\\b((?:0x)?\\d+.\\d+|\\d+)");
+        result.bounded("string", "\"", "\"", true);
+        add_keywords_case_indep(
+            &mut result,
+            &[
+                "mov", "add", "sub", "jmp", "call", "ret", "bss", "data", "text", "section",
+                "globl", "extern", "db", "eax", "ebx", "ecx", "edx", "esp", "ebp", "int", "xor",
+                "imul", "inc", "jle", "cmp", "global", "section", "resb",
+            ],
+        );
+        result
+    })
+}
+
+fn python_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(#.*)$");
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
+        result.bounded("string", "\'\'\'", "\'\'\'", true);
+        result.bounded("string", "b\"", "\"", true);
+        result.bounded("string", "r\"", "\"", true);
+        result.bounded_interp("string", "f\"", "\"", "\\{", "\\}", true);
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "b\'", "\'", true);
+        result.bounded("string", "r\'", "\'", true);
+        result.bounded_interp("string", "f\'", "\'", "\\{", "\\}", true);
+        result.bounded("string", "\'", "\'", true);
+        add_keywords(&mut result, &[
+            "and", "as", "assert", "break", "class", "continue", "def", "del", "elif", "else", "except",
+            "exec", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "not",
+            "or", "pass", "print", "raise", "return", "try", "while", "with", "yield", "str", "bool",
+            "int", "tuple", "list", "dict", "tuple", "len", "None", "input", "type", "set", "range",
+            "enumerate", "open", "iter", "min", "max", "dir", "self", "isinstance", "help", "next",
+            "super", "match", "case",
+        ]);
+        result.multiline_keyword("attribute", r"^[ \t]*(@[\w.]+(?:\((?:[^()]|\([^()]*\))*\))?)", 5);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(\s//\s)", r"(%)", r"(\+=)",
+            r"(\-=)", r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)",
+        ]);
+        bulk_add(&mut result, "boolean", &["\\b(True)\\b", "\\b(False)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "def\\s+([a-z_][A-Za-z0-9_]*)",
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result.indent_scope("block", r"^\s*(?:async\s+)?(?:def|class|if|elif|else|for|while|try|except|finally|with|match|case)\b.*:\s*(?:#.*)?$");
+        add_bracket_indents(&mut result);
+        result.indent(r":\s*(?:#.*)?$");
+        result.outdent(r"^\s*(?:else|elif|except|finally)\b");
+        result
+    })
+}
+
+fn ruby_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(#.*)$");
+        result.bounded("comment", "=begin", "=end", false);
+        result.bounded_interp("string", "\"", "\"", "#\\{", "\\}", true);
+        result.bounded("string", "\'", "\'", true);
+        result.bounded_capture("string", r#"<<[~-]?['"]?([A-Z_][A-Za-z0-9_]*)['"]?"#, r"^\s*\1$", false);
+        result.keyword("string", r"(\:[a-zA-Z_]+)");
+        add_keywords(&mut result, &[
+            "__ENCODING__", "__LINE__", "__FILE__", "BEGIN", "END", "alias", "and", "begin", "break",
+            "case", "class", "def", "defined?", "do", "else", "elsif", "end", "ensure", "for", "if",
+            "in", "module", "next", "nil", "not", "or", "redo", "rescue", "retry", "return", "self",
+            "super", "then", "undef", "unless", "until", "when", "while", "yield", "extend", "include",
+            "attr_reader", "attr_writer", "attr_accessor",
+        ]);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+        bulk_add(&mut result, "operator", &[
+            "!!", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=",
+            "==", "!=", "\\?", ">=", "<=", "<", ">", "&&", "\\|\\|", "!", "&", "\\|", "\\^",
+            "%",
+        ]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "def\\s+([a-z_][A-Za-z0-9_]*)",
+            "^\\s*([a-z_][A-Za-z0-9_]*)\\s+[^=]",
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result.grammar_mut().word_chars = vec!['?', '!'];
+        result
+    })
+}
+
+fn cgi_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(#.*)$");
+        result.bounded_interp("string", "\"", "\"", "#\\{", "\\}", true);
+        result.bounded("string", "(?:m|s)/", "/", true);
+        result.bounded("string", "\'", "\'", true);
+        result.bounded_capture("string", r#"<<~?['"]?([A-Za-z_]\w*)['"]?"#, r"^\s*\1$", false);
+        result.keyword("string", r"(\:[a-zA-Z_]+)");
+        add_keywords(&mut result, &[
+            "if", "else", "elsif", "unless", "while", "for", "foreach", "until", "do", "next",
+            "last", "goto", "return", "sub", "my", "local", "our", "package", "use", "require",
+            "import", "undef", "and", "or", "not", "eq", "ne", "lt", "le", "gt", "ge", "cmp",
+            "qw", "scalar", "array", "hash", "undef", "undef", "ref", "bless", "glob", "filehandle",
+            "code", "regexp", "integer", "float", "string", "boolean", "reference", "die",
+        ]);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
+        bulk_add(&mut result, "operator", &[
+            "!!", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=",
+            "==", "!=", "\\?", ">=", "<=", "<", ">", "\\$","&&", "\\|\\|", "!", "&", "\\|",
+            "\\^", "(?:\\\\)?%", "\\\\@",
+        ]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "sub\\s+([a-z_][A-Za-z0-9_]*)",
+            "^\\s*([a-z_][A-Za-z0-9_]*)\\s+[^=]",
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result
+    })
+}
+
+fn lua_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"--\[\[", r"\]\]--", false);
+        result.keyword("comment", "(--.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "\'", "\'", true);
+        result.bounded("string", "\\[\\[", "\\]\\]", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)",
+            r"(\+=)", r"(\-=)", r"(\*=)", r"(\\=)", r"(\.\.)", r"(==)", r"(~=)",
+            r"(>=)", r"(<=)", r"(<)", r"(>)", r"(#)", r"(<<)", r"(>>)", r"\b(and)\b",
+            r"\b(or)\b", r"\b(not)\b",
+        ]);
+        add_keywords(&mut result, &[
+            "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in",
+            "local", "nil", "repeat", "return", "then", "true", "until", "while", "self",
+        ]);
+        result
+    })
+}
+
+fn r_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(#.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "\'", "\'", true);
+        bulk_add(&mut result, "boolean", &["\\b(FALSE)\\b", "\\b(TRUE)\\b"]);
+        add_keywords(&mut result, &[
+            "if", "else", "repeat", "while", "function", "for", "in", "next", "break", "TRUE",
+            "FALSE", "NULL", "Inf", "NaN", "NA", "NA_integer_", "NA_real_", "NA_complex_",
+            "NA_character_", r"\.\.\.",
+        ]);
+        result.keyword("attribute", "@.*$");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+        bulk_add(&mut result, "operator", &[
+            r"<-", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(\s//\s)", r"(&)", r"(%)",
+            r"(\+=)", r"(\-=)", r"(\*=)", r"(\\=)", r"(\$)", r"(|)", r"(==)", r"(!=)", r"(>=)",
+            r"(<=)", r"(<)", r"(>)", r"(\?)",
+        ]);
+        bulk_add(&mut result, "function", &[
+            "def\\s+([a-z_][A-Za-z0-9_]*)",
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result
+    })
+}
+
+fn go_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "`", "`", true);
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        add_keywords(&mut result, &[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough",
+            "for", "func", "go", "goto", "if", "import", "interface", "map", "package", "range",
+            "return", "select", "struct", "switch", "type", "var", "bool", "byte", "complex64", "complex128",
+            "error", "float32", "float64", "int", "int8", "int16", "int32", "int64", "rune", "string",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            ":=", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=",
+            "==", "!=", "\\?", ">=", "<=", "<", ">",
+        ]);
+        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f32|f64))"]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "func\\s+([A-Za-z0-9_]+)\\s*\\(",
+            "\\.([A-Za-z0-9_]+)\\s*\\(",
+            "([A-Za-z0-9_]+)\\s*\\(",
+        ]);
+        bulk_add(&mut result, "reference", &["&"]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn js_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        add_js_rules(&mut result);
+        result
+    })
+}
+
+/// The rules for JavaScript, which are shared with the languages that extend it
+fn add_js_rules(result: &mut Highlighter) {
+    result.bounded("comment", r"/\*", r"\*/", false);
+    result.keyword("comment", "//.*$");
+    result.bounded("string", "r\"", "\"", true);
+    result.bounded("string", "f\"", "\"", true);
+    result.bounded("string", "\"", "\"", true);
+    result.bounded("string", "r\'", "\'", true);
+    result.bounded("string", "f\'", "\'", true);
+    result.bounded("string", "\'", "\'", true);
+    result.bounded_interp("string", "r`", "`", "\\$\\{", "\\}", true);
+    result.bounded_interp("string", "f`", "`", "\\$\\{", "\\}", true);
+    result.bounded_interp("string", "`", "`", "\\$\\{", "\\}", true);
+    result.bounded("string", "/", "/", true);
+    add_keywords(result, &[
+        "abstract", "arguments", "await", "boolean", "break", "byte", "case", "catch", "char",
+        "class", "const", "continue", "debugger", "default", "delete", "do", "double", "else",
+        "enum", "eval", "export", "extends", "final", "finally", "float", "for", "of", "function",
+        "goto", "if", "implements", "import", "in", "instanceof", "int", "interface", "let", "long",
+        "native", "new", "null", "package", "private", "protected", "public", "return", "short",
+        "static", "super", "switch", "synchronized", "this", "throw", "throws", "transient", "try",
+        "typeof", "var", "void", "volatile", "console", "while", "with", "yield", "undefined", "NaN",
+        "-Infinity", "Infinity",
+    ]);
+    result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+    result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+    bulk_add(result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+    bulk_add(result, "function", &[
+        "function\\s+([a-z_][A-Za-z0-9_]*)",
+        "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        "\\.([a-z_][A-Za-z0-9_]*)\\s*",
+    ]);
+    bulk_add(result, "operator", &[
+        r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
+        r"(\-=)", r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)",
+        r"(>)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
+    ]);
+    result.fold("block", r"\{", r"\}");
+    add_bracket_indents(result);
+}
+
+fn ts_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        add_ts_rules(&mut result);
+        result
+    })
+}
+
+/// The rules for TypeScript, which are shared with the languages that extend it
+fn add_ts_rules(result: &mut Highlighter) {
+    result.bounded("comment", r"/\*", r"\*/", false);
+    result.keyword("comment", "//.*$");
+    result.bounded("string", "r\"", "\"", true);
+    result.bounded("string", "f\"", "\"", true);
+    result.bounded("string", "\"", "\"", true);
+    result.bounded("string", "r\'", "\'", true);
+    result.bounded("string", "f\'", "\'", true);
+    result.bounded("string", "\'", "\'", true);
+    result.bounded_interp("string", "r`", "`", "\\$\\{", "\\}", true);
+    result.bounded_interp("string", "f`", "`", "\\$\\{", "\\}", true);
+    result.bounded_interp("string", "`", "`", "\\$\\{", "\\}", true);
+    result.bounded("string", "/", "/", true);
+    add_keywords(result, &[
+        "abstract", "any", "as", "asserts", "boolean", "break", "case", "catch", "class", "const", "constructor",
+        "continue", "debugger", "declare", "default", "delete", "do", "else", "enum", "export", "extends", "false",
+        "finally", "for", "from", "function", "get", "if", "implements", "import", "in", "infer", "instanceof",
+        "interface", "is", "keyof", "let", "module", "namespace", "never", "new", "null", "number", "object", "package",
+        "private", "protected", "public", "readonly", "require", "global", "return", "set", "static", "string",
+        "super", "switch", "symbol", "this", "throw", "true", "try", "type", "typeof", "undefined", "unique", "unknown",
+        "var", "void", "while", "with", "yield",
+    ]);
+    result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+    result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+    bulk_add(result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+    bulk_add(result, "function", &[
+        "function\\s+([a-z_][A-Za-z0-9_]*)",
+        "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        "\\.([a-z_][A-Za-z0-9_]*)\\s*",
+    ]);
+    bulk_add(result, "operator", &[
+        r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
+        r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
+        r"(\&\&)", r"(\|\|)", r"(!)\S",
+    ]);
+}
+
+fn dart_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded_nested("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "//.*$");
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
+        result.bounded("string", "\'\'\'", "\'\'\'", true);
+        result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
+        result.bounded("string", "\'", "\'", true);
+        add_keywords(&mut result, &[
+            "abstract", "as", "assert", "async", "await", "break", "case", "catch", "class", "const", "continue", "covariant", "default",
+            "deferred", "do", "dynamic", "else", "enum", "export", "extends", "extension", "external", "factory", "false", "final", "finally",
+            "for", "Function", "get", "hide", "if", "implements", "import", "in", "inout", "interface", "is", "late", "library", "mixin",
+            "new", "null", "on", "operator", "out", "part", "required", "rethrow", "return", "set", "show", "static", "super", "switch",
+            "sync", "this", "throw", "true", "try", "typedef", "var", "void", "while", "with", "yield", "int", "double", "num", "string",
+        ]);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]+)");
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "\\b([a-z_][A-Za-z0-9_]*)(?:<[A-Za-z_]*>)?\\s*\\(",
+            "\\.([a-z_][A-Za-z0-9_]*)\\s*",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
+            r"(\-=)", r"(\*=)", r"(\\=)", "~/", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)",
+            r"(>)", "\\?", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", "\\?\\?",
+        ]);
+        result
+    })
+}
+
+fn c_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "\"", "\"", true);
+        add_keywords(&mut result, &[
+            "auto", "break", "case", "char", "const", "continue", "default", "do", "double",
+            "else", "enum", "extern", "float", "for", "goto", "if", "int", "long", "register",
+            "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
+            "union", "unsigned", "void", "volatile", "while", "printf", "fscanf", "scanf",
+            "fputsf", "exit", "stderr", "malloc", "calloc", "bool", "realloc", "free",
+            "strlen", "size_t",
+        ]);
+        result.keyword("struct", "\\}\\s+([A-Za-z0-9_]+)\\s*");
+        result.keyword("attribute", "^\\s*(#.*?)\\s");
+        result.keyword("header", "(<.*?>)");
+        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f|))"]);
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "(int|bool|void|char|double|long|short|size_t)\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
+            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)",
+            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)",
+            r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
+        ]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn cpp_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        add_cpp_rules(&mut result);
+        result
+    })
+}
+
+/// The rules for C++, which are shared with the languages that extend it
+fn add_cpp_rules(result: &mut Highlighter) {
+    result.bounded("comment", r"/\*", r"\*/", false);
+    result.keyword("comment", "(//.*)$");
+    result.bounded("string", "\"", "\"", true);
+    add_keywords(result, &[
+        "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break", "case",
+        "catch", "char", "char8_t", "char16_t", "char32_t", "class", "compl", "concept", "const", "consteval", "constexpr",
+        "constinit", "const_cast", "continue", "co_await", "co_return", "co_yield", "decltype", "default",
+        "delete", "do", "double", "dynamic_cast", "else", "enum", "explicit", "export", "extern", "false", "float",
+        "for", "friend", "goto", "if", "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq",
+        "nullptr", "operator", "or", "or_eq", "private", "protected", "public", "register", "reinterpret_cast", "requires", "return",
+        "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct", "switch", "template", "this",
+        "thread_local", "throw", "true", "try", "typedef", "typeid", "typename", "union", "unsigned", "using", "virtual",
+        "void", "volatile", "wchar_t", "while", "xor", "xor_eq", "std", "string",
+    ]);
+    result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
+    result.keyword("attribute", "^\\s*(#[a-zA-Z_]+)\\s*");
+    bulk_add(result, "operator", &[
+        r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)",
+        r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)",
+        r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(|)", r"(&)", r"(^)", r"(~)",
+    ]);
+    result.keyword("header", "(<.*?>)");
+    bulk_add(result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f|))"]);
+    bulk_add(result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+    bulk_add(result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+    bulk_add(result, "function", &[
+        "(int|bool|void|char|double|long|short|size_t)\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+    ]);
+    result.fold("block", r"\{", r"\}");
+    add_bracket_indents(result);
+}
+
+fn cs_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "\"", "\"", true);
+        add_keywords(&mut result, &[
+            "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked",
+            "class", "const", "continue", "decimal", "default", "delegate", "do", "double", "else",
+            "enum", "event", "explicit", "extern", "false", "finally", "fixed", "float", "for",
+            "foreach", "goto", "if", "implicit", "in", "int", "interface", "internal", "is", "lock",
+            "long", "namespace", "new", "null", "object", "operator", "out", "override", "params",
+            "private", "protected", "public", "readonly", "ref", "return", "sbyte", "sealed",
+            "short", "sizeof", "stackalloc", "static", "string", "struct", "switch", "this", "throw",
+            "true", "try", "typeof", "uint", "ulong", "unchecked", "unsafe", "ushort", "using",
+            "using", "static", "virtual", "void", "volatile", "while", "add", "alias", "ascending", "async",
+            "await", "by", "descending", "dynamic", "equals", "from", "get", "global", "group",
+            "into", "join", "let", "nameof", "on", "orderby", "partial", "remove", "select", "set",
+            "unmanaged", "value", "var", "when", "where", "with", "yield",
+        ]);
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)",
+            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)",
+            r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(|)", r"(&)", r"(^)", r"(~)",
+        ]);
+        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f|m|))"]);
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "(int|bool|void|char|double|long|short|size_t)\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
+            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        ]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn swift_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded_nested("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded_interp("string", "#\"", "\"#", "\\\\#?\\(", "\\)", true);
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
+        result.bounded_interp("string", "\"", "\"", "\\\\\\(", "\\)", true);
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        add_keywords(&mut result, &[
+            "associatedtype", "class", "deinit", "enum", "extension", "fileprivate", "func",
+            "import", "init", "inout", "internal", "let", "open", "operator", "private",
+            "protocol", "public", "static", "struct", "subscript", "typealias", "var", "break",
+            "case", "continue", "default", "defer", "do", "else", "fallthrough", "for", "guard",
+            "if", "in", "repeat", "return", "switch", "where", "while", "as", "catch", "throw",
+            "try", "Any", "false", "is", "nil", "super", "self", "Self", "true", "associativity",
+            "convenience", "dynamic", "didSet", "final", "get", "infix", "indirect", "lazy", "left",
+            "mutating", "none", "nonmutating", "optional", "override", "postfix", "precedence", "prefix",
+            "Protocol", "required", "right", "set", "Type", "unowned", "weak", "willSet", "Int",
+            "String", "Double", "Optional", "endif",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=", "==",
+            "!=", "\\?", ">=", "<=", "<", ">", "!",
+        ]);
+        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f32|f64))"]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &[
+            "func\\s+([a-z_][A-Za-z0-9_]*)\\s*(?:\\(|<)",
+            "\\.([a-z_][A-Za-z0-9_]*)\\s*\\(",
+            "([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        ]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn json_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("keyword", r"\b(null)\b");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result.fold("array", r"\[", r"\]");
+        result
+    })
+}
+
+fn kotlin_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded_nested("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("attribute", r"@\w+");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
+            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
+            r"(\&\&)", r"(\|\|)", r"(!)\S",
+        ]);
+        add_keywords(&mut result, &[
+            "abstract", "actual", "annotation", "companion", "constructor", "enum", "external", "expect",
+            "final", "fun", "inline", "inner", "interface", "internal", "private", "protected", "public",
+            "sealed", "suspend", "tailrec", "vararg", "as", "break", "class", "continue", "do", "else",
+            "false", "for", "if", "in", "is", "null", "object", "infix", "package", "return", "super", "this",
+            "throw", "true", "try", "data", "typealias", "typeof", "val", "when", "while", "var", "operator",
+            "override",
+        ]);
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\{",
+        ]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn java_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("attribute", r"@\w+");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
+            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
+            r"(\&\&)", r"(\|\|)", r"(!)\S",
+        ]);
+        add_keywords(&mut result, &[
+            "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const", "continue",
+            "default", "do", "double", "else", "enum", "extends", "final", "finally", "float", "for", "if", "goto",
+            "implements", "import", "instanceof", "int", "interface", "long", "native", "new", "package", "private",
+            "protected", "public", "return", "short", "static", "strictfp", "super", "switch", "synchronized", "this",
+            "throw", "throws", "transient", "try", "var", "void", "volatile", "while", "null",
+        ]);
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result.fold("block", r"\{", r"\}");
+        add_bracket_indents(&mut result);
+        result
+    })
+}
+
+fn vb_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "('.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        bulk_add(&mut result, "function", &["\\b([A-Za-z0-9_\\?!]*)\\s*\\("]);
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
+            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
+            r"(\&\&)", r"(\|\|)", r"(!)\S",
+        ]);
+        add_keywords(&mut result, &[
+            "AddHandler", "AddressOf", "Alias", "And", "AndAlso", "Ansi", "As", "Assembly", "Auto", "Boolean",
+            "ByRef", "Byte", "ByVal", "Call", "Case", "Catch", "CBool", "CByte", "CChar", "CDate", "CDec", "CDbl",
+            "Char", "CInt", "Class", "CLng", "CObj", "Const", "CShort", "CSng", "CStr", "CType", "Date", "Decimal",
+            "Declare", "Default", "Delegate", "Dim", "DirectCast", "Do", "Double", "Each", "Else", "ElseIf", "End",
+            "Enum", "Erase", "Error", "Event", "Exit", "False", "Finally", "For", "Friend", "Function", "Get", "GetType",
+            "GoSub", "GoTo", "Handles", "If", "Implements", "Imports", "In", "Inherits", "Integer", "Interface",
+            "Is", "IsNot", "Let", "Lib", "Like", "Long", "Loop", "Me", "Mod", "Module", "MustInherit", "MustOverride",
+            "MyBase", "MyClass", "Namespace", "Narrowing", "New", "Next", "Not", "Nothing", "NotInheritable",
+            "NotOverridable", "Object", "Of", "On", "Operator", "Option", "Optional", "Or", "OrElse", "Out", "Overloads",
+            "Overridable", "Overrides", "ParamArray", "Partial", "Private", "Property", "Protected", "Public", "RaiseEvent",
+            "ReadOnly", "ReDim", "REM", "RemoveHandler", "Resume", "Return", "SByte", "Select", "Set", "Shadows", "Shared",
+            "Short", "Single", "Static", "Step", "Stop", "String", "Structure", "Sub", "SyncLock", "Then", "Throw", "To",
+            "True", "Try", "TryCast", "TypeOf", "UInteger", "ULong", "UShort", "Using", "Variant", "Wend", "When", "While",
+            "Widening", "With", "WithEvents", "WriteOnly", "Xor", "Console",
+        ]);
+        result
+    })
+}
+
+fn m_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", "%\\{", "%\\}", true);
+        result.keyword("comment", "(%.*)$");
+        result.bounded("string", "\'", "\'", true);
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
+            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
+            r"(\&\&)", r"(\|\|)", r"(!)\S",
+        ]);
+        add_keywords(&mut result, &[
+            "break", "case", "catch", "classdef", "continue", "else", "elseif", "end", "for", "function",
+            "global", "if", "otherwise", "parfor", "persistent", "return", "spmd", "switch", "try", "while",
+            "inf", "nan", "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "single",
+            "double", "char", "string", "cell", "struct", "table", "datetime", "properties", "NaN", "max",
+            "min", "length", "sort", "sum", "prod", "mode", "median", "mean", "std", "pi", "randi", "randn",
+            "rand", "clf", "shg", "close", "path", "addpath", "rmpath", "cd", "grid", "on", "axis", "square",
+            "equal", "off", "hold", "help", "doc", "lookfor", "profile", "viewer", "clc", "diary", "ctrl-c", "who",
+            "whos", "clear", "load", "format", "short", "long", "bank",
+        ]);
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result
+    })
+}
+
+fn php_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.keyword("comment", "(#.*)$");
+        result.bounded_interp("string", "\"", "\"", "\\{", "\\}", true);
+        result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
+        result.bounded("string", "\'", "\'", true);
+        result.keyword("boolean", "\\b(true|false|TRUE|FALSE)\\b");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        add_keywords(&mut result, &[
+            "__halt_compiler", "abstract", "and", "array", "as", "break", "callable", "case",
+            "catch", "class", "clone", "const", "continue", "declare", "default", "die", "do",
+            "echo", "else", "elseif", "empty", "enddeclare", "endfor", "endforeach", "endif", 
+            "endswitch", "endwhile", "eval", "exit", "extends", "final", "finally", "for", 
+            "foreach", "function", "global", "goto", "if", "implements", "include", "include_once",
+            "instanceof", "insteadof", "interface", "isset", "list", "namespace", "new", "or",
+            "print", "private", "protected", "public", "require", "require_once", "return", "static",
+            "switch", "throw", "trait", "try", "unset", "use", "var", "while", "xor",
+            "__CLASS__", "__DIR__", "__FILE__", "__FUNCTION__", "__LINE__", "__METHOD__",
+            "__NAMESPACE__", "__TRAIT__", "null",
+        ]);
+        result.keyword("keyword", r"<\?php");
+        result.keyword("keyword", r"\?>");
+        bulk_add(&mut result, "operator", &[
+            r"(->)", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
+            r"(\-=)", r"(\*=)", r"(\\=)", r"(\?)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)",
+            r"(>)", r"(\$)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(\.)",
+        ]);
+        result
+    })
+}
+
+fn scala_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded_nested("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded_interp("string", "f\"", "\"", "\\$\\{", "\\}", true);
+        result.bounded_interp("string", "s\"", "\"", "\\$\\{", "\\}", true);
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
+        result.bounded("string", "raw\"", "\"", true);
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        result.keyword("boolean", "\\b(true|false)\\b");
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)", r"(\*=)", r"(\\=)",
+            r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
+        ]);
+        add_keywords(&mut result, &[
+            "abstract", "case", "catch", "class", "def", "do", "else", "extends", "false", "final", "finally",
+            "for", "forSome", "if", "implicit", "import", "lazy", "macro", "match", "new", "null", "object",
+            "override", "package", "private", "protected", "return", "sealed", "super", "this", "throw", "trait",
+            "try", "true", "type", "val", "var", "while", "with", "yield", "Boolean", "Byte", "Char", "Double",
+            "Float", "Int", "Long", "Short", "String", "Unit", "Any", "AnyVal", "AnyRef", "Nothing", "Null",
+            "foreach", "map", "println", "to", "by",
+        ]);
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
+            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        ]);
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        result
+    })
+}
+
+fn prolog_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(\\%.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        add_keywords_no_boundary(&mut result, &[
+            ":-", "\\,", "\\.", ";", "\\->", "\\+", "=", "is", "not", "fail", "!", "repeat", "call", "cut",
+            "assert", "asserta", "assertz", "retract", "abolish", "dynamic", "consult", "listing", "op",
+            "assertions", "clauses", "predicate", "query", "rule", "fact", "variable", "atom", "number",
+            "list", "compound", "ground", "callable", "atom", "number", "integer", "float", "variable",
+            "list", "compound",
+        ]);
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(<)", r"(>)",
+        ]);
+        bulk_add(&mut result, "function", &["\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\("]);
+        result
+    })
+}
+
+fn haskell_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(\\-\\-.*)$");
+        result.bounded_nested("comment", "\\{-", "-\\}", true);
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("boolean", "\\b(True|False)\\b");
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        bulk_add(&mut result, "operator", &[
+            "->", "\\$", "`.*`", "<-", "<", ">", "&&", "\\|\\|", "\\\\", "\\:",
+            "=", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
+            r"(\-=)", r"(\*=)", r"(/=)", "!", "\\.", "\\|", r"(==)", r"(!=)", r"(>=)",
+            r"(<=)", "_", r"(<<)", r"(>>)", r"(!)\S", "\\band\\b", "\\bor\\b", "\\bnot\\b",
+        ]);
+        add_keywords(&mut result, &[
+            "module", "import", "as", "qualified", "hiding", "do", "case", "of", "let", "in", "if", "then", "else",
+            "data", "type", "newtype", "deriving", "class", "instance", "where", "foreign", "export", "ccall",
+            "stdcall", "capi", "prim", "safe", "unsafe", "otherwise", "head", "tail", "last", "init", "null",
+            "length", "return", "map", "filter", "foldl", "foldr", "zip", "zipWith", "take", "drop", "reverse",
+            "concat", "concatMap", "maximum", "minimum", "elem", "notElem", "sum", "array", "product", "scanl",
+            "scanr", "replicate", "cycle", "repeat", "iterate", "fst", "snd", "id", "Maybe", "Either", "Bool",
+            "Char", "String", "putStrLn", "getLine", "Just", "Nothing", "for", "Int", "Integer", "Float",
+            "Double", "Ordering", "IO", "Functor", "Applicative", "Monad",
+        ]);
+        result.keyword("function", "^[a-z][a-zA-Z0-9]*");
+        result.grammar_mut().word_chars = vec!['\''];
+        result
+    })
+}
+
+fn css_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        add_css_rules(&mut result);
+        result
+    })
+}
+
+/// The rules for CSS, which are shared with the languages that extend it
+fn add_css_rules(result: &mut Highlighter) {
+    result.bounded("comment", r"/\*", r"\*/", false);
+    result.bounded("string", "\"", "\"", true);
+    add_keywords(result, &["from", "to", "rotate", "none"]);
+    result.keyword("digit", r"\#[0-9a-fA-F]+");
+    result.keyword("digit", "((?:\\d+.\\d+|\\d+)(?:%|deg|px|em|rem)?)");
+    result.keyword("boolean", "\\b(true|false)\\b");
+    result.keyword("attribute", r"\.[a-zA-Z0-9\-]*");
+    result.keyword("attribute", r"\:[a-zA-Z0-9\-]*");
+    result.keyword("attribute", r"\::[a-zA-Z0-9\-]*");
+    result.keyword("attribute", r"@\w+");
+    add_keywords(result, &[
+        "a", "abbr", "address", "area", "article", "aside", "audio", "b", "base", "bdi", "bdo", "blockquote",
+        "body", "br", "button", "canvas", "caption", "cite", "code", "col", "colgroup", "data", "datalist",
+        "dd", "del", "details", "dfn", "dialog", "div", "dl", "dt", "em", "embed", "fieldset", "figcaption",
+        "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr",
+        "html", "i", "iframe", "img", "input", "ins", "kbd", "label", "legend", "li", "link", "main", "map",
+        "mark", "meta", "meter", "nav", "noscript", "object", "ol", "optgroup", "option", "output", "p",
+        "param", "picture", "pre", "progress", "q", "rb", "rp", "rt", "rtc", "ruby", "s", "samp", "script",
+        "section", "select", "slot", "small", "source", "span", "strong", "style", "sub", "summary", "sup", 
+        "table", "tbody", "td", "template", "textarea", "tfoot", "th", "thead", "time", "title", "tr", "track",
+        "u", "ul", "var", "video", "wbr", "svg",
+    ]);
+    add_keywords(result, &[
+        "-webkit-touch-callout", "-webkit-user-select", "-moz-user-select", "-ms-user-select",
+        "user-select", "transform", "border-radius", "border-right", "border-left", "border-top",
+        "border-bottom", "border", "content", "display", "height", "width", "margin-top", "margin-bottom",
+        "margin-left", "margin-right", "margin", "pointer-events", "position", "top", "transform-origin",
+        "-moz-appearance", "-webkit-appearance", "cursor", "flex-grow", "flex-shrink", "font-size",
+        "max-height", "max-width", "min-height", "min-width", "outline", "vertical-align", "background-color", 
+        "background-image", "background-position", "background-repeat", "background-size", "background",
+        "animation", "border-(?:left|right|top|bottom)-color", "border-(?:left|right|top|bottom)-radius",
+        "border-(?:left|right|top|bottom)-width", "border-(?:left|right|top|bottom)-style", "align-items",
+        "box-shadow", "justify-content", "line-height", "padding", "padding-(?:left|bottom|right|top)", "font-weight",
+        "list-style", "box-sizing", "text-align", "bottom", "overflow-x", "overflow-y", "text-rendering",
+        "-moz-osx-font-smoothing", "-webkit-font-smoothing", "text-size-adjust", "font-family", "color",
+        "text-decoration", "font-style", "word-wrap", "white-space", "-webkit-overflow-scrolling",
+        "clear", "float", "overflow", "!important", "text-transform", "clip", "visibility", "border-color",
+        "opacity", "flex-wrap", "border-(?:top|bottom)-(?:left|right)-radius", "z-index", "word-break", "letter-spacing",
+        "text-transform", "resize", "flex-direction", "order", "border-style", "border-width", "text-overflow",
+        "flex-basis", "-ms-overflow-y", "-ms-overflow-x", "transition-duration", "transition-property", 
+        "transition-timing-function", "(flex)[^-]", "-webkit-text-decoration-style", "-apple-system", "sans-serif",
+        "left", "right", "bottom", "top", "font", "tab-size", "text-shadow",
+    ]);
+    result.grammar_mut().word_chars = vec!['-'];
+    result.fold("block", r"\{", r"\}");
+    add_bracket_indents(result);
+}
+
+fn html_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        add_html_rules(&mut result);
+        result
+    })
+}
+
+/// The rules for HTML, which are shared with the languages that extend it
+fn add_html_rules(result: &mut Highlighter) {
+    result.bounded("comment", "<!--", "-->", false);
+    result.bounded("string", "\"", "\"", true);
+    result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+    result.keyword("boolean", "\\b(true|false)\\b");
+    result.keyword("operator", "=");
+    bulk_add(result, "tag", &["</", "/>", ">", "<!", "<"]);
+    add_html_keywords(result, &[
+        "a", "abbr", "address", "area", "article", "aside", "audio", "b", "base", "bdi", "bdo", "blockquote",
+        "body", "br", "button", "canvas", "caption", "cite", "code", "col", "colgroup", "data", "datalist",
+        "dd", "del", "details", "dfn", "dialog", "div", "dl", "dt", "em", "embed", "fieldset", "figcaption", 
+        "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr", "html",
+        "i", "iframe", "img", "input", "ins", "kbd", "label", "legend", "li", "link", "main", "map", "mark",
+        "meta", "meter", "nav", "noscript", "object", "ol", "optgroup", "option", "output", "p", "param", "picture",
+        "pre", "progress", "q", "rb", "rp", "rt", "rtc", "ruby", "s", "samp", "script", "section", "select", "slot",
+        "small", "source", "span", "strong", "style", "sub", "summary", "sup", "table", "tbody", "td", "template",
+        "textarea", "tfoot", "th", "thead", "time", "title", "tr", "track", "u", "ul", "var", "video", "wbr", "svg",
+    ]);
+    bulk_add(result, "attribute", &[
+        r"([A-Za-z0-9-]+)=", r"(class)\s*=", r"(id)\s*=", r"(style)\s*=", r"(src)\s*=", r"(rel)\s*=",
+        r"(type)\s*=", r"(charset)\s*=", r"(data-target)\s*=", r"(name)\s*=", r"(href)\s*=", r"(content)\s*=",
+        r"(width)\s*=", r"(height)\s*=", r"(aria-label)\s*=", r"(role)\s*=", r"(aria-hidden)\s*=",
+        r"(aria-expanded)\s*=", r"\s*defer\s*",
+    ]);
+    result.embed(r"<script\b[^>]*>", "</script>", js_syntax_highlighter());
+    result.embed(r"<style\b[^>]*>", "</style>", css_syntax_highlighter());
+    result.grammar_mut().word_chars = vec!['-'];
+}
+
+fn markdown_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // Fenced code blocks, highlighted using the language named in their info string
+        result.keyword("block", r"^\s*(```|~~~)[^`]*$");
+        result.keyword("info", r"^\s*(?:```|~~~)\s*([^`\s][^`]*?)\s*$");
+        let fence = r"^\s*(?:```|~~~)\s*$";
+        for (names, highlighter) in markdown_fence_languages() {
+            result.embed(format!(r"^\s*(?:```|~~~)\s*(?:{names})(?:\s[^`]*)?$"), fence.to_string(), highlighter);
+        }
+        // Code blocks in any other language are highlighted as a whole
+        let mut plain = Highlighter::new(4);
+        plain.keyword("block", ".+");
+        result.embed(r"^\s*(?:```|~~~)[^`]*$", fence, &plain);
+        result.bounded("comment", "<!--", "-->", false);
+        result.keyword("heading", "(#.*)$");
+        result.keyword("quote", "^(>.*)$");
+        result.bounded("bold", "\\*\\*", "\\*\\*", true);
+        result.bounded("italic", "\\*", "\\*", true);
+        result.bounded("strikethrough", "~~", "~~", true);
+        result.bounded("image", "!\\[", "\\]", true);
+        result.bounded("link", "\\[", "\\]", true);
+        result.bounded("math", "\\$\\$", "\\$\\$", false);
+        result.bounded("math", "\\$", "\\$", false);
+        result.bounded("block", "`", "`", true);
+        result.keyword("link", r"\b(?:https?://|www\.)\S+\b");
+        result.keyword("linebreak", "^\\s*-{3}");
+        result.keyword("list", "[0-9]+\\.");
+        result.keyword("list", "^\\s*-");
+        result.keyword("list", "^\\s*\\+");
+        result.grammar_mut().prose_kinds = ["comment", "heading", "quote", "bold", "italic", "strikethrough"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        result.grammar_mut().prose_plain = true;
+        result
+    })
+}
+
+/// The languages that fenced code blocks in Markdown can be highlighted in, by info string
+fn markdown_fence_languages() -> Vec<(&'static str, &'static Highlighter)> {
+    vec![
+        ("rust|rs", rust_syntax_highlighter()),
+        ("python|py|python3", python_syntax_highlighter()),
+        ("javascript|js|mjs|cjs", js_syntax_highlighter()),
+        ("typescript|ts", ts_syntax_highlighter()),
+        ("jsx", jsx_syntax_highlighter()),
+        ("tsx", tsx_syntax_highlighter()),
+        ("html|htm", html_syntax_highlighter()),
+        ("css", css_syntax_highlighter()),
+        ("scss", scss_syntax_highlighter()),
+        ("json|jsonc", json_syntax_highlighter()),
+        ("toml", toml_syntax_highlighter()),
+        ("yaml|yml", yaml_syntax_highlighter()),
+        ("bash|sh|shell", shell_syntax_highlighter()),
+        ("zsh", zsh_syntax_highlighter()),
+        ("powershell|ps1", powershell_syntax_highlighter()),
+        ("c|h", c_syntax_highlighter()),
+        ("cpp|c\\+\\+|cxx|hpp", cpp_syntax_highlighter()),
+        ("csharp|cs|c#", cs_syntax_highlighter()),
+        ("go|golang", go_syntax_highlighter()),
+        ("java", java_syntax_highlighter()),
+        ("kotlin|kt", kotlin_syntax_highlighter()),
+        ("swift", swift_syntax_highlighter()),
+        ("ruby|rb", ruby_syntax_highlighter()),
+        ("php", php_syntax_highlighter()),
+        ("lua", lua_syntax_highlighter()),
+        ("haskell|hs", haskell_syntax_highlighter()),
+        ("sql", sql_syntax_highlighter()),
+        ("xml|svg", xml_syntax_highlighter()),
+        ("diff|patch", diff_syntax_highlighter()),
+        ("dockerfile|docker", dockerfile_syntax_highlighter()),
+        ("makefile|make", makefile_syntax_highlighter()),
+        ("mermaid", mermaid_syntax_highlighter()),
+    ]
+}
+
+fn toml_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "\'", "\'", true);
+        result.keyword("comment", "(#.*)$");
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("table", r"^(\[.*\])");
+        bulk_add(&mut result, "digit", &[
+            r"(?:=|\[|,)\s*(0x[a-fA-F]+)",
+            r"(?:=|\[|,)\s*(0o[0-7]+)",
+            r"(?:=|\[|,)\s*(0b[0-1]+)",
+            r"(?:=|\[|,)\s*((?:\+|-)?[0-9]+(?:\.[0-9]+)?(?:e|E)(?:\+|-)?[0-9]+)",
+            r"(?:=|\[|,)\s*((?:\+|-)?[0-9_]+(?:\.[0-9]+)?)",
+        ]);
+        add_keywords(&mut result, &["inf", "nan"]);
+        result
+    })
 }
 
-/// Function to obtain a syntax highlighter based on a file extension
-pub fn from_extension(ext: &str, tab_width: usize) -> Option<Highlighter> {
-    let mut result = match ext.to_lowercase().as_str() {
-        "rs" => rust_syntax_highlighter().to_owned(),
-        "asm" | "s" => asm_syntax_highlighter().to_owned(),
-        "py" | "pyw" => python_syntax_highlighter().to_owned(),
-        "rb" | "ruby" => ruby_syntax_highlighter().to_owned(),
-        "cgi" | "pm" => cgi_syntax_highlighter().to_owned(),
-        "lua" => lua_syntax_highlighter().to_owned(),
-        "r" | "rproj" => r_syntax_highlighter().to_owned(),
-        "go" => go_syntax_highlighter().to_owned(),
-        "js" => js_syntax_highlighter().to_owned(),
-        "ts" | "tsx" => ts_syntax_highlighter().to_owned(),
-        "dart" => dart_syntax_highlighter().to_owned(),
-        "c" | "h" => c_syntax_highlighter().to_owned(),
-        "cpp" | "hpp" | "c++" | "cxx" | "cc" => cpp_syntax_highlighter().to_owned(),
-        "cs" | "csproj" => cs_syntax_highlighter().to_owned(),
-        "swift" => swift_syntax_highlighter().to_owned(),
-        "json" => json_syntax_highlighter().to_owned(),
-        "kt" => kotlin_syntax_highlighter().to_owned(),
-        "class" | "java" => java_syntax_highlighter().to_owned(),
-        "vb" => vb_syntax_highlighter().to_owned(),
-        "m" => m_syntax_highlighter().to_owned(),
-        "php" => php_syntax_highlighter().to_owned(),
-        "scala" => scala_syntax_highlighter().to_owned(),
-        "pl" | "prolog" => prolog_syntax_highlighter().to_owned(),
-        "hs" => haskell_syntax_highlighter().to_owned(),
-        "css" => css_syntax_highlighter().to_owned(),
-        "html" | "htm" | "xhtml" => html_syntax_highlighter().to_owned(),
-        "md" | "markdown" => markdown_syntax_highlighter().to_owned(),
-        "toml" => toml_syntax_highlighter().to_owned(),
-        "yaml" | "yml" => yaml_syntax_highlighter().to_owned(),
-        "csv" => csv_syntax_highlighter().to_owned(),
-        "sh" | "bash" | "bash_profile" | "bashrc" => shell_syntax_highlighter().to_owned(),
-        "sql" | "sqlproj" => sql_syntax_highlighter().to_owned(),
-        "xml" => xml_syntax_highlighter().to_owned(),
-        "nu" => nushell_syntax_highlighter().to_owned(),
-        "tex" => tex_syntax_highlighter().to_owned(),
-        "diff" => diff_syntax_highlighter().to_owned(),
-        _ => Highlighter::new(tab_width),
-    };
-    result.tab_width = tab_width;
-    Some(result)
+fn yaml_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "\'", "\'", true);
+        result.keyword("comment", "(#.*)$");
+        result.keyword("key", r"^\s*[ \.a-zA-Z_-]+:");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("tag", "!!(?:bool|int|float|str|timestamp|null|binary)");
+        add_keywords(&mut result, &["No", "Yes", "no", "yes", "true", "false", "null"]);
+        result.indent_scope("mapping", r"^\s*(?:-\s+)?[^\s#-][^#]*:\s*(?:[|>][-+0-9]*)?\s*(?:#.*)?$");
+        result.indent_scope("item", r"^\s*-\s");
+        result
+    })
 }
 
-fn add_html_keywords(h: &mut Highlighter, kw: &[&str]) {
-    h.keyword("keyword", &format!(r"(?:<|</|<!)({})\b", kw.join("|")));
+fn csv_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("keyword", ",");
+        result
+    })
 }
 
-fn add_keywords_no_boundary(h: &mut Highlighter, kw: &[&str]) {
-    h.keyword("keyword", &format!(r"({})", kw.join("|")));
+fn shell_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        add_shell_rules(&mut result);
+        result
+    })
 }
 
-fn add_keywords(h: &mut Highlighter, kw: &[&str]) {
-    h.keyword("keyword", &format!(r"\b({})\b", kw.join("|")));
+/// The rules for shell scripts, which are shared with the shells that extend them
+fn add_shell_rules(result: &mut Highlighter) {
+    result.bounded_interp("string", "\"", "\"", "\\$\\(", "\\)", true);
+    result.bounded("string", "\'", "\'", true);
+    // Here strings (<<<) come first so that they aren't mistaken for heredocs
+    result.keyword("operator", r"(<<<)");
+    result.bounded_capture("string", r#"<<-?\s*['"]?([A-Za-z_]\w*)['"]?"#, r"^\s*\1$", false);
+    result.keyword("comment", "(#.*)$");
+    result.keyword("boolean", "\\b(true|false)\\b");
+    result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+    bulk_add(result, "operator", &[
+        r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)", r"(\*=)",
+        r"(\\=)", r"(\{)", r"(\})", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(\$)", r"(\.\.)",
+        r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(\.)", r"(&)",
+    ]);
+    add_keywords(result, &[
+        "if", "then", "else", "elif", "fi", "case", "esac", "for", "while", "until", "do", "done",
+        "in", "function", "select", "continue", "break", "return", "exit", "source", "declare", "readonly",
+        "local", "export", "ls", "cd", "pwd", "cp", "mv", "rm", "mkdir", "rmdir", "touch", "chmod",
+        "chown", "grep", "awk", "sed", "cat", "head", "tail", "sort", "uniq", "wc", "cut", "paste",
+        "find", "tar", "gzip", "gunzip", "zip", "unzip", "ssh", "scp", "rsync", "curl", "wget", "ping",
+        "traceroute", "netstat", "ps", "kill", "top", "df", "du", "date", "cal", "history", "alias",
+        "source", "source", "exec", "exit", "help", "man", "info", "echo", "fgrep", "apropos", 
+        "whoami", "python", "bg", "fg", "sleep", "jobs", "read", "trap", "clear", "sh", "bash",
+    ]);
+    bulk_add(result, "function", &["\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\("]);
 }
 
-fn add_keywords_case_indep(h: &mut Highlighter, kw: &[&str]) {
-    h.keyword("keyword", &format!(r"\b({})\b", kw.join("|")));
-    h.keyword(
-        "keyword",
-        &format!(
-            r"\b({})\b",
-            kw.iter()
-                .map(|x| x.to_uppercase())
-                .collect::<Vec<_>>()
-                .join("|")
-        ),
-    );
+fn sql_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", "(--.*)$");
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "\'", "\'", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        bulk_add(&mut result, "operator", &[
+            r"\+", "-", r"\*", "/", "%", "=", "<>", "!=", "<", ">", "<=", ">=", "&", "|", "^",
+            "~", "||", "=",
+        ]);
+        add_keywords(&mut result, &[
+            "ADD", "ALL", "ALTER", "AND", "AS", "ASC", "BETWEEN", "BY", "CASE", "CHECK",
+            "COLUMN", "CONSTRAINT", "CREATE", "DATABASE", "DEFAULT", "DELETE", "DESC",
+            "DISTINCT", "DROP", "ELSE", "END", "EXISTS", "FOREIGN", "FROM", "FULL", "GROUP",
+            "HAVING", "IN", "INDEX", "INNER", "INSERT", "INTO", "IS", "JOIN", "LEFT", "LIKE",
+            "LIMIT", "NOT", "NULL", "ON", "OR", "ORDER", "OUTER", "PRIMARY", "REFERENCES",
+            "RIGHT", "SELECT", "SET", "TABLE", "TOP", "TRUNCATE", "UNION", "UNIQUE", "UPDATE",
+            "VALUES", "VIEW", "WHERE", "SHOW", "USE", "VARCHAR"
+        ]);
+        result
+    })
 }
 
-fn bulk_add(h: &mut Highlighter, name: &str, kw: &[&str]) {
-    h.keyword(name, &format!(r"({})", kw.join("|")));
+fn xml_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", "<!--", "-->", false);
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("operator", "=");
+        bulk_add(&mut result, "tag", &["<[A-Za-z0-9_]+>?", "</[A-Za-z0-9_]+>", "</", "/>", ">", "<!", "<"]);
+        bulk_add(&mut result, "attribute", &[r"([A-Za-z0-9-]+)="]);
+        result
+    })
 }
 
-fn rust_syntax_highlighter() -> &'static Highlighter {
+fn nushell_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
-        result.bounded("string", "r#\"", "\"#", true);
-        result.bounded("string", "r\"", "\"", true);
-        result.bounded("string", "#\"", "\"#", true);
         result.bounded("string", "\"", "\"", true);
-        result.bounded("attribute", r"\#\[", r"\]", false);
-        result.bounded("attribute", r"\#!\[", r"\]", false);
-        result.keyword("namespace", "([a-z_][A-Za-z0-9_]*)::");
-        add_keywords(&mut result, &[
-            "as", "break", "const", "continue", "char", "crate", "else", "enum", "extern",
-            "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
-            "pub", "ref", "return", "self", "static", "struct", "super", "trait", "type",
-            "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become",
-            "box", "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual",
-            "yield", "try", "'static", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
-            "i32", "i64", "i128", "isize", "f32", "f64", "String", "Vec", "str", "Some",
-            "bool", "None", "Box", "Result", "Option", "Ok", "Err", "Self", "std",
-        ]);
+        result.bounded("string", "'", "'", true);
+        result.keyword("comment", "(#.*)$");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
         bulk_add(&mut result, "operator", &[
-            "&&", r"\|\|", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=",
-            "\\-=", "\\*=", "\\\\=", "==", "!=", "\\?", ">=", "<=", "<", ">", "!",
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
+            r"(\-=)", r"(\*=)", r"(\\=)", r"(\{)", r"(\})", r"(==)", r"(!=)", r"(>=)",
+            r"(<=)", r"(<)", r"(>)", r"(\$)", r"(\.\.)", r"(<<)", r"(>>)", r"(\&\&)", 
+            r"(\|\|)", r"(!)\S", r"(\.)", r"(&)", r"(\|)"
         ]);
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f32|f64))"]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "fn\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "fn\\s+([a-z_][A-Za-z0-9_]*)\\s*<.*>\\s*\\(",
-            "\\.([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        add_keywords(&mut result, &[
+            "alias", "append", "build-string", "cd", "config", "cp", "debug", "def", "do",
+            "each", "echo", "else", "empty?", "enter", "every", "exit", "export", "filter",
+            "first", "flatten", "for", "format", "from", "get", "group-by", "help", "history",
+            "if", "insert", "keep", "last", "let", "ls", "math", "merge", "metadata", "move",
+            "mut", "open", "parse", "pivot", "plugin", "post", "pre", "prune", "reduce", "reject",
+            "rename", "rm", "save", "select", "skip", "sort-by", "source", "split", "str", "table",
+            "to", "touch", "uniq", "update", "url", "use", "where", "with-env", "drop", "complete",
+            "load-env", "exec", "mkdir", "du", "glob", "mktemp", "mv", "ps", "run-external", "start",
+            "sys", "uname", "watch", "which", "nu-check", "nu-highlight", "print", "decode", "char",
+            "encode", "detect", "url", "dexit", "shells", "random", "gstat", "ansi", "input",
+            "keybindings", "kill", "sleep", "term", "ulimit", "whoami", "is-terminal", "clear", "path",
+            "http", "query", "port", "tutor", "math", "polars", "hash", "cal", "generate", "seq",
+            "columns", "collect", "compact", "flatten", "group", "headers", "transpose", "enumerate",
+            "catch", "try", "find", "upsert", "string", "pattern", "fill",
         ]);
-        bulk_add(&mut result, "struct", &[
-            "(?:trait|enum|struct|impl)\\s+([A-Z][A-Za-z0-9_]*)\\s*",
-            "impl(?:<.*?>|)\\s+([A-Z][A-Za-z0-9_]*)",
-            "([A-Z][A-Za-z0-9_]*)::",
-            "([A-Z][A-Za-z0-9_]*)\\s*\\(",
-            "impl.*for\\s+([A-Z][A-Za-z0-9_]*)",
-            "::\\s*([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        result.grammar_mut().word_chars = vec!['-'];
+        result
+    })
+}
+
+fn tex_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("string", "\\$", "\\$", true);
+        result.keyword("comment", r"([^\\]%.*)$");
+        result.keyword("comment", r"^(%.*)$");
+        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        bulk_add(&mut result, "keyword", &[
+            r"\\addbibresource\b", r"\\author\b", r"\\begin\b", r"\\caption\b",
+            r"\\centering\b", r"\\date\b", r"\\end\b", r"\\geometry\b", r"\\hline\b",
+            r"\\includegraphics\b", r"\\item\b", r"\\label\b", r"\\maketitle\b", r"\\paragraph\b",
+            r"\\parindent\b", r"\\parskip\b", r"\\printbibliography\b", r"\\section\b", r"\\setlength\b",
+            r"\\subsection\b", r"\\tableofcontents\b", r"\\textbf\b", r"\\textit\b", r"\\texttt\b",
+            r"\\title\b", r"\\today\b", r"\\underline\b", r"\\usepackage\b", r"\\ref\b",
+            r"\\cite\b", r"\\pageref\b", r"\\include\b", r"\\input\b", r"\\bibliographystyle\b",
+            r"\\newcommand\b", r"\\renewcommand\b", r"\\renewenvironment\b", r"\\newenvironment\b", 
+            r"\\footnote\b", r"\\hline\b", r"\\vspace\b", r"\\hspace\b", r"\\newline\b", r"\\frac\b", 
+            r"\\textbackslash\b", r"\\documentclass\b",
         ]);
-        bulk_add(&mut result, "macro", &["\\b([a-z_][a-zA-Z0-9_]*!)", "(\\$[a-z_][A-Za-z0-9_]*)"]);
-        bulk_add(&mut result, "reference", &[
-            "&", "&str", "&mut", "&self", "&i8", "&i16", "&i32", "&i64", "&i128", "&isize",
-            "&u8", "&u16", "&u32", "&u64", "&u128", "&usize", "&f32", "&f64",
+        bulk_add(&mut result, "operator", &[
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(#)", r"(\+=)", r"(\-=)", 
+            r"(\*=)", r"(\\=)", r"(\^)", r"(%)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)",
+            r"(\$)", r"(\.\.)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(&)", r"(\|)",
         ]);
         result
     })
 }
 
-fn asm_syntax_highlighter() -> &'static Highlighter {
+fn diff_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("function", "([a-zA-Z_]+)\\:$");
-        result.keyword("comment", "(;.*)$");
-        result.keyword("digit", "\\b((?:0x)?\\d+.\\d+|\\d+)");
-        result.bounded("string", "\"", "\"", true);
-        add_keywords_case_indep(
-            &mut result,
-            &[
-                "mov", "add", "sub", "jmp", "call", "ret", "bss", "data", "text", "section",
-                "globl", "extern", "db", "eax", "ebx", "ecx", "edx", "esp", "ebp", "int", "xor",
-                "imul", "inc", "jle", "cmp", "global", "section", "resb",
-            ],
-        );
+        result.keyword("insertion", r"^(\+(?:[^+]|$).*)$");
+        result.keyword("deletion", r"^\-(?:[^-]|$).*$");
+        result.keyword("comment", r"@@.*@@");
         result
     })
 }
 
-fn python_syntax_highlighter() -> &'static Highlighter {
+fn d_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(#.*)$");
-        result.bounded("string", "\"\"\"", "\"\"\"", true);
-        result.bounded("string", "\'\'\'", "\'\'\'", true);
-        result.bounded("string", "b\"", "\"", true);
-        result.bounded("string", "r\"", "\"", true);
-        result.bounded_interp("string", "f\"", "\"", "\\{", "\\}", true);
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.bounded_nested("comment", r"/\+", r"\+/", false);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "r\"", "\"", false);
+        result.bounded("string", "`", "`", false);
         result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "b\'", "\'", true);
-        result.bounded("string", "r\'", "\'", true);
-        result.bounded_interp("string", "f\'", "\'", "\\{", "\\}", true);
-        result.bounded("string", "\'", "\'", true);
+        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
+        result.keyword("attribute", r"@[a-zA-Z_][A-Za-z0-9_]*");
+        result.keyword("macro", r"\b([a-zA-Z_][A-Za-z0-9_]*!)[(a-zA-Z_0-9]");
         add_keywords(&mut result, &[
-            "and", "as", "assert", "break", "class", "continue", "def", "del", "elif", "else", "except",
-            "exec", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "not",
-            "or", "pass", "print", "raise", "return", "try", "while", "with", "yield", "str", "bool",
-            "int", "tuple", "list", "dict", "tuple", "len", "None", "input", "type", "set", "range",
-            "enumerate", "open", "iter", "min", "max", "dir", "self", "isinstance", "help", "next",
-            "super", "match", "case",
+            "abstract", "alias", "align", "asm", "assert", "auto", "body", "bool", "break", "byte", "case",
+            "cast", "catch", "char", "class", "const", "continue", "dchar", "debug", "default", "delegate",
+            "deprecated", "do", "double", "else", "enum", "export", "extern", "final", "finally", "float",
+            "for", "foreach", "foreach_reverse", "function", "goto", "if", "immutable", "import", "in",
+            "inout", "int", "interface", "invariant", "is", "lazy", "long", "mixin", "module", "new",
+            "nothrow", "null", "out", "override", "package", "pragma", "private", "protected", "public",
+            "pure", "real", "ref", "return", "scope", "shared", "short", "static", "string", "struct",
+            "super", "switch", "synchronized", "template", "this", "throw", "try", "typeid", "typeof",
+            "ubyte", "uint", "ulong", "union", "unittest", "ushort", "version", "void", "wchar", "while",
+            "with", "size_t", "__gshared", "__traits",
         ]);
-        result.keyword("attribute", "@.*$");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
         bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(\s//\s)", r"(%)", r"(\+=)",
-            r"(\-=)", r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)",
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)", r"(\*=)", r"(/=)",
+            r"(~=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)", r"(\&\&)",
+            r"(\|\|)", r"(!)\S", r"(\.\.)", r"(~)", r"(=>)",
         ]);
-        bulk_add(&mut result, "boolean", &["\\b(True)\\b", "\\b(False)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "def\\s+([a-z_][A-Za-z0-9_]*)",
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        bulk_add(&mut result, "digit", &[
+            "\\b(0x[0-9a-fA-F_]+)", "\\b(0b[01_]+)", "\\b(\\d[\\d_]*(?:\\.\\d+)?(?:[fFL]|[uU]?L?)?)",
         ]);
+        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        bulk_add(&mut result, "function", &["\\b([a-z_][A-Za-z0-9_]*)\\s*\\("]);
         result
     })
 }
 
-fn ruby_syntax_highlighter() -> &'static Highlighter {
+fn ada_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(#.*)$");
-        result.bounded("comment", "=begin", "=end", false);
-        result.bounded_interp("string", "\"", "\"", "#\\{", "\\}", true);
-        result.bounded("string", "\'", "\'", true);
-        result.keyword("string", r"(\:[a-zA-Z_]+)");
-        add_keywords(&mut result, &[
-            "__ENCODING__", "__LINE__", "__FILE__", "BEGIN", "END", "alias", "and", "begin", "break",
-            "case", "class", "def", "defined?", "do", "else", "elsif", "end", "ensure", "for", "if",
-            "in", "module", "next", "nil", "not", "or", "redo", "rescue", "retry", "return", "self",
-            "super", "then", "undef", "unless", "until", "when", "while", "yield", "extend", "include",
-            "attr_reader", "attr_writer", "attr_accessor",
+        result.keyword("comment", "(--.*)$");
+        result.keyword("string", r#""(?:[^"]|"")*""#);
+        result.keyword("character", "'.'");
+        result.keyword("attribute", r"(?i)^\s*(pragma\s+[a-z_][a-z0-9_]*)");
+        result.keyword("attribute", r"[A-Za-z0-9_)]('[A-Za-z_][A-Za-z0-9_]*)");
+        add_keywords_ignore_case(&mut result, &[
+            "abort", "abs", "abstract", "accept", "access", "aliased", "all", "and", "array", "at", "begin",
+            "body", "case", "constant", "declare", "delay", "delta", "digits", "do", "else", "elsif", "end",
+            "entry", "exception", "exit", "for", "function", "generic", "goto", "if", "in", "interface", "is",
+            "limited", "loop", "mod", "new", "not", "null", "of", "or", "others", "out", "overriding",
+            "package", "parallel", "private", "procedure", "protected", "raise", "range", "record", "rem",
+            "renames", "requeue", "return", "reverse", "select", "separate", "some", "subtype",
+            "synchronized", "tagged", "task", "terminate", "then", "type", "until", "use", "when", "while",
+            "with", "xor",
         ]);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
+        result.keyword("boolean", r"(?i)\b(true|false)\b");
+        result.keyword("digit", r"\b(\d[\d_]*#[0-9a-fA-F_.]+#|\d[\d_]*(?:\.[\d_]+)?(?:[eE][+-]?\d+)?)");
         bulk_add(&mut result, "operator", &[
-            "!!", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=",
-            "==", "!=", "\\?", ">=", "<=", "<", ">", "&&", "\\|\\|", "!", "&", "\\|", "\\^",
-            "%",
+            r"(:=)", r"(=>)", r"(\.\.)", r"(=)", r"(/=)", r"(<=)", r"(>=)", r"(<)", r"(>)", r"(\+)",
+            r"(\-)", r"(\*\*)", r"(\*)", r"(/)", r"(&)",
         ]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
         bulk_add(&mut result, "function", &[
-            "def\\s+([a-z_][A-Za-z0-9_]*)",
-            "^\\s*([a-z_][A-Za-z0-9_]*)\\s+[^=]",
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+            r"(?i)\b(?:procedure|function)\s+([a-z_][a-z0-9_.]*)",
+            r"\b([A-Za-z_][A-Za-z0-9_]*)\s*\(",
         ]);
+        bulk_add(&mut result, "namespace", &[r"(?i)\b(?:package(?:\s+body)?|with|use)\s+([a-z_][a-z0-9_.]*)"]);
         result
     })
 }
 
-fn cgi_syntax_highlighter() -> &'static Highlighter {
+fn pascal_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(#.*)$");
-        result.bounded_interp("string", "\"", "\"", "#\\{", "\\}", true);
-        result.bounded("string", "(?:m|s)/", "/", true);
-        result.bounded("string", "\'", "\'", true);
-        result.keyword("string", r"(\:[a-zA-Z_]+)");
-        add_keywords(&mut result, &[
-            "if", "else", "elsif", "unless", "while", "for", "foreach", "until", "do", "next",
-            "last", "goto", "return", "sub", "my", "local", "our", "package", "use", "require",
-            "import", "undef", "and", "or", "not", "eq", "ne", "lt", "le", "gt", "ge", "cmp",
-            "qw", "scalar", "array", "hash", "undef", "undef", "ref", "bless", "glob", "filehandle",
-            "code", "regexp", "integer", "float", "string", "boolean", "reference", "die",
+        result.keyword("attribute", r"\{\$[^}]*\}");
+        result.bounded("comment", r"\{", r"\}", false);
+        result.bounded("comment", r"\(\*", r"\*\)", false);
+        result.keyword("comment", "(//.*)$");
+        result.keyword("string", "'(?:[^']|'')*'");
+        result.keyword("character", r"#\d+");
+        add_keywords_ignore_case(&mut result, &[
+            "and", "array", "as", "asm", "begin", "case", "class", "const", "constructor", "destructor",
+            "div", "do", "downto", "else", "end", "except", "exports", "file", "finalization", "finally",
+            "for", "function", "goto", "if", "implementation", "in", "inherited", "initialization", "inline",
+            "interface", "is", "label", "library", "mod", "nil", "not", "object", "of", "on", "or", "out",
+            "packed", "private", "procedure", "program", "property", "protected", "public", "published",
+            "raise", "record", "repeat", "resourcestring", "set", "shl", "shr", "string", "then",
+            "threadvar", "to", "try", "type", "unit", "until", "uses", "var", "while", "with", "xor",
+            "override", "virtual", "abstract", "overload", "integer", "boolean", "char", "real", "double",
+            "byte", "word", "cardinal", "int64", "exit", "result", "self",
         ]);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
+        result.keyword("boolean", r"(?i)\b(true|false)\b");
+        bulk_add(&mut result, "digit", &[r"(\$[0-9a-fA-F]+)", r"\b(\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)"]);
         bulk_add(&mut result, "operator", &[
-            "!!", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=",
-            "==", "!=", "\\?", ">=", "<=", "<", ">", "\\$","&&", "\\|\\|", "!", "&", "\\|",
-            "\\^", "(?:\\\\)?%", "\\\\@",
+            r"(:=)", r"(=)", r"(<>)", r"(<=)", r"(>=)", r"(<)", r"(>)", r"(\+)", r"(\-)", r"(\*)", r"(/)",
+            r"(\^)", r"(@)", r"(\.\.)",
         ]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
         bulk_add(&mut result, "function", &[
-            "sub\\s+([a-z_][A-Za-z0-9_]*)",
-            "^\\s*([a-z_][A-Za-z0-9_]*)\\s+[^=]",
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+            r"(?i)\b(?:procedure|function|constructor|destructor)\s+([a-z_][a-z0-9_.]*)",
+            r"\b([A-Za-z_][A-Za-z0-9_]*)\s*\(",
         ]);
+        result.keyword("struct", r"\b(T[A-Z][A-Za-z0-9_]*)");
         result
     })
 }
 
-fn lua_syntax_highlighter() -> &'static Highlighter {
+fn groovy_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"--\[\[", r"\]\]--", false);
-        result.keyword("comment", "(--.*)$");
-        result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "\'", "\'", true);
-        result.bounded("string", "\\[\\[", "\\]\\]", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
+        result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(//.*)$");
+        result.keyword("comment", "^(#!.*)$");
+        result.bounded_interp("string", "\"\"\"", "\"\"\"", "\\$\\{", "\\}", true);
+        result.bounded("string", "'''", "'''", true);
+        result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
+        result.bounded("string", "'", "'", true);
+        result.keyword("string", r"(?:[=~(,]|^)\s*(/(?:[^/\\\s]|\\.)(?:[^/\\]|\\.)*/)");
+        result.keyword("string", r"(\$/.*?/\$)");
+        result.keyword("attribute", r"@\w+");
         result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
-        bulk_add(&mut result, "function", &[
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
-        ]);
+        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("digit", "\\b(\\d+(?:\\.\\d+)?[gGlLiIdDfF]?)");
         bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)",
-            r"(\+=)", r"(\-=)", r"(\*=)", r"(\\=)", r"(\.\.)", r"(==)", r"(~=)",
-            r"(>=)", r"(<=)", r"(<)", r"(>)", r"(#)", r"(<<)", r"(>>)", r"\b(and)\b",
-            r"\b(or)\b", r"\b(not)\b",
+            r"(->)", r"(\?:)", r"(\?\.)", r"(\*\.)", r"(\.\.)", r"(==~)", r"(=~)", r"(<=>)", r"(\.&)",
+            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)", r"(\*=)", r"(==)",
+            r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
         ]);
         add_keywords(&mut result, &[
-            "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in",
-            "local", "nil", "repeat", "return", "then", "true", "until", "while", "self",
+            "abstract", "as", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const",
+            "continue", "def", "default", "do", "double", "else", "enum", "extends", "final", "finally",
+            "float", "for", "goto", "if", "implements", "import", "in", "instanceof", "int", "interface",
+            "long", "native", "new", "null", "package", "private", "protected", "public", "return", "short",
+            "static", "strictfp", "super", "switch", "synchronized", "this", "throw", "throws", "trait",
+            "transient", "try", "var", "void", "volatile", "while", "it",
+        ]);
+        bulk_add(&mut result, "function", &[
+            "\\.([a-z_][A-Za-z0-9_]*)\\s*[({]",
+            "\\b([a-z_][A-Za-z0-9_]*)\\s*[({]",
+            "^\\s*([a-z_][A-Za-z0-9_]*)\\s+['\"]",
         ]);
         result
     })
 }
 
-fn r_syntax_highlighter() -> &'static Highlighter {
+fn gleam_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(#.*)$");
+        result.keyword("comment", "(//.*)$");
         result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "\'", "\'", true);
-        bulk_add(&mut result, "boolean", &["\\b(FALSE)\\b", "\\b(TRUE)\\b"]);
+        result.keyword("attribute", r"@[a-z_][A-Za-z0-9_]*");
         add_keywords(&mut result, &[
-            "if", "else", "repeat", "while", "function", "for", "in", "next", "break", "TRUE",
-            "FALSE", "NULL", "Inf", "NaN", "NA", "NA_integer_", "NA_real_", "NA_complex_",
-            "NA_character_", r"\.\.\.",
+            "as", "assert", "case", "const", "echo", "fn", "if", "import", "let", "opaque", "panic", "pub",
+            "todo", "type", "use",
+        ]);
+        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        result.keyword("boolean", "\\b(True|False)\\b");
+        result.keyword("namespace", r"\b([a-z_][a-z0-9_]*)\.");
+        bulk_add(&mut result, "digit", &[
+            r"\b(0x[0-9a-fA-F_]+)", r"\b(0o[0-7_]+)", r"\b(0b[01_]+)", r"\b(\d[\d_]*(?:\.[\d_]*)?(?:e-?\d+)?)",
         ]);
-        result.keyword("attribute", "@.*$");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
         bulk_add(&mut result, "operator", &[
-            r"<-", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(\s//\s)", r"(&)", r"(%)",
-            r"(\+=)", r"(\-=)", r"(\*=)", r"(\\=)", r"(\$)", r"(|)", r"(==)", r"(!=)", r"(>=)",
-            r"(<=)", r"(<)", r"(>)", r"(\?)",
+            r"(\|>)", r"(<<)", r"(>>)", r"(->)", r"(<-)", r"(\.\.)", r"(<>)", r"(==)", r"(!=)",
+            r"(>=\.?)", r"(<=\.?)", r"(<\.?)", r"(>\.?)", r"(\+\.?)", r"(\-\.?)", r"(\*\.?)", r"(/\.?)",
+            r"(%)", r"(&&)", r"(\|\|)", r"(=)", r"(\|)", r"(!)",
         ]);
         bulk_add(&mut result, "function", &[
-            "def\\s+([a-z_][A-Za-z0-9_]*)",
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+            r"\bfn\s+([a-z_][a-z0-9_]*)",
+            r"\b([a-z_][a-z0-9_]*)\s*\(",
         ]);
         result
     })
 }
 
-fn go_syntax_highlighter() -> &'static Highlighter {
+fn scheme_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
+        result.bounded_nested("comment", r"#\|", r"\|#", false);
+        result.keyword("comment", "(;.*)$");
+        result.keyword("comment", r"#;");
+        result.keyword("attribute", r"^(#lang\b.*)$");
         result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "`", "`", true);
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        add_keywords(&mut result, &[
-            "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough",
-            "for", "func", "go", "goto", "if", "import", "interface", "map", "package", "range",
-            "return", "select", "struct", "switch", "type", "var", "bool", "byte", "complex64", "complex128",
-            "error", "float32", "float64", "int", "int8", "int16", "int32", "int64", "rune", "string",
+        result.keyword("character", r"#\\(?:[a-z]+|.)");
+        result.keyword("string", r"'[^\s()\[\]'`,;]+");
+        result.keyword("boolean", r"(#t|#f|#true|#false)\b");
+        bulk_add(&mut result, "function", &[
+            r"\(define\s+\(([^\s()]+)",
+            r"\(define(?:-syntax|-values)?\s+([^\s()]+)",
+        ]);
+        result.keyword("operator", r"['`,]|,@");
+        // Identifiers can contain symbols, so keywords end at whitespace or brackets rather than \b
+        let keywords = [
+            "define-syntax", "define-values", "define-record-type", "define", "lambda", "λ", "let\\*",
+            "letrec\\*", "letrec", "let-values", "let", "if", "cond", "case", "else", "when", "unless", "and", "or",
+            "not", "begin", "do", "set!", "quote", "quasiquote", "unquote", "syntax-rules", "syntax-case",
+            "delay", "force", "import", "export", "library", "require", "provide", "module", "struct",
+            "match", "parameterize", "call/cc", "values", "for/list", "for", "null", "nil",
+        ];
+        result.keyword("keyword", &format!(r"(?:^|[\s(\[])({})(?:$|[\s()\[\]])", keywords.join("|")));
+        result.keyword("digit", r"(?:^|[\s(\[])([+-]?\d+(?:\.\d+)?(?:/\d+)?)\b");
+        result.keyword("digit", r"(#[xXbBoOdD][0-9a-fA-F]+)");
+        result.grammar_mut().word_chars = vec!['-', '!', '?', '*', '/', '>', '<', '='];
+        result
+    })
+}
+
+fn powershell_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", "<#", "#>", false);
+        result.keyword("comment", "(#.*)$");
+        // PowerShell escapes with backticks rather than backslashes
+        result.bounded_interp("string", "@\"", "^\"@", "\\$\\(", "\\)", false);
+        result.bounded("string", "@'", "^'@", false);
+        result.bounded_interp("string", "\"", "\"", "\\$\\(", "\\)", false);
+        result.bounded("string", "'", "'", false);
+        result.keyword("boolean", r"(?i)\$(?:true|false|null)\b");
+        result.keyword("variable", r"(?i)\$(?:env|global|local|script|private|using):[a-z_][a-z0-9_]*");
+        result.keyword("variable", r"\$[A-Za-z_][A-Za-z0-9_]*|\$\{[^}]*\}|\$[$?^_]");
+        result.keyword("attribute", r"^\s*(\[[A-Za-z.]+(?:\(.*\))?\])");
+        result.keyword("struct", r"(\[[A-Za-z.]+(?:\[\])?\])");
+        add_keywords_ignore_case(&mut result, &[
+            "begin", "break", "catch", "class", "continue", "data", "define", "do", "dynamicparam", "else",
+            "elseif", "end", "enum", "exit", "filter", "finally", "for", "foreach", "from", "function",
+            "hidden", "if", "in", "param", "process", "return", "static", "switch", "throw", "trap", "try",
+            "until", "using", "var", "while", "workflow", "parallel", "sequence", "inlinescript",
         ]);
+        result.keyword("operator", &format!(r"(?i)\B-(?:{})\b", [
+            "eq", "ne", "gt", "ge", "lt", "le", "like", "notlike", "match", "notmatch", "contains",
+            "notcontains", "in", "notin", "replace", "split", "join", "is", "isnot", "as", "and", "or",
+            "xor", "not", "band", "bor", "bxor", "bnot", "shl", "shr", "f",
+        ].iter().flat_map(|op| [op.to_string(), format!("c{op}"), format!("i{op}")]).collect::<Vec<_>>().join("|")));
         bulk_add(&mut result, "operator", &[
-            ":=", "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=",
-            "==", "!=", "\\?", ">=", "<=", "<", ">",
+            r"(=)", r"(\+=)", r"(\-=)", r"(\*=)", r"(/=)", r"(%=)", r"(\+\+)", r"(\-\-)", r"(\+)", r"(\*)",
+            r"(\s/\s)", r"(%)", r"(\|)", r"(!)", r"(::)", r"(\.\.)",
         ]);
-        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f32|f64))"]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
+        result.keyword("digit", r"\b(0x[0-9a-fA-F]+|\d+(?:\.\d+)?(?:[kKmMgGtTpP][bB])?)\b");
         bulk_add(&mut result, "function", &[
-            "func\\s+([A-Za-z0-9_]+)\\s*\\(",
-            "\\.([A-Za-z0-9_]+)\\s*\\(",
-            "([A-Za-z0-9_]+)\\s*\\(",
+            r"(?i)\bfunction\s+([a-z_][a-z0-9_-]*)",
+            r"\b([A-Z][a-z]+-[A-Z][A-Za-z0-9]*)\b",
+            r"\.([A-Za-z_][A-Za-z0-9_]*)\(",
         ]);
-        bulk_add(&mut result, "reference", &["&"]);
+        result.keyword("attribute", r"(?:^|\s)(-[A-Z][A-Za-z]*)\b");
+        result.grammar_mut().word_chars = vec!['-'];
         result
     })
 }
 
-fn js_syntax_highlighter() -> &'static Highlighter {
+fn batch_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "//.*$");
-        result.bounded("string", "r\"", "\"", true);
-        result.bounded("string", "f\"", "\"", true);
-        result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "r\'", "\'", true);
-        result.bounded("string", "f\'", "\'", true);
-        result.bounded("string", "\'", "\'", true);
-        result.bounded_interp("string", "r`", "`", "\\$\\{", "\\}", true);
-        result.bounded_interp("string", "f`", "`", "\\$\\{", "\\}", true);
-        result.bounded_interp("string", "`", "`", "\\$\\{", "\\}", true);
-        result.bounded("string", "/", "/", true);
-        add_keywords(&mut result, &[
-            "abstract", "arguments", "await", "boolean", "break", "byte", "case", "catch", "char",
-            "class", "const", "continue", "debugger", "default", "delete", "do", "double", "else",
-            "enum", "eval", "export", "extends", "final", "finally", "float", "for", "of", "function",
-            "goto", "if", "implements", "import", "in", "instanceof", "int", "interface", "let", "long",
-            "native", "new", "null", "package", "private", "protected", "public", "return", "short",
-            "static", "super", "switch", "synchronized", "this", "throw", "throws", "transient", "try",
-            "typeof", "var", "void", "volatile", "console", "while", "with", "yield", "undefined", "NaN",
-            "-Infinity", "Infinity",
+        result.keyword("comment", r"(?i)^\s*@?(rem(?:\s.*)?)$");
+        result.keyword("comment", r"^\s*(::.*)$");
+        result.keyword("function", r"^\s*(:[A-Za-z0-9_.-]+)");
+        result.keyword("function", r"(?i)\b(?:goto|call)\s+(:?[A-Za-z0-9_.-]+)");
+        result.keyword("variable", r"%%~?[a-zA-Z]|%~[a-zA-Z]*[0-9]|%[0-9*]|%[A-Za-z0-9_#$'()*+,\-.?@\[\]`{}~ ]+(?::[^%]*)?%|![A-Za-z0-9_#$'()*+,\-.?@\[\]`{}~]+(?::[^!]*)?!");
+        result.keyword("string", r#""[^"]*""#);
+        add_keywords_ignore_case(&mut result, &[
+            "assoc", "break", "call", "cd", "chdir", "cls", "color", "copy", "date", "defined", "del",
+            "dir", "do", "echo", "else", "endlocal", "enabledelayedexpansion", "enableextensions", "equ",
+            "erase", "errorlevel", "exist", "exit", "for", "ftype", "geq", "goto", "gtr", "if", "in",
+            "leq", "lss", "md", "mkdir", "mklink", "move", "neq", "not", "nul", "off", "on", "path", "pause",
+            "popd", "prompt", "pushd", "rd", "ren", "rename", "rmdir", "set", "setlocal", "shift", "start",
+            "time", "title", "type", "ver", "verify", "vol",
         ]);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "function\\s+([a-z_][A-Za-z0-9_]*)",
-            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "\\.([a-z_][A-Za-z0-9_]*)\\s*",
+        result.keyword("attribute", r"(?:^|\s)(/[A-Za-z?]+)\b");
+        bulk_add(&mut result, "operator", &[r"(==)", r"(\|\|)", r"(&&)", r"(>>)", r"(2>&1)", r"(@)", r"(\|)", r"(&)", r"(>)", r"(<)", r"(=)"]);
+        result.keyword("digit", r"\b(\d+)\b");
+        result
+    })
+}
+
+fn dockerfile_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("attribute", r"(?i)^(#\s*(?:syntax|escape|check)\s*=.*)$");
+        result.keyword("comment", r"^\s*(#.*)$");
+        result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
+        result.bounded("string", "'", "'", false);
+        result.keyword("variable", r"\$\{[^}]*\}|\$[A-Za-z_][A-Za-z0-9_]*");
+        result.keyword("keyword", &format!(r"(?i)^\s*(?:ONBUILD\s+)?({})\b", [
+            "ADD", "ARG", "CMD", "COPY", "ENTRYPOINT", "ENV", "EXPOSE", "FROM", "HEALTHCHECK", "LABEL",
+            "MAINTAINER", "ONBUILD", "RUN", "SHELL", "STOPSIGNAL", "USER", "VOLUME", "WORKDIR",
+        ].join("|")));
+        result.keyword("keyword", r"(?i)^\s*FROM\s+\S+\s+(AS)\b");
+        result.keyword("struct", r"(?i)^\s*FROM\s+\S+\s+AS\s+(\S+)");
+        result.keyword("attribute", r"\s(--[a-z-]+)(?:=|\b)");
+        result.keyword("operator", r"(\\)$");
+        bulk_add(&mut result, "operator", &[r"(&&)", r"(\|\|)", r"(\|)", r"(=)"]);
+        result.keyword("digit", r"\b(\d+(?:\.\d+)*)\b");
+        result
+    })
+}
+
+fn cmake_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"#\[\[", r"\]\]", false);
+        result.bounded("comment", r"#\[=\[", r"\]=\]", false);
+        result.keyword("comment", "(#.*)$");
+        result.bounded("string", r"\[\[", r"\]\]", false);
+        result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
+        result.keyword("variable", r"\$(?:ENV|CACHE)?\{[^}]*\}|@[A-Za-z_][A-Za-z0-9_]*@");
+        result.keyword("attribute", r"\$<[^>]*>");
+        // Variable names within quoted arguments, where the ${ and } are part of the interpolation
+        result.keyword("variable", r"\$\{([A-Za-z0-9_./+-]+)\}");
+        add_keywords_ignore_case(&mut result, &[
+            "if", "elseif", "else", "endif", "foreach", "endforeach", "while", "endwhile", "function",
+            "endfunction", "macro", "endmacro", "return", "break", "continue", "block", "endblock",
         ]);
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
-            r"(\-=)", r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)",
-            r"(>)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
+        result.keyword("function", r"^\s*([A-Za-z_][A-Za-z0-9_]*)\s*\(");
+        result.keyword("function", r"(?i)\b(?:function|macro)\s*\(\s*([A-Za-z_][A-Za-z0-9_]*)");
+        add_keywords(&mut result, &[
+            "AFTER", "ALIAS", "ALL", "AND", "APPEND", "ARCHIVE", "BEFORE", "BOOL", "BUILD_INTERFACE",
+            "CACHE", "COMMAND", "COMPONENTS", "CONFIG", "COPYONLY", "DEFINED", "DEPENDS", "DESTINATION",
+            "DIRECTORY", "EQUAL", "EXCLUDE_FROM_ALL", "EXISTS", "FATAL_ERROR", "FILES", "FORCE", "GLOB",
+            "GLOB_RECURSE", "GREATER", "IMPORTED", "IN", "INSTALL_INTERFACE", "INTERFACE", "ITEMS", "LANGUAGES",
+            "LESS", "LIBRARY", "LISTS", "MATCHES", "MODULE", "NAMES", "NOT", "OBJECT", "OFF", "ON", "OPTIONAL",
+            "OR", "OUTPUT", "PATH", "PATHS", "PARENT_SCOPE", "POST_BUILD", "PRE_BUILD", "PRIVATE",
+            "PROPERTIES", "PUBLIC", "QUIET", "RANGE", "REQUIRED", "RUNTIME", "SEND_ERROR", "SHARED",
+            "STATIC", "STATUS", "STREQUAL", "STRING", "TARGET", "TARGETS", "VERSION", "VERSION_GREATER",
+            "VERSION_LESS", "WARNING", "WORKING_DIRECTORY", "FALSE", "TRUE", "YES", "NO",
         ]);
+        result.keyword("digit", r"\b(\d+(?:\.\d+)*)\b");
         result
     })
 }
 
-fn ts_syntax_highlighter() -> &'static Highlighter {
+fn graphql_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "//.*$");
-        result.bounded("string", "r\"", "\"", true);
-        result.bounded("string", "f\"", "\"", true);
+        result.keyword("comment", "(#.*)$");
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
         result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "r\'", "\'", true);
-        result.bounded("string", "f\'", "\'", true);
-        result.bounded("string", "\'", "\'", true);
-        result.bounded_interp("string", "r`", "`", "\\$\\{", "\\}", true);
-        result.bounded_interp("string", "f`", "`", "\\$\\{", "\\}", true);
-        result.bounded_interp("string", "`", "`", "\\$\\{", "\\}", true);
-        result.bounded("string", "/", "/", true);
+        result.keyword("variable", r"\$[_A-Za-z][_0-9A-Za-z]*");
+        result.keyword("attribute", r"@[_A-Za-z][_0-9A-Za-z]*");
         add_keywords(&mut result, &[
-            "abstract", "any", "as", "asserts", "boolean", "break", "case", "catch", "class", "const", "constructor",
-            "continue", "debugger", "declare", "default", "delete", "do", "else", "enum", "export", "extends", "false",
-            "finally", "for", "from", "function", "get", "if", "implements", "import", "in", "infer", "instanceof",
-            "interface", "is", "keyof", "let", "module", "namespace", "never", "new", "null", "number", "object", "package",
-            "private", "protected", "public", "readonly", "require", "global", "return", "set", "static", "string",
-            "super", "switch", "symbol", "this", "throw", "true", "try", "type", "typeof", "undefined", "unique", "unknown",
-            "var", "void", "while", "with", "yield",
-        ]);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "class\\s+([A-Za-z0-9_]+)");
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "function\\s+([a-z_][A-Za-z0-9_]*)",
-            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "\\.([a-z_][A-Za-z0-9_]*)\\s*",
-        ]);
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
-            r"(\&\&)", r"(\|\|)", r"(!)\S",
+            "directive", "enum", "extend", "fragment", "implements", "input", "interface", "mutation", "on",
+            "query", "repeatable", "scalar", "schema", "subscription", "type", "union",
         ]);
+        result.keyword("boolean", r"\b(true|false|null)\b");
+        result.keyword("struct", r"\b([A-Z][_0-9A-Za-z]*)\b");
+        result.keyword("key", r"\b([_a-z][_0-9A-Za-z]*)\s*:");
+        result.keyword("digit", r"(-?\b\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[r"(\.\.\.)", r"(!)", r"(=)", r"(\|)", r"(&)", r"(:)"]);
+        result.keyword("function", r"\b([_a-z][_0-9A-Za-z]*)\s*\(");
         result
     })
 }
 
-fn dart_syntax_highlighter() -> &'static Highlighter {
+fn ini_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "//.*$");
-        result.bounded("string", "\"\"\"", "\"\"\"", true);
-        result.bounded("string", "\'\'\'", "\'\'\'", true);
+        result.keyword("comment", r"^\s*([;#].*)$");
+        result.keyword("table", r"^\s*(\[.*\])");
+        result.keyword("key", r"^\s*([^=:;#\[\s][^=:]*?)\s*[=:]");
+        result.keyword("operator", r"^[^=:]*?([=:])");
+        result.bounded("string", "\"", "\"", true);
+        result.bounded("string", "'", "'", false);
+        result.keyword("boolean", r"(?i)[=:]\s*(true|false|yes|no|on|off)\s*$");
+        result.keyword("digit", r"\b(0x[0-9a-fA-F]+|-?\d+(?:\.\d+)?)\b");
+        result
+    })
+}
+
+fn env_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^\s*([#!].*)$");
+        result.keyword("keyword", r"^\s*(export)\s");
+        result.keyword("key", r"^\s*(?:export\s+)?([^\s=:#!][^\s=:]*)\s*[=:\s]");
+        result.keyword("operator", r"^\s*(?:export\s+)?[^\s=:#!][^\s=:]*\s*([=:])");
         result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
-        result.bounded("string", "\'", "\'", true);
-        add_keywords(&mut result, &[
-            "abstract", "as", "assert", "async", "await", "break", "case", "catch", "class", "const", "continue", "covariant", "default",
-            "deferred", "do", "dynamic", "else", "enum", "export", "extends", "extension", "external", "factory", "false", "final", "finally",
-            "for", "Function", "get", "hide", "if", "implements", "import", "in", "inout", "interface", "is", "late", "library", "mixin",
-            "new", "null", "on", "operator", "out", "part", "required", "rethrow", "return", "set", "show", "static", "super", "switch",
-            "sync", "this", "throw", "true", "try", "typedef", "var", "void", "while", "with", "yield", "int", "double", "num", "string",
-        ]);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]+)");
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "\\b([a-z_][A-Za-z0-9_]*)(?:<[A-Za-z_]*>)?\\s*\\(",
-            "\\.([a-z_][A-Za-z0-9_]*)\\s*",
-        ]);
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
-            r"(\-=)", r"(\*=)", r"(\\=)", "~/", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)",
-            r"(>)", "\\?", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", "\\?\\?",
-        ]);
+        result.bounded("string", "'", "'", false);
+        result.keyword("variable", r"\$\{[^}]*\}|\$[A-Za-z_][A-Za-z0-9_]*");
+        // Variable names within double-quoted values, where the ${ and } are part of the interpolation
+        result.keyword("variable", r"\$\{([A-Za-z0-9_.:-]+)\}");
+        result.keyword("operator", r"(\\)$");
+        result.keyword("boolean", r"(?i)[=:]\s*(true|false)\s*$");
+        result.keyword("digit", r"[=:]\s*(-?\d+(?:\.\d+)?)\s*$");
         result
     })
 }
 
-fn c_syntax_highlighter() -> &'static Highlighter {
+fn gitconfig_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
+        result.keyword("comment", r"(?:^|\s)([;#].*)$");
+        result.keyword("table", r#"^\s*(\[[A-Za-z0-9.-]+(?:\s+"(?:[^"\\]|\\.)*")?\])"#);
+        result.keyword("key", r"^\s*([A-Za-z][A-Za-z0-9-]*)\s*(?:=|$)");
+        result.keyword("operator", r"^\s*[A-Za-z][A-Za-z0-9-]*\s*(=)");
         result.bounded("string", "\"", "\"", true);
-        add_keywords(&mut result, &[
-            "auto", "break", "case", "char", "const", "continue", "default", "do", "double",
-            "else", "enum", "extern", "float", "for", "goto", "if", "int", "long", "register",
-            "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
-            "union", "unsigned", "void", "volatile", "while", "printf", "fscanf", "scanf",
-            "fputsf", "exit", "stderr", "malloc", "calloc", "bool", "realloc", "free",
-            "strlen", "size_t",
-        ]);
-        result.keyword("struct", "\\}\\s+([A-Za-z0-9_]+)\\s*");
-        result.keyword("attribute", "^\\s*(#.*?)\\s");
-        result.keyword("header", "(<.*?>)");
-        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f|))"]);
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "(int|bool|void|char|double|long|short|size_t)\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
-        ]);
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)",
-            r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
-        ]);
+        result.keyword("boolean", r"(?i)=\s*(true|false|yes|no|on|off)\s*(?:[;#].*)?$");
+        result.keyword("digit", r"=\s*(-?\d+[kmgKMG]?)\s*(?:[;#].*)?$");
+        result.keyword("operator", r"(\\)$");
+        result
+    })
+}
+
+fn gitignore_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^(#.*)$");
+        result.keyword("operator", r"^(!)");
+        result.keyword("operator", r"(\*\*|\*|\?)");
+        result.keyword("string", r"(\[[^\]]*\])");
+        result.keyword("struct", r"([^\s/*?!\[][^\s/*?\[]*/)\s*$");
+        result.keyword("operator", r"\\.");
+        result
+    })
+}
+
+fn gitattributes_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^\s*(#.*)$");
+        result.keyword("attribute", r"^\s*(\[attr\][A-Za-z0-9_.-]+)");
+        result.keyword("operator", r"\s([-!])[A-Za-z]");
+        result.keyword("attribute", r"\s[-!]?([A-Za-z][A-Za-z0-9_.-]*)");
+        result.keyword("operator", r"\s[A-Za-z][A-Za-z0-9_.-]*(=)");
+        result.keyword("string", r"\s[A-Za-z][A-Za-z0-9_.-]*=(\S+)");
+        result.keyword("operator", r"^\s*\S*?(\*\*|\*|\?)");
+        result
+    })
+}
+
+fn git_commit_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // The subject is the first line, which is meant to fit in 50 columns
+        result.multiline_keyword("subject", r"\A([^#\n].{0,49})", 2);
+        result.multiline_keyword("warning", r"\A[^#\n].{49}(.+)", 2);
+        result.keyword("comment", r"^(#.*)$");
+        // Trailers, e.g. Signed-off-by: Name <email>
+        result.keyword("key", r"^([A-Z][A-Za-z0-9]*(?:-[A-Za-z0-9]+)*):\s");
+        result.keyword("link", r"<[^<>\s]+@[^<>\s]+>");
+        // Lines are meant to be wrapped at 72 columns, anything past that is flagged
+        result.keyword("warning", r"^[^#].{71}(.+)$");
+        result
+    })
+}
+
+fn git_rebase_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^\s*(#.*)$");
+        result.keyword("keyword", &format!(r"^({})(?:\s|$)", [
+            "pick", "p", "reword", "r", "edit", "e", "squash", "s", "fixup", "f", "exec", "x", "break", "b",
+            "drop", "d", "label", "l", "reset", "t", "merge", "m", "update-ref", "u",
+        ].join("|")));
+        result.keyword("attribute", r"^(?:fixup|f|merge|m)\s+(-[cC])\s");
+        result.keyword("digit", r"^[a-z-]+\s+(?:-[cC]\s+)?([0-9a-f]{7,40})\b");
+        result
+    })
+}
+
+fn scss_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // These come before the CSS rules so they take priority over them
+        result.keyword("comment", "(//.*)$");
+        result.bounded_interp("string", "\"", "\"", "#\\{", "\\}", true);
+        result.bounded_interp("string", "'", "'", "#\\{", "\\}", true);
+        result.keyword("variable", r"#\{[^}]*\}");
+        result.keyword("variable", r"\$[A-Za-z_][A-Za-z0-9_-]*");
+        result.keyword("keyword", &format!(r"(@(?:{}))\b", [
+            "mixin", "include", "function", "return", "if", "else if", "else", "each", "for", "while",
+            "extend", "use", "forward", "import", "debug", "warn", "error", "content", "at-root",
+        ].join("|")));
+        result.keyword("function", r"@(?:mixin|include|function)\s+([A-Za-z_][A-Za-z0-9_-]*)");
+        result.keyword("attribute", r"%[A-Za-z_][A-Za-z0-9_-]*");
+        result.keyword("operator", r"(&)");
+        result.keyword("keyword", r"\b(from|through|to|in|and|or|not)\b");
+        result.keyword("boolean", r"\b(null)\b");
+        add_css_rules(&mut result);
+        result
+    })
+}
+
+fn less_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // These come before the CSS rules so they take priority over them
+        result.keyword("comment", "(//.*)$");
+        result.keyword("string", r#"~"[^"]*"|~'[^']*'"#);
+        result.keyword("attribute", &format!(r"@(?:{})\b", [
+            "media", "import", "font-face", "keyframes", "supports", "charset", "namespace", "page",
+            "plugin", "container", "layer",
+        ].join("|")));
+        result.keyword("variable", r"@@?[A-Za-z_][A-Za-z0-9_-]*|@\{[A-Za-z0-9_-]+\}");
+        result.keyword("function", r"(\.[A-Za-z_][A-Za-z0-9_-]*)\s*\(");
+        result.keyword("function", r"^\s*(\.[A-Za-z_][A-Za-z0-9_-]*)\s*;");
+        result.keyword("keyword", r"\b(when|and|not|or)\b");
+        result.keyword("keyword", r"(!important)");
+        result.keyword("operator", r"(&)");
+        add_css_rules(&mut result);
         result
     })
 }
 
-fn cpp_syntax_highlighter() -> &'static Highlighter {
+fn cuda_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
-        result.bounded("string", "\"", "\"", true);
+        // These come before the C++ rules so they take priority over them
+        result.keyword("launch", r"(<<<.*?>>>)");
         add_keywords(&mut result, &[
-            "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break", "case",
-            "catch", "char", "char8_t", "char16_t", "char32_t", "class", "compl", "concept", "const", "consteval", "constexpr",
-            "constinit", "const_cast", "continue", "co_await", "co_return", "co_yield", "decltype", "default",
-            "delete", "do", "double", "dynamic_cast", "else", "enum", "explicit", "export", "extern", "false", "float",
-            "for", "friend", "goto", "if", "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq",
-            "nullptr", "operator", "or", "or_eq", "private", "protected", "public", "register", "reinterpret_cast", "requires", "return",
-            "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct", "switch", "template", "this",
-            "thread_local", "throw", "true", "try", "typedef", "typeid", "typename", "union", "unsigned", "using", "virtual",
-            "void", "volatile", "wchar_t", "while", "xor", "xor_eq", "std", "string",
-        ]);
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
-        result.keyword("attribute", "^\\s*(#[a-zA-Z_]+)\\s*");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)",
-            r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(|)", r"(&)", r"(^)", r"(~)",
+            "__global__", "__device__", "__host__", "__shared__", "__constant__", "__managed__",
+            "__restrict__", "__noinline__", "__forceinline__", "__launch_bounds__", "__grid_constant__",
         ]);
-        result.keyword("header", "(<.*?>)");
-        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f|))"]);
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "(int|bool|void|char|double|long|short|size_t)\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        bulk_add(&mut result, "builtin", &[
+            r"\b(threadIdx|blockIdx|blockDim|gridDim|warpSize)\b",
+            r"\b(__syncthreads|__syncwarp|__threadfence|atomicAdd|atomicSub|atomicExch|atomicCAS|atomicMin|atomicMax)\b",
         ]);
+        result.keyword("struct", r"\b((?:u?char|u?short|u?int|u?long|u?longlong|float|double)[1-4]|dim3|cudaError_t|cudaStream_t)\b");
+        add_cpp_rules(&mut result);
         result
     })
 }
 
-fn cs_syntax_highlighter() -> &'static Highlighter {
+fn vhdl_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
+        result.keyword("comment", "(--.*)$");
         result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
-        result.bounded("string", "\"", "\"", true);
-        add_keywords(&mut result, &[
-            "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked",
-            "class", "const", "continue", "decimal", "default", "delegate", "do", "double", "else",
-            "enum", "event", "explicit", "extern", "false", "finally", "fixed", "float", "for",
-            "foreach", "goto", "if", "implicit", "in", "int", "interface", "internal", "is", "lock",
-            "long", "namespace", "new", "null", "object", "operator", "out", "override", "params",
-            "private", "protected", "public", "readonly", "ref", "return", "sbyte", "sealed",
-            "short", "sizeof", "stackalloc", "static", "string", "struct", "switch", "this", "throw",
-            "true", "try", "typeof", "uint", "ulong", "unchecked", "unsafe", "ushort", "using",
-            "using", "static", "virtual", "void", "volatile", "while", "add", "alias", "ascending", "async",
-            "await", "by", "descending", "dynamic", "equals", "from", "get", "global", "group",
-            "into", "join", "let", "nameof", "on", "orderby", "partial", "remove", "select", "set",
-            "unmanaged", "value", "var", "when", "where", "with", "yield",
+        // Logic values, e.g. '1', "0101" and x"FF"
+        bulk_add(&mut result, "digit", &[
+            r"('[01UXZWLHuxzwlh-]')", r#"(\b[bBoOxXdD]"[0-9a-fA-F_]+")"#, r#"("[01UXZWLHuxzwlh_-]+")"#,
         ]);
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)",
-            r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(|)", r"(&)", r"(^)", r"(~)",
+        result.keyword("string", r#""(?:[^"]|"")*""#);
+        result.keyword("character", "'.'");
+        result.keyword("attribute", r"[A-Za-z0-9_)]('[A-Za-z_][A-Za-z0-9_]*)");
+        result.keyword("struct", r"(?i)\b(?:entity|architecture|component|package(?:\s+body)?|configuration)\s+([a-z_][a-z0-9_]*)");
+        result.keyword("struct", r"(?i)\barchitecture\s+[a-z_][a-z0-9_]*\s+of\s+([a-z_][a-z0-9_]*)");
+        add_keywords_ignore_case(&mut result, &[
+            "abs", "access", "after", "alias", "all", "and", "architecture", "array", "assert", "attribute",
+            "begin", "block", "body", "buffer", "bus", "case", "component", "configuration", "constant",
+            "disconnect", "downto", "else", "elsif", "end", "entity", "exit", "file", "for", "function",
+            "generate", "generic", "group", "guarded", "if", "impure", "in", "inertial", "inout", "is",
+            "label", "library", "linkage", "literal", "loop", "map", "mod", "nand", "new", "next", "nor",
+            "not", "null", "of", "on", "open", "or", "others", "out", "package", "port", "postponed",
+            "procedure", "process", "pure", "range", "record", "register", "reject", "rem", "report",
+            "return", "rol", "ror", "select", "severity", "signal", "shared", "sla", "sll", "sra", "srl",
+            "subtype", "then", "to", "transport", "type", "unaffected", "units", "until", "use", "variable",
+            "wait", "when", "while", "with", "xnor", "xor",
         ]);
-        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f|m|))"]);
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "(int|bool|void|char|double|long|short|size_t)\\s+([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "\\b([a-z_][A-Za-z0-9_]*)\\s*\\(",
+        result.keyword("struct", r"(?i)\b(std_logic|std_ulogic|std_logic_vector|std_ulogic_vector|signed|unsigned|integer|natural|positive|boolean|bit|bit_vector|string|real|time)\b");
+        result.keyword("boolean", r"(?i)\b(true|false)\b");
+        result.keyword("digit", r"\b(\d[\d_]*(?:\.[\d_]+)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(<=)", r"(:=)", r"(=>)", r"(/=)", r"(>=)", r"(\*\*)", r"(=)", r"(<)", r"(>)", r"(\+)", r"(\-)",
+            r"(\*)", r"(/)", r"(&)",
         ]);
+        result.keyword("function", r"(?i)\b(rising_edge|falling_edge|to_unsigned|to_signed|to_integer|resize|shift_left|shift_right)\s*\(");
         result
     })
 }
 
-fn swift_syntax_highlighter() -> &'static Highlighter {
+fn verilog_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
         result.bounded("comment", r"/\*", r"\*/", false);
         result.keyword("comment", "(//.*)$");
-        result.bounded_interp("string", "#\"", "\"#", "\\\\#?\\(", "\\)", true);
-        result.bounded("string", "\"\"\"", "\"\"\"", true);
-        result.bounded_interp("string", "\"", "\"", "\\\\\\(", "\\)", true);
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        result.bounded("string", "\"", "\"", true);
+        result.keyword("attribute", r"`[A-Za-z_][A-Za-z0-9_]*");
+        result.keyword("attribute", r"\(\*.*?\*\)");
+        result.keyword("digit", r"(?:\b\d+)?'[sS]?[bBoOdDhH]\s*[0-9a-fA-FxXzZ?_]+|'[01xXzZ]");
+        result.keyword("function", r"\$[A-Za-z_][A-Za-z0-9_$]*");
         add_keywords(&mut result, &[
-            "associatedtype", "class", "deinit", "enum", "extension", "fileprivate", "func",
-            "import", "init", "inout", "internal", "let", "open", "operator", "private",
-            "protocol", "public", "static", "struct", "subscript", "typealias", "var", "break",
-            "case", "continue", "default", "defer", "do", "else", "fallthrough", "for", "guard",
-            "if", "in", "repeat", "return", "switch", "where", "while", "as", "catch", "throw",
-            "try", "Any", "false", "is", "nil", "super", "self", "Self", "true", "associativity",
-            "convenience", "dynamic", "didSet", "final", "get", "infix", "indirect", "lazy", "left",
-            "mutating", "none", "nonmutating", "optional", "override", "postfix", "precedence", "prefix",
-            "Protocol", "required", "right", "set", "Type", "unowned", "weak", "willSet", "Int",
-            "String", "Double", "Optional", "endif",
+            "always", "always_comb", "always_ff", "always_latch", "and", "assign", "automatic", "begin",
+            "bit", "buf", "byte", "case", "casex", "casez", "class", "const", "default", "defparam",
+            "disable", "do", "else", "end", "endcase", "endclass", "endfunction", "endgenerate",
+            "endinterface", "endmodule", "endpackage", "endprogram", "endtask", "enum", "event", "extends",
+            "final", "for", "foreach", "forever", "fork", "function", "generate", "genvar", "if", "import",
+            "initial", "inout", "input", "int", "integer", "interface", "join", "join_any", "join_none",
+            "localparam", "logic", "longint", "module", "nand", "negedge", "new", "nor", "not", "or",
+            "output", "package", "packed", "parameter", "posedge", "program", "real", "reg", "repeat",
+            "return", "shortint", "signed", "struct", "supply0", "supply1", "task", "time", "tri",
+            "typedef", "union", "unique", "unsigned", "virtual", "void", "wait", "while", "wire", "wor",
+            "xnor", "xor",
         ]);
+        result.keyword("struct", r"\b(?:module|interface|package|program|class)\s+([A-Za-z_][A-Za-z0-9_]*)");
+        result.keyword("struct", r"^\s*([A-Za-z_][A-Za-z0-9_]*)\s+(?:#\s*\(|[A-Za-z_][A-Za-z0-9_]*\s*\()");
+        result.keyword("digit", r"\b(\d[\d_]*(?:\.\d+)?)\b");
         bulk_add(&mut result, "operator", &[
-            "=", "\\+", "\\-", "\\*", "[^/](/)[^/]", "\\+=", "\\-=", "\\*=", "\\\\=", "==",
-            "!=", "\\?", ">=", "<=", "<", ">", "!",
-        ]);
-        bulk_add(&mut result, "digit", &["\\b(\\d+.\\d+|\\d+)", "\\b(\\d+.\\d+(?:f32|f64))"]);
-        bulk_add(&mut result, "boolean", &["\\b(true)\\b", "\\b(false)\\b"]);
-        bulk_add(&mut result, "function", &[
-            "func\\s+([a-z_][A-Za-z0-9_]*)\\s*(?:\\(|<)",
-            "\\.([a-z_][A-Za-z0-9_]*)\\s*\\(",
-            "([a-z_][A-Za-z0-9_]*)\\s*\\(",
+            r"(<=)", r"(>=)", r"(===)", r"(!==)", r"(==)", r"(!=)", r"(&&)", r"(\|\|)", r"(<<<)", r"(>>>)",
+            r"(<<)", r"(>>)", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(/)", r"(%)", r"(!)", r"(~)", r"(&)",
+            r"(\|)", r"(\^)", r"(\?)", r"(@)", r"(#)",
         ]);
         result
     })
 }
 
-fn json_syntax_highlighter() -> &'static Highlighter {
+fn llvm_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("string", "\"", "\"", true);
-        result.keyword("keyword", r"\b(null)\b");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("boolean", "\\b(true|false)\\b");
+        result.keyword("comment", "(;.*)$");
+        result.keyword("string", r#"c?"[^"]*""#);
+        result.keyword("global", r#"@(?:[-a-zA-Z$._][-a-zA-Z$._0-9]*|\d+|"[^"]*")"#);
+        result.keyword("local", r#"%(?:[-a-zA-Z$._][-a-zA-Z$._0-9]*|\d+|"[^"]*")"#);
+        result.keyword("attribute", r"![A-Za-z._][-A-Za-z0-9._]*|!\d+|#\d+");
+        result.keyword("function", r"^([-a-zA-Z$._0-9]+):");
+        result.keyword("struct", r"\b(i\d+|half|bfloat|float|double|fp128|x86_fp80|ppc_fp128|ptr|void|label|metadata|token|opaque)\b");
+        add_keywords(&mut result, &[
+            // Top level
+            "define", "declare", "global", "constant", "private", "internal", "external", "linkonce",
+            "linkonce_odr", "weak", "weak_odr", "common", "appending", "extern_weak", "dso_local",
+            "dso_preemptable", "unnamed_addr", "local_unnamed_addr", "align", "section", "comdat", "type",
+            "target", "datalayout", "triple", "source_filename", "attributes", "personality", "to",
+            // Instructions
+            "ret", "br", "switch", "indirectbr", "invoke", "resume", "unreachable", "fneg", "add", "fadd",
+            "sub", "fsub", "mul", "fmul", "udiv", "sdiv", "fdiv", "urem", "srem", "frem", "shl", "lshr",
+            "ashr", "and", "or", "xor", "extractelement", "insertelement", "shufflevector", "extractvalue",
+            "insertvalue", "alloca", "load", "store", "fence", "cmpxchg", "atomicrmw", "getelementptr",
+            "trunc", "zext", "sext", "fptrunc", "fpext", "fptoui", "fptosi", "uitofp", "sitofp", "ptrtoint",
+            "inttoptr", "bitcast", "addrspacecast", "icmp", "fcmp", "phi", "select", "freeze", "call",
+            "tail", "musttail", "va_arg", "landingpad", "catchpad", "cleanuppad",
+            // Flags and conditions
+            "nuw", "nsw", "exact", "inbounds", "volatile", "eq", "ne", "ugt", "uge", "ult", "ule", "sgt",
+            "sge", "slt", "sle", "oeq", "ogt", "oge", "olt", "ole", "one", "ord", "ueq", "une", "uno",
+            "nounwind", "noinline", "alwaysinline", "optnone", "readonly", "readnone", "noundef", "nonnull",
+            "nocapture", "signext", "zeroext",
+        ]);
+        result.keyword("boolean", r"\b(true|false|null|undef|poison|zeroinitializer)\b");
+        result.keyword("digit", r"\b(-?\d+(?:\.\d+)?(?:e[+-]?\d+)?|0x[0-9a-fA-F]+)\b");
+        bulk_add(&mut result, "operator", &[r"(=)", r"(\*)", r"(\.\.\.)"]);
         result
     })
 }
 
-fn kotlin_syntax_highlighter() -> &'static Highlighter {
+fn riscv_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
         result.bounded("comment", r"/\*", r"\*/", false);
+        result.keyword("comment", "(#.*)$");
         result.keyword("comment", "(//.*)$");
-        result.bounded("string", "\"\"\"", "\"\"\"", true);
         result.bounded("string", "\"", "\"", true);
-        result.keyword("attribute", r"@\w+");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
-            r"(\&\&)", r"(\|\|)", r"(!)\S",
-        ]);
+        result.keyword("function", r"^\s*([A-Za-z_.$][A-Za-z0-9_.$]*):");
+        result.keyword("attribute", r"(?:^|\s)(\.[A-Za-z_][A-Za-z0-9_]*)\b");
+        result.keyword("attribute", r"%(?:hi|lo|pcrel_hi|pcrel_lo|tprel_hi|tprel_lo|got_pcrel_hi)\b");
+        result.keyword("register", &format!(r"\b(?:x(?:[12]?\d|3[01])|f(?:[12]?\d|3[01])|{})\b", [
+            "zero", "ra", "sp", "gp", "tp", "fp", "pc", "t[0-6]", "s(?:1[01]|\\d)", "a[0-7]",
+            "ft(?:1[01]|\\d)", "fs(?:1[01]|\\d)", "fa[0-7]",
+        ].join("|")));
         add_keywords(&mut result, &[
-            "abstract", "actual", "annotation", "companion", "constructor", "enum", "external", "expect",
-            "final", "fun", "inline", "inner", "interface", "internal", "private", "protected", "public",
-            "sealed", "suspend", "tailrec", "vararg", "as", "break", "class", "continue", "do", "else",
-            "false", "for", "if", "in", "is", "null", "object", "infix", "package", "return", "super", "this",
-            "throw", "true", "try", "data", "typealias", "typeof", "val", "when", "while", "var", "operator",
-            "override",
-        ]);
-        bulk_add(&mut result, "function", &[
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\{",
+            "lui", "auipc", "jal", "jalr", "beq", "bne", "blt", "bge", "bltu", "bgeu", "lb", "lh", "lw", "ld",
+            "lbu", "lhu", "lwu", "sb", "sh", "sw", "sd", "addi", "slti", "sltiu", "xori", "ori", "andi",
+            "slli", "srli", "srai", "add", "sub", "sll", "slt", "sltu", "xor", "srl", "sra", "or", "and",
+            "addiw", "slliw", "srliw", "sraiw", "addw", "subw", "sllw", "srlw", "sraw", "fence", "ecall",
+            "ebreak", "mul", "mulh", "mulhsu", "mulhu", "div", "divu", "rem", "remu", "mulw", "divw",
+            "divuw", "remw", "remuw", "lr\\.[wd]", "sc\\.[wd]", "amo[a-z]+\\.[wd]", "csrr[wsc]i?",
+            "fl[whd]", "fs[whd]", "f[a-z]+\\.[sdqh](?:\\.[a-z]+)?", "li", "la", "mv", "not", "neg",
+            "nop", "j", "jr", "ret", "call", "tail", "beqz", "bnez", "blez", "bgez", "bltz", "bgtz",
+            "bgt", "ble", "bgtu", "bleu", "seqz", "snez", "sltz", "sgtz", "csrr", "csrw",
         ]);
+        result.keyword("digit", r"\b(-?(?:0x[0-9a-fA-F]+|0b[01]+|\d+))\b");
         result
     })
 }
 
-fn java_syntax_highlighter() -> &'static Highlighter {
+fn arm_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
         result.bounded("comment", r"/\*", r"\*/", false);
         result.keyword("comment", "(//.*)$");
+        result.keyword("comment", "(@.*)$");
+        result.keyword("comment", r"^\s*(#.*)$");
         result.bounded("string", "\"", "\"", true);
-        result.keyword("attribute", r"@\w+");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
-            r"(\&\&)", r"(\|\|)", r"(!)\S",
-        ]);
-        add_keywords(&mut result, &[
-            "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const", "continue",
-            "default", "do", "double", "else", "enum", "extends", "final", "finally", "float", "for", "if", "goto",
-            "implements", "import", "instanceof", "int", "interface", "long", "native", "new", "package", "private",
-            "protected", "public", "return", "short", "static", "strictfp", "super", "switch", "synchronized", "this",
-            "throw", "throws", "transient", "try", "var", "void", "volatile", "while", "null",
-        ]);
-        bulk_add(&mut result, "function", &[
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        result.keyword("function", r"^\s*([A-Za-z_.$][A-Za-z0-9_.$]*):");
+        result.keyword("attribute", r"(?:^|\s)(\.[A-Za-z_][A-Za-z0-9_]*)\b");
+        result.keyword("attribute", r":(?:lo12|got|got_lo12|tprel_hi12|tprel_lo12_nc):");
+        result.keyword("register", &format!(r"(?i)\b(?:{})\b", [
+            "r(?:1[0-5]|\\d)", "[xw](?:[12]?\\d|30)", "[bhsdqv](?:[12]?\\d|3[01])(?:\\.\\d*[bhsd])?",
+            "sp", "lr", "pc", "fp", "ip", "xzr", "wzr", "wsp", "cpsr", "spsr", "apsr", "nzcv", "fpcr", "fpsr",
+        ].join("|")));
+        add_keywords_ignore_case(&mut result, &[
+            // Conditional forms come first so that they aren't cut short
+            "b\\.(?:eq|ne|cs|hs|cc|lo|mi|pl|vs|vc|hi|ls|ge|lt|gt|le|al)",
+            "b(?:eq|ne|cs|hs|cc|lo|mi|pl|vs|vc|hi|ls|ge|lt|gt|le|al)",
+            "bl(?:eq|ne|cs|hs|cc|lo|mi|pl|vs|vc|hi|ls|ge|lt|gt|le|al)",
+            "mov(?:eq|ne|cs|hs|cc|lo|mi|pl|vs|vc|hi|ls|ge|lt|gt|le|al)",
+            "adc", "adcs", "add", "adds", "adr", "adrp", "and", "ands", "asr", "b", "bic", "bics", "bl",
+            "blr", "blx", "br", "bx", "cbnz", "cbz", "cmn", "cmp", "csel", "cset", "csinc", "csinv",
+            "csneg", "dmb", "dsb", "eor", "eon", "isb", "ldp", "ldr", "ldrb", "ldrh", "ldrsb", "ldrsh",
+            "ldrsw", "ldur", "ldm", "ldmia", "ldmfd", "ldxr", "ldaxr", "lsl", "lsr", "madd", "mla", "mls",
+            "mov", "movk", "movn", "movz", "movs", "msub", "mul", "muls", "mvn", "neg", "nop", "orn",
+            "orr", "pop", "push", "ret", "ror", "rsb", "sbc", "sdiv", "smull", "stp", "str", "strb",
+            "strh", "stur", "stm", "stmia", "stmfd", "stmdb", "stxr", "stlxr", "sub", "subs", "svc",
+            "swi", "sxtw", "teq", "tst", "tbz", "tbnz", "udiv", "umull", "uxtb", "uxth", "wfe", "wfi",
         ]);
+        result.keyword("digit", r"(#-?(?:0x[0-9a-fA-F]+|\d+))");
+        result.keyword("digit", r"\b(-?(?:0x[0-9a-fA-F]+|\d+))\b");
+        bulk_add(&mut result, "operator", &[r"(\[)", r"(\])", r"(!)", r"(\{)", r"(\})", r"(=)"]);
         result
     })
 }
 
-fn vb_syntax_highlighter() -> &'static Highlighter {
+fn handlebars_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "('.*)$");
+        // These come before the HTML rules so they take priority over them
+        result.bounded("comment", r"\{\{!--", r"--\}\}", false);
+        result.bounded("comment", r"\{\{!", r"\}\}", false);
+        result.bounded_interp("string", "\"", "\"", r"\{\{\{?", r"\}\}\}?", true);
+        result.keyword("macro", r"\{\{\{|\}\}\}|\{\{~?[#/^>&]?|~?\}\}");
+        result.keyword("keyword", r"\{\{~?[#/^]\s*([A-Za-z_][A-Za-z0-9_-]*)");
+        result.keyword("keyword", r"\{\{~?\s*(else)\b");
+        result.keyword("function", r"\{\{~?>\s*([A-Za-z0-9_./-]+)");
+        result.keyword("keyword", r"(@[A-Za-z_]+|\bthis\b)");
+        result.keyword("attribute", r"\b([A-Za-z_][A-Za-z0-9_-]*)=");
+        result.bounded("string", "'", "'", true);
+        add_html_rules(&mut result);
+        result
+    })
+}
+
+fn rst_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // Section titles are marked by the lines above and below them
+        result.keyword("heading", r#"^([=\-~^*#"'`:.+_]{3,})\s*$"#);
+        result.keyword("attribute", r"^\s*(\.\.\s+[A-Za-z0-9_-]+::)");
+        result.keyword("link", r"^\s*(\.\.\s+_[^:]+:)");
+        result.keyword("reference", r"^\s*(\.\.\s+\[[^\]]+\])");
+        result.keyword("comment", r"^\s*(\.\..*)$");
+        result.keyword("key", r"^\s*(:[^:\s][^:]*:)(?:\s|$)");
+        // Roles, e.g. :ref:`target`
+        result.keyword("attribute", r"(:[A-Za-z0-9_.+-]+:(?:[A-Za-z0-9_.+-]+:)?)`[^`]+`");
+        result.keyword("reference", r":[A-Za-z0-9_.+-]+:(?:[A-Za-z0-9_.+-]+:)?(`[^`]+`)");
+        result.bounded("block", "``", "``", false);
+        result.keyword("link", r"`[^`]+`__?");
+        result.keyword("reference", r"`[^`]+`");
+        result.keyword("reference", r"\[[0-9#*]+\]_|\|[^|\s][^|]*\|");
+        result.bounded("bold", r"\*\*", r"\*\*", false);
+        result.bounded("italic", r"\*", r"\*", false);
+        result.keyword("link", r"\b(?:https?://|www\.)\S+\b");
+        result.keyword("list", r"^\s*(?:[-*+•]|\d+[.)]|#\.|[a-zA-Z][.)])\s");
+        result.keyword("operator", r"(::)\s*$");
+        result.grammar_mut().prose_kinds = ["comment", "heading", "bold", "italic"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        result.grammar_mut().prose_plain = true;
+        result
+    })
+}
+
+fn asciidoc_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.bounded("comment", r"^/{4,}\s*$", r"^/{4,}\s*$", false);
+        result.keyword("comment", r"^(//(?:[^/].*)?)$");
+        // Listing blocks, highlighted using the language named in a [source,<lang>] line before them
+        result.keyword("block", r"^(-{4,})\s*$");
+        let delimiter = r"^-{4,}\s*$";
+        for (names, highlighter) in markdown_fence_languages() {
+            result.multiline_embed(format!(r"^\[source,\s*(?:{names})\s*(?:,[^\]]*)?\]\s*\n-{{4,}}\s*$"), delimiter.to_string(), 2, highlighter);
+        }
+        // Listing blocks in any other language are highlighted as a whole
+        let mut plain = Highlighter::new(4);
+        plain.keyword("block", ".+");
+        result.embed(delimiter, delimiter, &plain);
+        result.bounded("block", r"^\.{4,}\s*$", r"^\.{4,}\s*$", false);
+        result.bounded("block", r"^\+{4,}\s*$", r"^\+{4,}\s*$", false);
+        result.keyword("heading", r"^([=#]{1,6}\s.*)$");
+        result.keyword("heading", r"^(\.[^\s.].*)$");
+        result.keyword("key", r"^(:!?[A-Za-z0-9_][A-Za-z0-9_-]*!?:)");
+        result.keyword("attribute", r"^(\[[^\]]*\])\s*$");
+        result.keyword("keyword", r"^(NOTE|TIP|IMPORTANT|WARNING|CAUTION):\s");
+        result.keyword("list", r"^\s*(?:[*.]+|-|\d+\.|[a-z]\.)\s");
+        result.keyword("reference", r"<<[^>]+>>|\b(?:xref|link|image|include|footnote):[^\[\s]*\[[^\]]*\]");
+        result.keyword("reference", r"\{[A-Za-z0-9_-]+\}");
+        result.keyword("link", r"\b(?:https?://|www\.)[^\s\[]+(?:\[[^\]]*\])?");
+        result.keyword("block", r"`[^`]+`|\+[^+\s][^+]*\+");
+        result.keyword("bold", r"\*\*[^*]+\*\*|\*[^*\s][^*]*\*");
+        result.keyword("italic", r"__[^_]+__|\b_[^_\s][^_]*_\b");
+        result.keyword("operator", r"\s(\+)$");
+        result.grammar_mut().prose_kinds = ["comment", "heading", "bold", "italic"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        result.grammar_mut().prose_plain = true;
+        result
+    })
+}
+
+fn org_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // Source blocks, highlighted using the language named after #+begin_src
+        result.keyword("block", r"(?i)^\s*(#\+(?:begin|end)_\w+)");
+        result.keyword("info", r"(?i)^\s*#\+begin_src\s+(\S+)");
+        let end = r"(?i)^\s*#\+end_src\s*$";
+        for (names, highlighter) in markdown_fence_languages() {
+            result.embed(format!(r"(?i)^\s*#\+begin_src\s+(?:{names})(?:\s.*)?$"), end.to_string(), highlighter);
+        }
+        // Other blocks, and source blocks in any other language, are highlighted as a whole
+        let mut plain = Highlighter::new(4);
+        plain.keyword("block", ".+");
+        result.embed(r"(?i)^\s*#\+begin_\w+.*$", r"(?i)^\s*#\+end_\w+\s*$", &plain);
+        result.keyword("attribute", r"^\s*(#\+[A-Za-z_]+:.*)$");
+        result.keyword("comment", r"^\s*(#(?:\s.*)?)$");
+        // Headings have a kind for each level, e.g. heading.2 for "** Heading",
+        // with the TODO keyword and tags highlighted separately
+        let todo = "TODO|DONE|NEXT|WAITING|CANCELLED";
+        for level in 1..=6 {
+            let stars = if level < 6 { format!(r"\*{{{level}}}") } else { r"\*{6,}".to_string() };
+            let kind = format!("heading.{level}");
+            result.keyword(&kind, &format!(r"^({stars})\s"));
+            result.keyword(&kind, &format!(r"^{stars}\s+(?:(?:{todo})\s+)?(.+?)(?:\s+:[A-Za-z0-9_@#%:]+:)?\s*$"));
+        }
+        result.keyword("keyword", &format!(r"^\*+\s+({todo})\b"));
+        result.keyword("attribute", r"^\*+\s+.*?(:[A-Za-z0-9_@#%:]+:)\s*$");
+        result.keyword("key", r"^\s*(:[A-Za-z0-9_-]+:)");
+        result.keyword("digit", r"[<\[]\d{4}-\d{2}-\d{2}(?: [A-Za-z]+)?(?: \d{1,2}:\d{2}(?:-\d{1,2}:\d{2})?)?(?: [.+]?\+\d+[hdwmy])?[>\]]");
+        result.keyword("link", r"\[\[[^\]]+\](?:\[[^\]]*\])?\]");
+        result.keyword("list", r"^\s*(?:[-+]|\d+[.)]|\*)\s(?:\[[ xX-]\]\s)?");
+        result.keyword("block", r"(?:^|[\s(])(=[^=\s](?:[^=]*[^=\s])?=|~[^~\s](?:[^~]*[^~\s])?~)");
+        result.keyword("bold", r"(?:^|[\s(])(\*[^*\s](?:[^*]*[^*\s])?\*)");
+        result.keyword("italic", r"(?:^|[\s(])(/[^/\s](?:[^/]*[^/\s])?/)");
+        result.keyword("strikethrough", r"(?:^|[\s(])(\+[^+\s](?:[^+]*[^+\s])?\+)");
+        result.keyword("link", r"\b(?:https?://|www\.)\S+\b");
+        result.grammar_mut().prose_kinds = [
+            "comment", "heading.1", "heading.2", "heading.3", "heading.4", "heading.5", "heading.6",
+            "bold", "italic", "strikethrough",
+        ].iter().map(|k| k.to_string()).collect();
+        result.grammar_mut().prose_plain = true;
+        result
+    })
+}
+
+fn bibtex_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^\s*(%.*)$");
+        // Braced values can contain nested braces, e.g. title = {The {Rust} Book}
+        result.bounded_balanced("string", r"=\s*(\{)", r"\}", r"\{", false);
         result.bounded("string", "\"", "\"", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "function", &["\\b([A-Za-z0-9_\\?!]*)\\s*\\("]);
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
-            r"(\&\&)", r"(\|\|)", r"(!)\S",
-        ]);
-        add_keywords(&mut result, &[
-            "AddHandler", "AddressOf", "Alias", "And", "AndAlso", "Ansi", "As", "Assembly", "Auto", "Boolean",
-            "ByRef", "Byte", "ByVal", "Call", "Case", "Catch", "CBool", "CByte", "CChar", "CDate", "CDec", "CDbl",
-            "Char", "CInt", "Class", "CLng", "CObj", "Const", "CShort", "CSng", "CStr", "CType", "Date", "Decimal",
-            "Declare", "Default", "Delegate", "Dim", "DirectCast", "Do", "Double", "Each", "Else", "ElseIf", "End",
-            "Enum", "Erase", "Error", "Event", "Exit", "False", "Finally", "For", "Friend", "Function", "Get", "GetType",
-            "GoSub", "GoTo", "Handles", "If", "Implements", "Imports", "In", "Inherits", "Integer", "Interface",
-            "Is", "IsNot", "Let", "Lib", "Like", "Long", "Loop", "Me", "Mod", "Module", "MustInherit", "MustOverride",
-            "MyBase", "MyClass", "Namespace", "Narrowing", "New", "Next", "Not", "Nothing", "NotInheritable",
-            "NotOverridable", "Object", "Of", "On", "Operator", "Option", "Optional", "Or", "OrElse", "Out", "Overloads",
-            "Overridable", "Overrides", "ParamArray", "Partial", "Private", "Property", "Protected", "Public", "RaiseEvent",
-            "ReadOnly", "ReDim", "REM", "RemoveHandler", "Resume", "Return", "SByte", "Select", "Set", "Shadows", "Shared",
-            "Short", "Single", "Static", "Step", "Stop", "String", "Structure", "Sub", "SyncLock", "Then", "Throw", "To",
-            "True", "Try", "TryCast", "TypeOf", "UInteger", "ULong", "UShort", "Using", "Variant", "Wend", "When", "While",
-            "Widening", "With", "WithEvents", "WriteOnly", "Xor", "Console",
-        ]);
+        result.keyword("keyword", r"(@[A-Za-z]+)\s*[{(]");
+        result.keyword("function", r"@[A-Za-z]+\s*[{(]\s*([^\s,{}()=]+)\s*,");
+        result.keyword("attribute", r"\b([A-Za-z][A-Za-z0-9_:.+-]*)\s*=");
+        result.keyword("digit", r"=\s*(\d+)\b");
+        bulk_add(&mut result, "operator", &[r"(=)", r"(#)"]);
         result
     })
 }
 
-fn m_syntax_highlighter() -> &'static Highlighter {
+fn vim_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", "%\\{", "%\\}", true);
-        result.keyword("comment", "(%.*)$");
-        result.bounded("string", "\'", "\'", true);
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
+        // A double quote starts a comment, unless it's the start of a string, which is only
+        // told apart by whether the quote is closed later on the line
+        result.keyword("comment", r#"^\s*(".*)$"#);
+        result.keyword("comment", r#"\s("[^"]*)$"#);
+        result.keyword("comment", r"^\s*(#.*)$");
+        result.keyword("string", r#""(?:[^"\\]|\\.)*""#);
+        result.keyword("string", "'(?:[^']|'')*'");
+        result.keyword("mapping", r"(?i)<(?:Plug|leader|localleader|silent|buffer|expr|nowait|unique|script|SID|CR|Esc|Tab|BS|Space|Bar|Bslash|Nop|Leader|Home|End|Up|Down|Left|Right|PageUp|PageDown|[CMASD]-[^>]+|F\d+)>");
+        result.keyword("function", r"\bfu(?:nction|nc|n)?!?\s+([A-Za-z0-9_:#.<>]+)");
+        result.keyword("function", r"\bdef!?\s+([A-Za-z0-9_:#.<>]+)");
+        result.keyword("variable", r"\b[gsbwtlav]:[A-Za-z0-9_#]+|&(?:[lg]:)?[a-z]+|\$[A-Z_][A-Z0-9_]*|@[a-z0-9\x22*+:/-]");
+        result.keyword("attribute", r"\bset?l?(?:ocal)?\s+(?:no|inv)?([a-z]+)");
+        result.keyword("struct", r"\b(Buf[A-Z][A-Za-z]*|File[A-Z][A-Za-z]*|Vim[A-Z][A-Za-z]*|Win[A-Z][A-Za-z]*|Insert[A-Z][A-Za-z]*|Cursor[A-Z][A-Za-z]*|TextChanged[A-Za-z]*|ColorScheme|FileType|Syntax|TermOpen|User)\b");
+        add_keywords(&mut result, &[
+            "let", "unlet", "const", "if", "elseif", "else", "endif", "for", "endfor", "while", "endwhile",
+            "try", "catch", "finally", "endtry", "throw", "function", "endfunction", "fun", "endfun", "func",
+            "endfunc", "def", "enddef", "return", "call", "execute", "exe", "echo", "echom", "echomsg",
+            "echoerr", "autocmd", "au", "augroup", "aug", "command", "com", "set", "setlocal", "setl",
+            "map", "nmap", "vmap", "xmap", "imap", "omap", "cmap", "tmap", "noremap", "nnoremap", "vnoremap",
+            "xnoremap", "inoremap", "onoremap", "cnoremap", "tnoremap", "unmap", "nunmap", "source", "so",
+            "runtime", "syntax", "syn", "highlight", "hi", "filetype", "colorscheme", "normal", "norm",
+            "silent", "abort", "range", "dict", "closure", "in", "is", "isnot", "import", "export", "vim9script",
+            "packadd",
+        ]);
+        result.keyword("boolean", r"\b(v:true|v:false|v:null|true|false)\b");
+        result.keyword("digit", r"\b(0x[0-9a-fA-F]+|\d+(?:\.\d+)?)\b");
         bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)",
-            r"(\*=)", r"(\\=)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)",
-            r"(\&\&)", r"(\|\|)", r"(!)\S",
+            r"(==\??#?)", r"(!=\??#?)", r"(=~#?)", r"(!~#?)", r"(>=)", r"(<=)", r"(\.\.)", r"(\+=)", r"(\-=)",
+            r"(\.=)", r"(&&)", r"(\|\|)", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(/)", r"(%)", r"(!)", r"(\?)",
+            r"(\|)",
         ]);
+        result
+    })
+}
+
+
+fn tcl_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^\s*(#.*)$");
+        result.keyword("comment", r";\s*(#.*)$");
+        // Command substitution is highlighted within strings
+        result.bounded_interp("string", "\"", "\"", r"\[", r"\]", true);
+        result.keyword("function", r"\bproc\s+([A-Za-z0-9_:]+)");
+        result.keyword("variable", r"\$(?:[A-Za-z0-9_]|::)+(?:\([^)]*\))?|\$\{[^}]*\}");
+        result.keyword("variable", r"\b(?:set|variable|global|upvar|incr|append|lappend)\s+([A-Za-z0-9_:]+)");
         add_keywords(&mut result, &[
-            "break", "case", "catch", "classdef", "continue", "else", "elseif", "end", "for", "function",
-            "global", "if", "otherwise", "parfor", "persistent", "return", "spmd", "switch", "try", "while",
-            "inf", "nan", "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "single",
-            "double", "char", "string", "cell", "struct", "table", "datetime", "properties", "NaN", "max",
-            "min", "length", "sort", "sum", "prod", "mode", "median", "mean", "std", "pi", "randi", "randn",
-            "rand", "clf", "shg", "close", "path", "addpath", "rmpath", "cd", "grid", "on", "axis", "square",
-            "equal", "off", "hold", "help", "doc", "lookfor", "profile", "viewer", "clc", "diary", "ctrl-c", "who",
-            "whos", "clear", "load", "format", "short", "long", "bank",
+            "proc", "return", "if", "then", "else", "elseif", "for", "foreach", "while", "switch", "break",
+            "continue", "set", "unset", "global", "variable", "upvar", "uplevel", "namespace", "package",
+            "require", "provide", "source", "expr", "incr", "append", "lappend", "lindex", "llength",
+            "list", "lrange", "lsearch", "lsort", "lset", "linsert", "lreplace", "split", "join", "string",
+            "dict", "array", "puts", "gets", "open", "close", "read", "eval", "catch", "try", "trap",
+            "finally", "error", "throw", "after", "update", "vwait", "file", "format", "scan", "regexp",
+            "regsub", "info", "rename", "clock", "exit", "exec", "lmap", "apply", "oo::class", "method",
+            "constructor", "destructor", "self", "my", "next", "in", "ni", "eq", "ne",
         ]);
-        bulk_add(&mut result, "function", &[
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        result.keyword("boolean", r"\b(true|false|yes|no|on|off)\b");
+        result.keyword("digit", r"\b(0x[0-9a-fA-F]+|\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(==)", r"(!=)", r"(<=)", r"(>=)", r"(&&)", r"(\|\|)", r"(\*\*)", r"(<<)", r"(>>)", r"(\{\*\})",
+            r"(\{)", r"(\})", r"(\[)", r"(\])", r"(<)", r"(>)", r"(\+)", r"(\-)", r"(\*)", r"(/)", r"(%)",
+            r"(!)", r"(\?)", r"(:)", r"(;)",
         ]);
         result
     })
 }
 
-fn php_syntax_highlighter() -> &'static Highlighter {
+
+fn awk_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
         result.keyword("comment", "(#.*)$");
-        result.bounded_interp("string", "\"", "\"", "\\{", "\\}", true);
-        result.bounded_interp("string", "\"", "\"", "\\$\\{", "\\}", true);
-        result.bounded("string", "\'", "\'", true);
-        result.keyword("boolean", "\\b(true|false|TRUE|FALSE)\\b");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
-        bulk_add(&mut result, "function", &[
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
-        ]);
+        result.bounded("string", "\"", "\"", true);
+        // A slash only starts a regex where an operand is expected, otherwise it divides
+        result.keyword("regex", r"(?:^|[(~,!&|;{]|\breturn)\s*(/(?:[^/\\\[]|\\.|\[(?:[^\]\\]|\\.)*\])+/)");
+        result.keyword("pattern", r"\b(BEGIN|END|BEGINFILE|ENDFILE)\b");
+        result.keyword("field", r"\$(?:\d+|[A-Za-z_][A-Za-z0-9_]*)|(\$)\(");
+        result.keyword("function", r"\bfunc(?:tion)?\s+([A-Za-z_][A-Za-z0-9_]*)");
         add_keywords(&mut result, &[
-            "__halt_compiler", "abstract", "and", "array", "as", "break", "callable", "case",
-            "catch", "class", "clone", "const", "continue", "declare", "default", "die", "do",
-            "echo", "else", "elseif", "empty", "enddeclare", "endfor", "endforeach", "endif", 
-            "endswitch", "endwhile", "eval", "exit", "extends", "final", "finally", "for", 
-            "foreach", "function", "global", "goto", "if", "implements", "include", "include_once",
-            "instanceof", "insteadof", "interface", "isset", "list", "namespace", "new", "or",
-            "print", "private", "protected", "public", "require", "require_once", "return", "static",
-            "switch", "throw", "trait", "try", "unset", "use", "var", "while", "xor",
-            "__CLASS__", "__DIR__", "__FILE__", "__FUNCTION__", "__LINE__", "__METHOD__",
-            "__NAMESPACE__", "__TRAIT__", "null",
+            "if", "else", "while", "for", "in", "do", "break", "continue", "next", "nextfile", "exit",
+            "return", "delete", "function", "func", "getline", "print", "printf", "switch", "case", "default",
         ]);
-        result.keyword("keyword", r"<\?php");
-        result.keyword("keyword", r"\?>");
+        result.keyword("function", r"\b(length|substr|index|split|sub|gsub|gensub|match|sprintf|sin|cos|atan2|exp|log|sqrt|int|rand|srand|tolower|toupper|system|close|fflush|strftime|systime|mktime|asort|asorti|patsplit|isarray|typeof)\b");
+        result.keyword("builtin", r"\b(NR|NF|FNR|FS|OFS|RS|ORS|FILENAME|SUBSEP|RSTART|RLENGTH|CONVFMT|OFMT|ENVIRON|ARGC|ARGV|ARGIND|ERRNO|FIELDWIDTHS|FPAT|IGNORECASE|PROCINFO|RT|TEXTDOMAIN)\b");
+        result.keyword("function", r"\b([A-Za-z_][A-Za-z0-9_]*)\(");
+        result.keyword("digit", r"\b(0x[0-9a-fA-F]+|\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)\b");
         bulk_add(&mut result, "operator", &[
-            r"(->)", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
-            r"(\-=)", r"(\*=)", r"(\\=)", r"(\?)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)",
-            r"(>)", r"(\$)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(\.)",
+            r"(==)", r"(!=)", r"(<=)", r"(>=)", r"(!~)", r"(&&)", r"(\|\|)", r"(\+\+)", r"(\-\-)", r"(\+=)",
+            r"(\-=)", r"(\*=)", r"(/=)", r"(%=)", r"(\^=)", r"(>>)", r"(~)", r"(<)", r"(>)", r"(=)", r"(\+)",
+            r"(\-)", r"(\*)", r"(/)", r"(%)", r"(\^)", r"(!)", r"(\?)", r"(:)", r"(\|)",
         ]);
         result
     })
 }
 
-fn scala_syntax_highlighter() -> &'static Highlighter {
+
+fn http_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // Lines starting with ### separate requests in REST client files
+        result.keyword("comment", r"^\s*((?:#|//).*)$");
+        result.keyword("variable", r"^(@[A-Za-z0-9_.-]+)\s*=");
+        result.keyword("variable", r"\{\{[^}]*\}\}");
+        // Request and status lines
+        result.keyword("keyword", r"^(GET|POST|PUT|PATCH|DELETE|HEAD|OPTIONS|TRACE|CONNECT)\s");
+        result.keyword("link", r"^(?:GET|POST|PUT|PATCH|DELETE|HEAD|OPTIONS|TRACE|CONNECT)\s+(\S+)");
+        result.keyword("keyword", r"(HTTP/[0-9.]+)");
+        result.keyword("digit", r"^HTTP/[0-9.]+\s+(\d{3})\b");
+        result.keyword("string", r"^HTTP/[0-9.]+\s+\d{3}\s+(.+)$");
+        // Headers
+        result.keyword("attribute", r"^([A-Za-z0-9!#$%&'*+.^_`|~-]+):");
+        result.keyword("string", r"^[A-Za-z0-9!#$%&'*+.^_`|~-]+:\s*(.+)$");
+        // JSON bodies
+        result.keyword("string", r#""(?:[^"\\]|\\.)*""#);
+        result.keyword("keyword", r"\b(null)\b");
+        result.keyword("boolean", r"\b(true|false)\b");
+        result.keyword("digit", r"(-?\b\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)\b");
+        result
+    })
+}
+
+
+fn xquery_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.keyword("comment", "(//.*)$");
-        result.bounded_interp("string", "f\"", "\"", "\\$\\{", "\\}", true);
-        result.bounded_interp("string", "s\"", "\"", "\\$\\{", "\\}", true);
-        result.bounded("string", "\"\"\"", "\"\"\"", true);
-        result.bounded("string", "raw\"", "\"", true);
-        result.bounded("string", "\"", "\"", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        result.keyword("boolean", "\\b(true|false)\\b");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)", r"(\*=)", r"(\\=)",
-            r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S",
-        ]);
+        result.bounded_nested("comment", r"\(:", r":\)", false);
+        result.bounded("string", "\"", "\"", false);
+        result.bounded("string", "'", "'", false);
+        result.keyword("keyword", r"\b(ancestor-or-self|ancestor|attribute|child|descendant-or-self|descendant|following-sibling|following|namespace|parent|preceding-sibling|preceding|self)::");
+        result.keyword("type", r"\b(node|text|comment|element|attribute|document-node|processing-instruction|schema-element|schema-attribute|namespace-node|item|empty-sequence)\s*\(");
+        result.keyword("type", r"\b(xs:[A-Za-z]+)\b");
+        result.keyword("variable", r"\$(?:[A-Za-z_][A-Za-z0-9_.-]*:)?[A-Za-z_][A-Za-z0-9_.-]*");
+        result.keyword("attribute", r"@(?:[A-Za-z_][A-Za-z0-9_.-]*:)?[A-Za-z_*][A-Za-z0-9_.-]*");
         add_keywords(&mut result, &[
-            "abstract", "case", "catch", "class", "def", "do", "else", "extends", "false", "final", "finally",
-            "for", "forSome", "if", "implicit", "import", "lazy", "macro", "match", "new", "null", "object",
-            "override", "package", "private", "protected", "return", "sealed", "super", "this", "throw", "trait",
-            "try", "true", "type", "val", "var", "while", "with", "yield", "Boolean", "Byte", "Char", "Double",
-            "Float", "Int", "Long", "Short", "String", "Unit", "Any", "AnyVal", "AnyRef", "Nothing", "Null",
-            "foreach", "map", "println", "to", "by",
+            "for", "let", "where", "order", "by", "group", "count", "return", "if", "then", "else", "some",
+            "every", "satisfies", "in", "as", "instance", "of", "cast", "castable", "treat", "typeswitch",
+            "switch", "case", "default", "declare", "function", "variable", "namespace", "module", "import",
+            "schema", "at", "external", "and", "or", "div", "idiv", "mod", "eq", "ne", "lt", "le", "gt", "ge",
+            "is", "union", "intersect", "except", "to", "ascending", "descending", "stable", "empty",
+            "greatest", "least", "xquery", "version", "encoding", "option", "try", "catch", "map", "array",
+            "tumbling", "sliding", "window", "start", "end", "when", "only", "previous", "next",
         ]);
-        bulk_add(&mut result, "function", &[
-            "\\.([a-z_][A-Za-z0-9_\\?!]*)\\s*",
-            "\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\(",
+        result.keyword("function", r"\b((?:[A-Za-z_][A-Za-z0-9_.-]*:)?[A-Za-z_][A-Za-z0-9_.-]*)\s*\(");
+        result.keyword("boolean", r"\b(true|false)\(\)");
+        result.keyword("digit", r"\b(\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(//)", r"(:=)", r"(=>)", r"(!=)", r"(<=)", r"(>=)", r"(<<)", r"(>>)", r"(\|\|)", r"(/)", r"(\|)",
+            r"(=)", r"(<)", r"(>)", r"(\+)", r"(\-)", r"(\*)", r"(\[)", r"(\])", r"(!)", r"(\?)",
         ]);
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
         result
     })
 }
 
-fn prolog_syntax_highlighter() -> &'static Highlighter {
+
+fn regex_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(\\%.*)$");
-        result.bounded("string", "\"", "\"", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("struct", "\\b([A-Z][A-Za-z0-9_]*)\\b");
-        add_keywords_no_boundary(&mut result, &[
-            ":-", "\\,", "\\.", ";", "\\->", "\\+", "=", "is", "not", "fail", "!", "repeat", "call", "cut",
-            "assert", "asserta", "assertz", "retract", "abolish", "dynamic", "consult", "listing", "op",
-            "assertions", "clauses", "predicate", "query", "rule", "fact", "variable", "atom", "number",
-            "list", "compound", "ground", "callable", "atom", "number", "integer", "float", "variable",
-            "list", "compound",
+        result.keyword("comment", r"\(\?#[^)]*\)");
+        result.keyword("anchor", r"\\[bBAzZG<>]|\^|\$");
+        result.keyword("escape", r"\\(?:x\{[0-9a-fA-F]+\}|x[0-9a-fA-F]{2}|u\{[0-9a-fA-F]+\}|u[0-9a-fA-F]{4}|[pP]\{[^}]*\}|[pP][A-Za-z]|c[A-Za-z]|0[0-7]{0,2}|k<[A-Za-z_][A-Za-z0-9_]*>|.)");
+        result.keyword("class", r"\[\^?\]?(?:\[:\^?[a-z]+:\]|\\.|[^\]\\])*\]|\.");
+        result.keyword("flag", r"\(\?(?:[a-zA-Z]+(?:-[a-zA-Z]+)?|-[a-zA-Z]+)[:)]");
+        result.keyword("group", r"\(\?(?:P?<[A-Za-z_][A-Za-z0-9_]*>|'[A-Za-z_][A-Za-z0-9_]*'|<[=!]|[:=!>|]|P=[A-Za-z_][A-Za-z0-9_]*\))|\(|\)");
+        result.keyword("quantifier", r"(?:[*+?]|\{\d+(?:,\d*)?\}|\{,\d+\})[?+]?");
+        result.keyword("operator", r"\|");
+        result
+    })
+}
+
+
+fn mermaid_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        result.keyword("comment", r"^\s*(%%.*)$");
+        result.keyword("keyword", r"^\s*(graph|flowchart|sequenceDiagram|classDiagram|stateDiagram-v2|stateDiagram|erDiagram|journey|gantt|pie|gitGraph|mindmap|timeline|quadrantChart|requirementDiagram|C4Context|sankey-beta|xychart-beta|block-beta)\b");
+        result.keyword("keyword", r"^\s*(?:graph|flowchart)\s+(TB|TD|BT|RL|LR)\b");
+        result.bounded("string", "\"", "\"", false);
+        // Edge labels and message text
+        result.keyword("string", r"\|[^|]*\|");
+        result.keyword("string", r"(?:->>|-->>|->|-->|-x|--x|-\)|--\))\s*[A-Za-z0-9_]+\s*:\s*(.+)$");
+        result.keyword("string", r"^\s*[Nn]ote\s+[^:]*:\s*(.+)$");
+        result.keyword("struct", r"\b(?:participant|actor|class|state)\s+([A-Za-z0-9_]+)");
+        // Nodes declared with a shape, e.g. A[Start] or B{Choice}
+        result.keyword("struct", r"\b([A-Za-z0-9_]+)(?:\[|\(|\{|>)");
+        result.keyword("string", r"\[\[[^\]]*\]\]|\[[^\]]*\]|\(\([^)]*\)\)|\([^)]*\)|\{\{[^}]*\}\}|\{[^}]*\}|>[^\]]*\]");
+        add_keywords(&mut result, &[
+            "subgraph", "end", "loop", "alt", "else", "opt", "par", "and", "critical", "break", "rect", "note", "Note",
+            "over", "left", "right", "of", "activate", "deactivate", "autonumber", "title", "section",
+            "click", "style", "classDef", "linkStyle", "direction", "participant", "actor", "class", "state",
+            "as", "dateFormat", "axisFormat", "commit", "branch", "checkout", "merge",
         ]);
+        result.keyword("digit", r"\b(\d+(?:\.\d+)?)\b");
         bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(<)", r"(>)",
+            r"(<<-->>)", r"(<<->>)", r"(-->>)", r"(->>)", r"(<-->)", r"(-\.->)", r"(-\.-)", r"(==>)", r"(-->)",
+            r"(---)", r"(--x)", r"(--o)", r"(--\))", r"(-x)", r"(-\))", r"(->)", r"(===)", r"(--)",
+            r"(\|\|--o\{)", r"(\}o--o\{)", r"(\|o--o\|)", r"(<\|--)", r"(\*--)", r"(o--)", r"(\.\.>)",
+            r"(\.\.)", r"(:::)", r"(&)",
         ]);
-        bulk_add(&mut result, "function", &["\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\("]);
         result
     })
 }
 
-fn haskell_syntax_highlighter() -> &'static Highlighter {
+fn plantuml_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(\\-\\-.*)$");
-        result.bounded("comment", "\\{-", "-\\}", true);
-        result.bounded("string", "\"", "\"", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("boolean", "\\b(True|False)\\b");
-        bulk_add(&mut result, "character", &[r"'[^\\]'", "'\\\\.'"]);
-        bulk_add(&mut result, "operator", &[
-            "->", "\\$", "`.*`", "<-", "<", ">", "&&", "\\|\\|", "\\\\", "\\:",
-            "=", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
-            r"(\-=)", r"(\*=)", r"(/=)", "!", "\\.", "\\|", r"(==)", r"(!=)", r"(>=)",
-            r"(<=)", "_", r"(<<)", r"(>>)", r"(!)\S", "\\band\\b", "\\bor\\b", "\\bnot\\b",
-        ]);
+        result.bounded("comment", r"/'", r"'/", false);
+        result.keyword("comment", r"^\s*('.*)$");
+        result.keyword("keyword", r"^\s*(@(?:start|end)[a-z]+)\b");
+        result.bounded("string", "\"", "\"", false);
+        // Message text after an arrow and activity labels
+        result.keyword("string", r"(?:-+|\.+)>>?\s*[A-Za-z0-9_.]+\s*:\s*(.+)$");
+        result.keyword("string", r"^\s*(:.*;)\s*$");
+        result.keyword("struct", r#"\b(?:participant|actor|boundary|control|entity|database|collections|queue|class|interface|enum|abstract|annotation|component|node|package|usecase|rectangle|object|state|cloud|folder|frame|artifact|storage|card)\s+("[^"]*"|[A-Za-z0-9_.]+)"#);
+        result.keyword("attribute", r"\bskinparam\s+([A-Za-z0-9_.]+)");
+        result.keyword("digit", r"(#[A-Za-z0-9]+)\b");
         add_keywords(&mut result, &[
-            "module", "import", "as", "qualified", "hiding", "do", "case", "of", "let", "in", "if", "then", "else",
-            "data", "type", "newtype", "deriving", "class", "instance", "where", "foreign", "export", "ccall",
-            "stdcall", "capi", "prim", "safe", "unsafe", "otherwise", "head", "tail", "last", "init", "null",
-            "length", "return", "map", "filter", "foldl", "foldr", "zip", "zipWith", "take", "drop", "reverse",
-            "concat", "concatMap", "maximum", "minimum", "elem", "notElem", "sum", "array", "product", "scanl",
-            "scanr", "replicate", "cycle", "repeat", "iterate", "fst", "snd", "id", "Maybe", "Either", "Bool",
-            "Char", "String", "putStrLn", "getLine", "Just", "Nothing", "for", "Int", "Integer", "Float",
-            "Double", "Ordering", "IO", "Functor", "Applicative", "Monad",
+            "participant", "actor", "boundary", "control", "entity", "database", "collections", "queue",
+            "class", "interface", "enum", "abstract", "annotation", "component", "node", "package", "usecase",
+            "rectangle", "object", "state", "cloud", "folder", "frame", "artifact", "storage", "card", "title",
+            "note", "end", "alt", "else", "opt", "loop", "par", "group", "break", "critical", "activate",
+            "deactivate", "destroy", "autonumber", "skinparam", "as", "over", "of", "on", "left", "right",
+            "top", "bottom", "legend", "header", "footer", "newpage", "box", "return", "ref", "start", "stop",
+            "if", "then", "elseif", "endif", "while", "endwhile", "repeat", "fork", "again", "extends",
+            "implements", "hide", "show", "caption", "scale", "direction",
+        ]);
+        result.keyword("digit", r"\b(\d+(?:\.\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(<\|--)", r"(--\|>)", r"(<\|\.\.)", r"(\.\.\|>)", r"(\*--)", r"(--\*)", r"(o--)", r"(--o)",
+            r"(<<?-+)", r"(-+>>?)", r"(<\.+)", r"(\.+>)", r"(-+\[[^\]]*\]-+>)", r"(--+)", r"(\.\.+)",
         ]);
-        result.keyword("function", "^[a-z][a-zA-Z0-9]*");
         result
     })
 }
 
-fn css_syntax_highlighter() -> &'static Highlighter {
+
+fn zsh_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", r"/\*", r"\*/", false);
-        result.bounded("string", "\"", "\"", true);
-        add_keywords(&mut result, &["from", "to", "rotate", "none"]);
-        result.keyword("digit", r"\#[0-9a-fA-F]+");
-        result.keyword("digit", "((?:\\d+.\\d+|\\d+)(?:%|deg|px|em|rem)?)");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("attribute", r"\.[a-zA-Z0-9\-]*");
-        result.keyword("attribute", r"\:[a-zA-Z0-9\-]*");
-        result.keyword("attribute", r"\::[a-zA-Z0-9\-]*");
-        result.keyword("attribute", r"@\w+");
-        add_keywords(&mut result, &[
-            "a", "abbr", "address", "area", "article", "aside", "audio", "b", "base", "bdi", "bdo", "blockquote",
-            "body", "br", "button", "canvas", "caption", "cite", "code", "col", "colgroup", "data", "datalist",
-            "dd", "del", "details", "dfn", "dialog", "div", "dl", "dt", "em", "embed", "fieldset", "figcaption",
-            "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr",
-            "html", "i", "iframe", "img", "input", "ins", "kbd", "label", "legend", "li", "link", "main", "map",
-            "mark", "meta", "meter", "nav", "noscript", "object", "ol", "optgroup", "option", "output", "p",
-            "param", "picture", "pre", "progress", "q", "rb", "rp", "rt", "rtc", "ruby", "s", "samp", "script",
-            "section", "select", "slot", "small", "source", "span", "strong", "style", "sub", "summary", "sup", 
-            "table", "tbody", "td", "template", "textarea", "tfoot", "th", "thead", "time", "title", "tr", "track",
-            "u", "ul", "var", "video", "wbr", "svg",
-        ]);
+        // Parameter expansion flags, e.g. ${(f)var}, and the length of a parameter
+        result.keyword("attribute", r"\$\{[=~^+#]*(\([^)]*\))");
+        result.keyword("operator", r"\$\{?[=~^+]*(#)");
+        // Glob qualifiers, e.g. **/*(.om[1,5])
+        result.keyword("attribute", r"[^\s$(](\((?:#q)?[./@=*%^N-][./@=p*%rwxRWXsStfeLmacoONn^:\[\],0-9-]*\))");
+        result.keyword("attribute", r"[*?\]](\((?:#q)?[./@=p*%rwxRWXsStfeLmacoONn^:\[\],0-9-]+\))");
+        result.keyword("escape", r"%[FK]\{[^}]*\}|%[fkBbUuSsnmMdy~#?%/cCDTt*l]");
+        result.keyword("variable", r"\b([A-Za-z_][A-Za-z0-9_]*)\+?=\(");
         add_keywords(&mut result, &[
-            "-webkit-touch-callout", "-webkit-user-select", "-moz-user-select", "-ms-user-select",
-            "user-select", "transform", "border-radius", "border-right", "border-left", "border-top",
-            "border-bottom", "border", "content", "display", "height", "width", "margin-top", "margin-bottom",
-            "margin-left", "margin-right", "margin", "pointer-events", "position", "top", "transform-origin",
-            "-moz-appearance", "-webkit-appearance", "cursor", "flex-grow", "flex-shrink", "font-size",
-            "max-height", "max-width", "min-height", "min-width", "outline", "vertical-align", "background-color", 
-            "background-image", "background-position", "background-repeat", "background-size", "background",
-            "animation", "border-(?:left|right|top|bottom)-color", "border-(?:left|right|top|bottom)-radius",
-            "border-(?:left|right|top|bottom)-width", "border-(?:left|right|top|bottom)-style", "align-items",
-            "box-shadow", "justify-content", "line-height", "padding", "padding-(?:left|bottom|right|top)", "font-weight",
-            "list-style", "box-sizing", "text-align", "bottom", "overflow-x", "overflow-y", "text-rendering",
-            "-moz-osx-font-smoothing", "-webkit-font-smoothing", "text-size-adjust", "font-family", "color",
-            "text-decoration", "font-style", "word-wrap", "white-space", "-webkit-overflow-scrolling",
-            "clear", "float", "overflow", "!important", "text-transform", "clip", "visibility", "border-color",
-            "opacity", "flex-wrap", "border-(?:top|bottom)-(?:left|right)-radius", "z-index", "word-break", "letter-spacing",
-            "text-transform", "resize", "flex-direction", "order", "border-style", "border-width", "text-overflow",
-            "flex-basis", "-ms-overflow-y", "-ms-overflow-x", "transition-duration", "transition-property", 
-            "transition-timing-function", "(flex)[^-]", "-webkit-text-decoration-style", "-apple-system", "sans-serif",
-            "left", "right", "bottom", "top", "font", "tab-size", "text-shadow",
+            "autoload", "setopt", "unsetopt", "zstyle", "zmodload", "compinit", "compdef", "bindkey", "zle",
+            "typeset", "emulate", "promptinit", "vared", "print", "whence", "rehash", "noglob", "integer",
+            "float", "repeat", "foreach", "end", "always", "coproc", "functions", "unfunction", "unhash",
+            "disable", "enable", "zparseopts", "zcompile", "add-zsh-hook",
         ]);
+        add_shell_rules(&mut result);
         result
     })
 }
 
-fn html_syntax_highlighter() -> &'static Highlighter {
+
+fn zig_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", "<!--", "-->", false);
+        result.keyword("comment", "(//.*)$");
+        // Multiline string literals, where each line starts with \\
+        result.keyword("string", r"(\\\\.*)$");
         result.bounded("string", "\"", "\"", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("operator", "=");
-        bulk_add(&mut result, "tag", &["</", "/>", ">", "<!", "<"]);
-        add_html_keywords(&mut result, &[
-            "a", "abbr", "address", "area", "article", "aside", "audio", "b", "base", "bdi", "bdo", "blockquote",
-            "body", "br", "button", "canvas", "caption", "cite", "code", "col", "colgroup", "data", "datalist",
-            "dd", "del", "details", "dfn", "dialog", "div", "dl", "dt", "em", "embed", "fieldset", "figcaption", 
-            "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr", "html",
-            "i", "iframe", "img", "input", "ins", "kbd", "label", "legend", "li", "link", "main", "map", "mark",
-            "meta", "meter", "nav", "noscript", "object", "ol", "optgroup", "option", "output", "p", "param", "picture",
-            "pre", "progress", "q", "rb", "rp", "rt", "rtc", "ruby", "s", "samp", "script", "section", "select", "slot",
-            "small", "source", "span", "strong", "style", "sub", "summary", "sup", "table", "tbody", "td", "template",
-            "textarea", "tfoot", "th", "thead", "time", "title", "tr", "track", "u", "ul", "var", "video", "wbr", "svg",
+        bulk_add(&mut result, "character", &[r"'[^\\']'", r"'\\(?:x[0-9a-fA-F]{2}|u\{[0-9a-fA-F]+\}|.)'"]);
+        result.keyword("builtin", r"@[A-Za-z_][A-Za-z0-9_]*");
+        add_keywords(&mut result, &[
+            "addrspace", "align", "allowzero", "and", "anyframe", "anytype", "asm", "async", "await",
+            "break", "callconv", "catch", "comptime", "const", "continue", "defer", "else", "enum",
+            "errdefer", "error", "export", "extern", "fn", "for", "if", "inline", "linksection",
+            "noalias", "noinline", "nosuspend", "opaque", "or", "orelse", "packed", "pub", "resume",
+            "return", "struct", "suspend", "switch", "test", "threadlocal", "try", "union", "unreachable",
+            "usingnamespace", "var", "volatile", "while", "null", "undefined", "void", "noreturn",
+            "type", "anyerror", "anyopaque", "bool", "isize", "usize", "comptime_int", "comptime_float",
+            "c_char", "c_short", "c_ushort", "c_int", "c_uint", "c_long", "c_ulong", "c_longlong",
+            "c_ulonglong", "c_longdouble", "f16", "f32", "f64", "f80", "f128",
+        ]);
+        result.keyword("keyword", r"\b([iu]\d+)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(\+\+)", r"(\*\*)", r"(\|\|)", r"(\.\.\.)", r"(\.\.)", r"(=>)", r"(==)", r"(!=)", r"(<=)",
+            r"(>=)", r"(<<=?)", r"(>>=?)", r"([+\-*/%&|^]=)", r"(\+%|\-%|\*%|\+\||\-\||\*\|)", r"(\.\*)",
+            r"(\.\?)", r"(=)", r"(\+)", r"(\-)", r"(\*)", r"[^/](/)[^/]", r"(%)", r"(<)", r"(>)", r"(!)",
+            r"(\?)", r"(&)", r"(\|)", r"(\^)", r"(~)",
+        ]);
+        bulk_add(&mut result, "digit", &[
+            r"\b(0x[0-9a-fA-F_]+(?:\.[0-9a-fA-F_]+)?(?:[pP][+-]?\d+)?)", r"\b(0o[0-7_]+|0b[01_]+)",
+            r"\b(\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)",
         ]);
-        bulk_add(&mut result, "attribute", &[
-            r"([A-Za-z0-9-]+)=", r"(class)\s*=", r"(id)\s*=", r"(style)\s*=", r"(src)\s*=", r"(rel)\s*=",
-            r"(type)\s*=", r"(charset)\s*=", r"(data-target)\s*=", r"(name)\s*=", r"(href)\s*=", r"(content)\s*=",
-            r"(width)\s*=", r"(height)\s*=", r"(aria-label)\s*=", r"(role)\s*=", r"(aria-hidden)\s*=",
-            r"(aria-expanded)\s*=", r"\s*defer\s*",
+        bulk_add(&mut result, "boolean", &[r"\b(true)\b", r"\b(false)\b"]);
+        bulk_add(&mut result, "function", &[
+            r"fn\s+([A-Za-z_][A-Za-z0-9_]*)\s*\(",
+            r"\.([A-Za-z_][A-Za-z0-9_]*)\s*\(",
+            r"\b([A-Za-z_][A-Za-z0-9_]*)\s*\(",
         ]);
+        result.keyword("struct", r"\b([A-Z][A-Za-z0-9_]*)\b");
         result
     })
 }
 
-fn markdown_syntax_highlighter() -> &'static Highlighter {
+
+fn objc_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", "<!--", "-->", false);
-        result.keyword("heading", "(#.*)$");
-        result.keyword("quote", "^(>.*)$");
-        result.bounded("bold", "\\*\\*", "\\*\\*", true);
-        result.bounded("italic", "\\*", "\\*", true);
-        result.bounded("strikethrough", "~~", "~~", true);
-        result.bounded("image", "!\\[", "\\]", true);
-        result.bounded("link", "\\[", "\\]", true);
-        result.bounded("math", "\\$\\$", "\\$\\$", false);
-        result.bounded("math", "\\$", "\\$", false);
-        result.bounded("block", "```", "```", false);
-        result.bounded("block", "`", "`", true);
-        result.keyword("link", r"\b(?:https?://|www\.)\S+\b");
-        result.keyword("linebreak", "^\\s*-{3}");
-        result.keyword("list", "[0-9]+\\.");
-        result.keyword("list", "^\\s*-");
-        result.keyword("list", "^\\s*\\+");
+        result.bounded("string", "@\"", "\"", true);
+        result.keyword("keyword", r"@(?:interface|implementation|end|protocol|property|synthesize|dynamic|class|selector|encode|autoreleasepool|try|catch|finally|throw|optional|required|private|public|protected|package|import|synchronized|available|compatibility_alias|defs)\b");
+        result.keyword("digit", r"@(?:\d+(?:\.\d+)?)");
+        result.keyword("boolean", r"\b(YES|NO)\b");
+        result.keyword("keyword", r"\b(default)\s*:");
+        // Method declarations and the parts of a selector in a message send
+        result.keyword("function", r"^\s*[-+]\s*\([^)]*\)\s*([A-Za-z_][A-Za-z0-9_]*)");
+        result.keyword("function", r"[\[\s)]([a-z_][A-Za-z0-9_]*):\s*[^\s:]");
+        result.keyword("function", r"\[\s*[A-Za-z_][A-Za-z0-9_.]*(?:\s*\[[^\]]*\])?\s+([a-z_][A-Za-z0-9_]*)\s*\]");
+        add_keywords(&mut result, &[
+            "id", "instancetype", "nil", "Nil", "self", "super", "BOOL", "SEL", "IMP", "Class", "nonatomic",
+            "atomic", "strong", "weak", "assign", "copy", "retain", "readonly", "readwrite", "getter",
+            "setter", "nullable", "nonnull", "null_unspecified", "__block", "__weak", "__strong",
+            "__unsafe_unretained", "__kindof", "_Nonnull", "_Nullable", "in", "out", "inout", "bycopy",
+            "byref", "oneway", "NS_ASSUME_NONNULL_BEGIN", "NS_ASSUME_NONNULL_END",
+        ]);
+        add_cpp_rules(&mut result);
         result
     })
 }
 
-fn toml_syntax_highlighter() -> &'static Highlighter {
+
+fn elixir_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "\'", "\'", true);
         result.keyword("comment", "(#.*)$");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("table", r"^(\[.*\])");
-        bulk_add(&mut result, "digit", &[
-            r"(?:=|\[|,)\s*(0x[a-fA-F]+)",
-            r"(?:=|\[|,)\s*(0o[0-7]+)",
-            r"(?:=|\[|,)\s*(0b[0-1]+)",
-            r"(?:=|\[|,)\s*((?:\+|-)?[0-9]+(?:\.[0-9]+)?(?:e|E)(?:\+|-)?[0-9]+)",
-            r"(?:=|\[|,)\s*((?:\+|-)?[0-9_]+(?:\.[0-9]+)?)",
+        // Documentation attributes hold their text in a heredoc
+        result.bounded("comment", r#"@(?:moduledoc|doc|typedoc)\s+~?[sS]?""""#, r#"""""#, false);
+        result.keyword("regex", r#"~r(?:/(?:[^/\\]|\\.)*/|\|(?:[^|\\]|\\.)*\||"(?:[^"\\]|\\.)*"|\((?:[^)\\]|\\.)*\)|\{(?:[^}\\]|\\.)*\}|\[(?:[^\]\\]|\\.)*\])[a-z]*"#);
+        result.keyword("string", r#"~[a-zA-Z]+(?:/(?:[^/\\]|\\.)*/|\|(?:[^|\\]|\\.)*\||"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|\((?:[^)\\]|\\.)*\)|\{(?:[^}\\]|\\.)*\}|\[(?:[^\]\\]|\\.)*\]|<(?:[^>\\]|\\.)*>)[a-zA-Z]*"#);
+        result.bounded_interp("string", "\"\"\"", "\"\"\"", "#\\{", "\\}", true);
+        result.bounded_interp("string", "\"", "\"", "#\\{", "\\}", true);
+        // Charlists
+        result.bounded("character", "'''", "'''", true);
+        result.bounded("character", "'", "'", true);
+        result.keyword("character", r"\?(?:\\.|[^\s\\])");
+        // Atoms and keyword list keys
+        result.keyword("string", r#":(?:[A-Za-z_][A-Za-z0-9_]*[?!]?|"(?:[^"\\]|\\.)*")"#);
+        result.keyword("string", r"\b([a-z_][A-Za-z0-9_]*[?!]?:)\s");
+        result.keyword("attribute", r"@[a-z_][A-Za-z0-9_]*");
+        result.keyword("function", r"\b(?:def|defp|defmacro|defmacrop|defguard|defguardp|defdelegate|defn|defnp)\s+([a-z_][A-Za-z0-9_]*[?!]?)");
+        add_keywords(&mut result, &[
+            "def", "defp", "defmodule", "defmacro", "defmacrop", "defguard", "defguardp", "defdelegate",
+            "defstruct", "defexception", "defprotocol", "defimpl", "defoverridable", "do", "end", "fn",
+            "case", "cond", "if", "unless", "else", "with", "for", "receive", "after", "try", "catch",
+            "rescue", "raise", "reraise", "throw", "when", "and", "or", "not", "in", "alias", "import",
+            "require", "use", "quote", "unquote", "unquote_splicing", "nil", "super", "__MODULE__",
+            "__DIR__", "__ENV__", "__CALLER__", "__STACKTRACE__",
         ]);
-        add_keywords(&mut result, &["inf", "nan"]);
+        result.keyword("boolean", r"\b(true|false)\b");
+        result.keyword("struct", r"\b([A-Z][A-Za-z0-9_]*)\b");
+        result.keyword("digit", r"\b(0x[0-9a-fA-F_]+|0o[0-7_]+|0b[01_]+|\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(\|>)", r"(<>)", r"(\+\+)", r"(\-\-)", r"(->)", r"(<-)", r"(=>)", r"(::)", r"(\\\\)", r"(===)",
+            r"(!==)", r"(==)", r"(!=)", r"(=~)", r"(<=)", r"(>=)", r"(&&)", r"(\|\|)", r"(\.\.)", r"(=)", r"(\+)",
+            r"(\-)", r"(\*)", r"[^/](/)[^/]", r"(<)", r"(>)", r"(!)", r"(&)", r"(\|)", r"(\^)",
+        ]);
+        bulk_add(&mut result, "function", &[
+            r"\.([a-z_][A-Za-z0-9_]*[?!]?)\s*\(",
+            r"\b([a-z_][A-Za-z0-9_]*[?!]?)\s*\(",
+        ]);
+        result.grammar_mut().word_chars = vec!['?', '!'];
         result
     })
 }
 
-fn yaml_syntax_highlighter() -> &'static Highlighter {
+fn erlang_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
+        result.keyword("comment", "(%.*)$");
+        result.bounded("comment", r#"^-(?:moduledoc|doc)\s+""""#, r#"""""#, false);
+        result.bounded("string", "\"\"\"", "\"\"\"", false);
         result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "\'", "\'", true);
-        result.keyword("comment", "(#.*)$");
-        result.keyword("key", r"^\s*[ \.a-zA-Z_-]+:");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("tag", "!!(?:bool|int|float|str|timestamp|null|binary)");
-        add_keywords(&mut result, &["No", "Yes", "no", "yes", "true", "false", "null"]);
+        // Quoted atoms
+        result.bounded("string", "'", "'", true);
+        result.keyword("character", r"\$(?:\\.|[^\s\\])");
+        result.keyword("attribute", r"^(-\s*[a-z_][A-Za-z0-9_]*)");
+        result.keyword("macro", r"\?\??[A-Za-z_][A-Za-z0-9_]*");
+        result.keyword("struct", r"#[a-z_][A-Za-z0-9_]*");
+        add_keywords(&mut result, &[
+            "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case", "catch",
+            "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or", "orelse", "receive",
+            "rem", "try", "when", "xor",
+        ]);
+        result.keyword("boolean", r"\b(true|false)\b");
+        result.keyword("variable", r"\b([A-Z_][A-Za-z0-9_]*)\b");
+        result.keyword("digit", r"\b(\d+#[0-9a-zA-Z]+|\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(<<)", r"(>>)", r"(->)", r"(<-)", r"(<=)", r"(=>)", r"(:=)", r"(\|\|)", r"(\+\+)", r"(\-\-)",
+            r"(=:=)", r"(=/=)", r"(==)", r"(/=)", r"(=<)", r"(>=)", r"(::)", r"(\.\.\.)", r"(=)", r"(\+)",
+            r"(\-)", r"(\*)", r"(/)", r"(<)", r"(>)", r"(!)", r"(\|)",
+        ]);
+        bulk_add(&mut result, "function", &[
+            r"^([a-z][A-Za-z0-9_]*)\s*\(",
+            r"\b([a-z][A-Za-z0-9_]*)\s*\(",
+        ]);
+        result.keyword("namespace", r"\b([a-z][A-Za-z0-9_]*):[a-z]");
         result
     })
 }
 
-fn csv_syntax_highlighter() -> &'static Highlighter {
+
+/// The rules for OCaml, which are shared with the ML languages that extend it
+fn add_ml_rules(result: &mut Highlighter) {
+    result.bounded_nested("comment", r"\(\*", r"\*\)", false);
+    result.bounded("string", "\"", "\"", true);
+    result.bounded("string", r"\{[a-z_]*\|", r"\|[a-z_]*\}", false);
+    bulk_add(result, "character", &[r"'[^\\']'", r"'\\(?:\d{3}|x[0-9a-fA-F]{2}|u\{[0-9a-fA-F]+\}|.)'"]);
+    result.keyword("type", r"'[a-z_][A-Za-z0-9_']*");
+    result.keyword("attribute", r"\[@@?@?[^\]]*\]");
+    add_keywords(result, &[
+        "and", "as", "assert", "asr", "begin", "class", "constraint", "do", "done", "downto", "else",
+        "end", "exception", "external", "for", "fun", "function", "functor", "if", "in", "include",
+        "inherit", "initializer", "land", "lazy", "let", "lor", "lsl", "lsr", "lxor", "match", "method",
+        "mod", "module", "mutable", "new", "nonrec", "not", "object", "of", "open", "or", "private", "rec",
+        "sig", "struct", "then", "to", "try", "type", "val", "virtual", "when", "while", "with", "raise",
+        "int", "float", "bool", "char", "string", "unit", "list", "array", "option", "ref",
+    ]);
+    result.keyword("boolean", r"\b(true|false)\b");
+    result.keyword("function", r"\blet\s+(?:rec\s+|inline\s+|private\s+|mutable\s+)*([a-z_][A-Za-z0-9_']*)\s+[a-z_(~?]");
+    result.keyword("struct", r"\b([A-Z][A-Za-z0-9_']*)\b");
+    result.keyword("digit", r"\b(0x[0-9a-fA-F_]+|0o[0-7_]+|0b[01_]+|\d[\d_]*(?:\.[\d_]*)?(?:[eE][+-]?\d+)?[lLnmfM]?)");
+    bulk_add(result, "operator", &[
+        r"(\|>)", r"(<\|)", r"(>>)", r"(<<)", r"(::)", r"(:=)", r"(:>)", r"(->)", r"(<-)", r"(<>)", r"(<=)",
+        r"(>=)", r"(==)", r"(!=)", r"(&&)", r"(\|\|)", r"(\+\.)", r"(\-\.)", r"(\*\.)", r"(/\.)", r"(=)",
+        r"(<)", r"(>)", r"(\+)", r"(\-)", r"(\*)", r"[^/](/)[^/]", r"(@)", r"(\^)", r"(\|)", r"(!)",
+    ]);
+}
+
+fn ocaml_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("keyword", ",");
+        add_ml_rules(&mut result);
         result
     })
 }
 
-fn shell_syntax_highlighter() -> &'static Highlighter {
+fn fsharp_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded_interp("string", "\"", "\"", "\\$\\(", "\\)", true);
-        result.bounded("string", "\'", "\'", true);
-        result.bounded("string", "EOF", "EOF", true);
-        result.keyword("comment", "(#.*)$");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)", r"(\-=)", r"(\*=)",
-            r"(\\=)", r"(\{)", r"(\})", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)", r"(\$)", r"(\.\.)",
-            r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(\.)", r"(&)",
-        ]);
+        result.keyword("comment", "(//.*)$");
+        result.bounded("string", "\"\"\"", "\"\"\"", false);
+        result.bounded_interp("string", "\\$\"", "\"", "\\{", "\\}", true);
+        result.bounded("string", "@\"", "\"", false);
+        result.bounded_balanced("attribute", r"\[<", r">\]", r"\[<", false);
+        result.keyword("attribute", r"^\s*(#[a-z]+)\b");
+        result.keyword("keyword", r"\b((?:let|use|do|match|return|yield|and)!)");
         add_keywords(&mut result, &[
-            "if", "then", "else", "elif", "fi", "case", "esac", "for", "while", "until", "do", "done",
-            "in", "function", "select", "continue", "break", "return", "exit", "source", "declare", "readonly",
-            "local", "export", "ls", "cd", "pwd", "cp", "mv", "rm", "mkdir", "rmdir", "touch", "chmod",
-            "chown", "grep", "awk", "sed", "cat", "head", "tail", "sort", "uniq", "wc", "cut", "paste",
-            "find", "tar", "gzip", "gunzip", "zip", "unzip", "ssh", "scp", "rsync", "curl", "wget", "ping",
-            "traceroute", "netstat", "ps", "kill", "top", "df", "du", "date", "cal", "history", "alias",
-            "source", "source", "exec", "exit", "help", "man", "info", "echo", "fgrep", "apropos", 
-            "whoami", "python", "bg", "fg", "sleep", "jobs", "read", "trap", "clear", "sh", "bash",
+            "abstract", "base", "default", "delegate", "downcast", "elif", "extern", "fixed", "global",
+            "inline", "interface", "internal", "member", "namespace", "null", "override", "public",
+            "return", "static", "upcast", "use", "yield", "async", "task", "seq", "byte", "sbyte", "int16",
+            "uint16", "int32", "uint32", "int64", "uint64", "decimal", "double", "single", "obj", "void",
         ]);
-        bulk_add(&mut result, "function", &["\\b([a-z_][A-Za-z0-9_\\?!]*)\\s*\\("]);
+        add_ml_rules(&mut result);
         result
     })
 }
 
-fn sql_syntax_highlighter() -> &'static Highlighter {
+fn elm_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("comment", "(--.*)$");
+        result.bounded_nested("comment", r"\{-", r"-\}", false);
+        result.keyword("comment", r"(\-\-.*)$");
+        result.bounded("string", "\"\"\"", "\"\"\"", true);
         result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "\'", "\'", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "operator", &[
-            r"\+", "-", r"\*", "/", "%", "=", "<>", "!=", "<", ">", "<=", ">=", "&", "|", "^",
-            "~", "||", "=",
-        ]);
+        bulk_add(&mut result, "character", &[r"'[^\\']'", r"'\\(?:u\{[0-9a-fA-F]+\}|.)'"]);
         add_keywords(&mut result, &[
-            "ADD", "ALL", "ALTER", "AND", "AS", "ASC", "BETWEEN", "BY", "CASE", "CHECK",
-            "COLUMN", "CONSTRAINT", "CREATE", "DATABASE", "DEFAULT", "DELETE", "DESC",
-            "DISTINCT", "DROP", "ELSE", "END", "EXISTS", "FOREIGN", "FROM", "FULL", "GROUP",
-            "HAVING", "IN", "INDEX", "INNER", "INSERT", "INTO", "IS", "JOIN", "LEFT", "LIKE",
-            "LIMIT", "NOT", "NULL", "ON", "OR", "ORDER", "OUTER", "PRIMARY", "REFERENCES",
-            "RIGHT", "SELECT", "SET", "TABLE", "TOP", "TRUNCATE", "UNION", "UNIQUE", "UPDATE",
-            "VALUES", "VIEW", "WHERE", "SHOW", "USE", "VARCHAR"
+            "module", "exposing", "import", "as", "port", "type", "alias", "case", "of", "let", "in", "if",
+            "then", "else", "where", "effect", "command", "subscription",
+        ]);
+        result.keyword("boolean", r"\b(True|False)\b");
+        // Type annotations and definitions at the top level
+        result.keyword("function", r"^([a-z_][A-Za-z0-9_']*)\s*:");
+        result.keyword("function", r"^([a-z_][A-Za-z0-9_']*)(?:\s+[^=:]*)?\s*=");
+        result.keyword("struct", r"\b([A-Z][A-Za-z0-9_]*)\b");
+        result.keyword("digit", r"\b(0x[0-9a-fA-F]+|\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)\b");
+        bulk_add(&mut result, "operator", &[
+            r"(\|>)", r"(<\|)", r"(>>)", r"(<<)", r"(::)", r"(\+\+)", r"(->)", r"(==)", r"(/=)", r"(<=)",
+            r"(>=)", r"(&&)", r"(\|\|)", r"(\.\.)", r"(//)", r"(=)", r"(<)", r"(>)", r"(\+)", r"(\-)", r"(\*)",
+            r"(/)", r"(\^)", r"(\|)", r"(\\)",
         ]);
         result
     })
 }
 
-fn xml_syntax_highlighter() -> &'static Highlighter {
+
+fn makefile_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("comment", "<!--", "-->", false);
+        result.keyword("comment", r"(#.*)$");
+        result.keyword("keyword", r"^\s*(-?include|sinclude|ifeq|ifneq|ifdef|ifndef|else|endif|define|endef|export|unexport|override|vpath|private|undefine)\b");
+        result.keyword("keyword", r"^(\.[A-Z_]+)\s*:");
+        result.keyword("variable", r"^\s*(?:export\s+|override\s+|private\s+)?([A-Za-z0-9_.-]+)\s*(?:::=|:::=|[:+?!]?=)");
+        // Rule targets, anything before a single or double colon that isn't an assignment
+        result.keyword("function", r"^([^\s:=#][^:=#]*?)\s*::?(?:\s|$|[^=:])");
+        result.keyword("function", r"\$[({](subst|patsubst|strip|findstring|filter-out|filter|sort|wordlist|words|word|firstword|lastword|dir|notdir|suffix|basename|addsuffix|addprefix|join|wildcard|realpath|abspath|if|or|and|foreach|file|call|value|eval|origin|flavor|error|warning|info|shell|let|intcmp)\s");
+        result.keyword("variable", r"\$[({][A-Za-z0-9_.-]+(?::[^)}]*)?[)}]|\$\([@<^?*%+][DF]\)|\$[@<^?*%+|$]");
+        // Recipe prefixes that silence, ignore errors of or always run a command
+        result.keyword("operator", r"^\t([@+-]+)");
+        bulk_add(&mut result, "operator", &[r"(::=)", r"(:=)", r"(\?=)", r"(\+=)", r"(!=)", r"(=)", r"(:)", r"(\|)", r"(;)"]);
         result.bounded("string", "\"", "\"", true);
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        result.keyword("boolean", "\\b(true|false)\\b");
-        result.keyword("operator", "=");
-        bulk_add(&mut result, "tag", &["<[A-Za-z0-9_]+>?", "</[A-Za-z0-9_]+>", "</", "/>", ">", "<!", "<"]);
-        bulk_add(&mut result, "attribute", &[r"([A-Za-z0-9-]+)="]);
+        result.bounded("string", "'", "'", false);
         result
     })
 }
 
-fn nushell_syntax_highlighter() -> &'static Highlighter {
+
+/// The rules for JSX elements, registered before the rules of the language they're embedded in
+fn add_jsx_rules(result: &mut Highlighter) {
+    // A tag only opens where an expression can start, so comparisons and generics are left alone
+    result.keyword("tag", r"(?:^|[\s(=>,?:&|{}\[])(</?>|</?[A-Za-z][A-Za-z0-9.:_-]*>?)");
+    result.keyword("tag", r"(/>)");
+    result.keyword("attribute", r"\s([A-Za-z_][A-Za-z0-9_:-]*)=[{\x22']");
+}
+
+fn jsx_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("string", "\"", "\"", true);
-        result.bounded("string", "'", "'", true);
-        result.keyword("comment", "(#.*)$");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(%)", r"(\+=)",
-            r"(\-=)", r"(\*=)", r"(\\=)", r"(\{)", r"(\})", r"(==)", r"(!=)", r"(>=)",
-            r"(<=)", r"(<)", r"(>)", r"(\$)", r"(\.\.)", r"(<<)", r"(>>)", r"(\&\&)", 
-            r"(\|\|)", r"(!)\S", r"(\.)", r"(&)", r"(\|)"
-        ]);
-        add_keywords(&mut result, &[
-            "alias", "append", "build-string", "cd", "config", "cp", "debug", "def", "do",
-            "each", "echo", "else", "empty?", "enter", "every", "exit", "export", "filter",
-            "first", "flatten", "for", "format", "from", "get", "group-by", "help", "history",
-            "if", "insert", "keep", "last", "let", "ls", "math", "merge", "metadata", "move",
-            "mut", "open", "parse", "pivot", "plugin", "post", "pre", "prune", "reduce", "reject",
-            "rename", "rm", "save", "select", "skip", "sort-by", "source", "split", "str", "table",
-            "to", "touch", "uniq", "update", "url", "use", "where", "with-env", "drop", "complete",
-            "load-env", "exec", "mkdir", "du", "glob", "mktemp", "mv", "ps", "run-external", "start",
-            "sys", "uname", "watch", "which", "nu-check", "nu-highlight", "print", "decode", "char",
-            "encode", "detect", "url", "dexit", "shells", "random", "gstat", "ansi", "input",
-            "keybindings", "kill", "sleep", "term", "ulimit", "whoami", "is-terminal", "clear", "path",
-            "http", "query", "port", "tutor", "math", "polars", "hash", "cal", "generate", "seq",
-            "columns", "collect", "compact", "flatten", "group", "headers", "transpose", "enumerate",
-            "catch", "try", "find", "upsert", "string", "pattern", "fill",
-        ]);
+        add_jsx_rules(&mut result);
+        add_js_rules(&mut result);
         result
     })
 }
 
-fn tex_syntax_highlighter() -> &'static Highlighter {
+fn tsx_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.bounded("string", "\\$", "\\$", true);
-        result.keyword("comment", r"([^\\]%.*)$");
-        result.keyword("comment", r"^(%.*)$");
-        result.keyword("digit", "\\b(\\d+.\\d+|\\d+)");
-        bulk_add(&mut result, "keyword", &[
-            r"\\addbibresource\b", r"\\author\b", r"\\begin\b", r"\\caption\b",
-            r"\\centering\b", r"\\date\b", r"\\end\b", r"\\geometry\b", r"\\hline\b",
-            r"\\includegraphics\b", r"\\item\b", r"\\label\b", r"\\maketitle\b", r"\\paragraph\b",
-            r"\\parindent\b", r"\\parskip\b", r"\\printbibliography\b", r"\\section\b", r"\\setlength\b",
-            r"\\subsection\b", r"\\tableofcontents\b", r"\\textbf\b", r"\\textit\b", r"\\texttt\b",
-            r"\\title\b", r"\\today\b", r"\\underline\b", r"\\usepackage\b", r"\\ref\b",
-            r"\\cite\b", r"\\pageref\b", r"\\include\b", r"\\input\b", r"\\bibliographystyle\b",
-            r"\\newcommand\b", r"\\renewcommand\b", r"\\renewenvironment\b", r"\\newenvironment\b", 
-            r"\\footnote\b", r"\\hline\b", r"\\vspace\b", r"\\hspace\b", r"\\newline\b", r"\\frac\b", 
-            r"\\textbackslash\b", r"\\documentclass\b",
-        ]);
-        bulk_add(&mut result, "operator", &[
-            r"(=)", r"(\+)", r"(\-)", r"(\*)", r"(\s/\s)", r"\s(//)\s", r"(#)", r"(\+=)", r"(\-=)", 
-            r"(\*=)", r"(\\=)", r"(\^)", r"(%)", r"(==)", r"(!=)", r"(>=)", r"(<=)", r"(<)", r"(>)",
-            r"(\$)", r"(\.\.)", r"(<<)", r"(>>)", r"(\&\&)", r"(\|\|)", r"(!)\S", r"(&)", r"(\|)",
-        ]);
+        add_jsx_rules(&mut result);
+        add_ts_rules(&mut result);
         result
     })
 }
 
-fn diff_syntax_highlighter() -> &'static Highlighter {
+fn vue_syntax_highlighter() -> &'static Highlighter {
     static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
     HIGHLIGHTER.get_or_init(|| {
         let mut result = Highlighter::new(4);
-        result.keyword("insertion", r"^(\+(?:[^+]|$).*)$");
-        result.keyword("deletion", r"^\-(?:[^-]|$).*$");
-        result.keyword("comment", r"@@.*@@");
+        result.keyword("macro", r"\{\{|\}\}");
+        result.keyword("attribute", r"\s((?:v-[a-z-]+(?::[A-Za-z0-9_-]+|:\[[^\]]*\])?|[:@#][A-Za-z0-9_-]+)(?:\.[a-z]+)*)(?:=|\s|>|$)");
+        add_sfc_embeds(&mut result);
+        add_html_rules(&mut result);
+        result
+    })
+}
+
+fn svelte_syntax_highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| {
+        let mut result = Highlighter::new(4);
+        // Logic blocks such as {#if}, {:else} and {/each}, and special tags such as {@html}
+        result.keyword("keyword", r"\{[#:/@](?:else\s+if|[a-z]+)");
+        result.keyword("macro", r"\{|\}");
+        result.keyword("attribute", r"\s((?:on|bind|class|style|use|transition|in|out|animate|let):[A-Za-z0-9_|-]+)");
+        add_sfc_embeds(&mut result);
+        add_html_rules(&mut result);
         result
     })
 }
+
+/// Script and style blocks in single-file components that use another language via lang="..."
+fn add_sfc_embeds(result: &mut Highlighter) {
+    result.embed(r#"<script\b[^>]*\blang=["']ts["'][^>]*>"#, "</script>", ts_syntax_highlighter());
+    result.embed(r#"<style\b[^>]*\blang=["']scss["'][^>]*>"#, "</style>", scss_syntax_highlighter());
+}
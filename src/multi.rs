@@ -0,0 +1,124 @@
+//! Highlighting a single document with different languages in different places.
+//!
+//! This is useful when the boundaries of regions are already known,
+//! for example cells in a notebook or blocks in a literate program.
+
+use crate::{Highlighter, TokOpt};
+use std::ops::Range;
+
+/// Highlights a document made up of regions (ranges of lines), where each region
+/// has its own [Highlighter]. Lines that don't fall within a region are plain text.
+///
+/// Line numbers given to a MultiHighlighter are line numbers within the whole document,
+/// they are translated to line numbers within the relevant region for you.
+#[derive(Debug, Clone)]
+pub struct MultiHighlighter {
+    /// The regions of the document, in order, each with the lines it covers and its highlighter
+    pub regions: Vec<(Range<usize>, Highlighter)>,
+    /// How many spaces a tab character should be (for lines outside of any region)
+    pub tab_width: usize,
+}
+
+impl MultiHighlighter {
+    /// Creates a new multi-highlighter with no regions
+    pub fn new(tab_width: usize) -> Self {
+        Self { regions: vec![], tab_width }
+    }
+
+    /// Assign a highlighter to a range of lines, replacing any regions that overlap it.
+    /// Call run afterwards to highlight the document.
+    pub fn add_region(&mut self, lines: Range<usize>, highlighter: Highlighter) {
+        self.regions.retain(|(r, _)| r.end <= lines.start || lines.end <= r.start);
+        let idx = self.regions.partition_point(|(r, _)| r.start < lines.start);
+        self.regions.insert(idx, (lines, highlighter));
+    }
+
+    /// Find the index of the region that contains a line
+    fn region_idx(&self, y: usize) -> Option<usize> {
+        self.regions.iter().position(|(r, _)| r.contains(&y))
+    }
+
+    /// Find the highlighter used for a line, if it falls within a region
+    pub fn region_at(&self, y: usize) -> Option<&Highlighter> {
+        self.region_idx(y).map(|idx| &self.regions[idx].1)
+    }
+
+    /// Do an initial pass on the lines of the document, highlighting every region
+    pub fn run(&mut self, lines: &[String]) {
+        for (range, highlighter) in &mut self.regions {
+            let end = range.end.min(lines.len());
+            let start = range.start.min(end);
+            highlighter.run(&lines[start..end]);
+        }
+    }
+
+    /// Retrieve the tokens for a line of the document, see [Highlighter::line]
+    pub fn line<S: AsRef<str>>(&self, y: usize, line: S) -> Vec<TokOpt> {
+        let line = line.as_ref();
+        if let Some(idx) = self.region_idx(y) {
+            let (range, highlighter) = &self.regions[idx];
+            return highlighter.line(y - range.start, line);
+        }
+        if line.is_empty() {
+            return vec![];
+        }
+        vec![TokOpt::None(line.replace('\t', &" ".repeat(self.tab_width)))]
+    }
+
+    /// Whenever a character is deleted or inserted on a line,
+    /// call this function to update any tokens.
+    pub fn edit<S: AsRef<str>>(&mut self, y: usize, line: S) {
+        if let Some(idx) = self.region_idx(y) {
+            let (range, highlighter) = &mut self.regions[idx];
+            highlighter.edit(y - range.start, line);
+        }
+    }
+
+    /// Apply edits to several lines at once, see [Highlighter::edit_batch]
+    pub fn edit_batch<S: AsRef<str>>(&mut self, edits: &[(usize, S)]) {
+        for (range, highlighter) in &mut self.regions {
+            let edits: Vec<(usize, &str)> = edits.iter()
+                .filter(|(y, _)| range.contains(y))
+                .map(|(y, line)| (y - range.start, line.as_ref()))
+                .collect();
+            if !edits.is_empty() {
+                highlighter.edit_batch(&edits);
+            }
+        }
+    }
+
+    /// Whenever a line is inserted into the document,
+    /// call this function to update any tokens.
+    ///
+    /// The line joins the region it is inserted into, a line inserted directly after the
+    /// last line of a region (and not into another region) extends that region.
+    pub fn insert_line<S: AsRef<str>>(&mut self, y: usize, line: S) {
+        let target = self.region_idx(y)
+            .or_else(|| self.regions.iter().position(|(r, _)| r.end == y && !r.is_empty()));
+        for (idx, (range, highlighter)) in self.regions.iter_mut().enumerate() {
+            if Some(idx) == target {
+                highlighter.insert_line(y - range.start, line.as_ref());
+                range.end += 1;
+            } else if range.start >= y {
+                range.start += 1;
+                range.end += 1;
+            }
+        }
+    }
+
+    /// Whenever a line is removed from a document,
+    /// call this function to update any tokens.
+    /// Regions that no longer contain any lines are removed.
+    pub fn remove_line(&mut self, y: usize) {
+        for (range, highlighter) in &mut self.regions {
+            if range.contains(&y) {
+                highlighter.remove_line(y - range.start);
+                range.end -= 1;
+            } else if range.start > y {
+                range.start -= 1;
+                range.end -= 1;
+            }
+        }
+        self.regions.retain(|(r, _)| !r.is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! Node.js bindings for synoptic, enabled with the `node` feature.
+//!
+//! This exposes [Highlighter](crate::Highlighter) and [from_extension](crate::from_extension)
+//! through N-API (using napi-rs), with tokens returned as plain JavaScript objects.
+//! Running and editing large documents can also be done on a worker thread, with
+//! `runAsync` and `editAsync` returning promises so the event loop isn't blocked.
+//! While one of these is running, the other methods of the highlighter throw an error
+//! rather than waiting for it, so wait for the promise before reading tokens.
+//! To build a loadable addon, build synoptic as a `cdylib` with this feature enabled,
+//! e.g. `cargo rustc --release --features node --crate-type cdylib`,
+//! and rename the library that is produced to `synoptic.node`.
+
+use crate::{Highlighter, SyntaxError, TokOpt};
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Task};
+use napi_derive::napi;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+
+/// A token produced by the highlighter, kind is null for plain text
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct JsToken {
+    /// The text covered by this token
+    pub text: String,
+    /// The name of the token, or null for plain text
+    pub kind: Option<String>,
+}
+
+impl From<TokOpt> for JsToken {
+    fn from(tok: TokOpt) -> Self {
+        match tok {
+            TokOpt::Some(text, kind) => Self { text, kind: Some(kind) },
+            TokOpt::None(text) => Self { text, kind: None },
+        }
+    }
+}
+
+/// Invalid rules are thrown as JavaScript errors
+fn to_js_err(err: SyntaxError) -> napi::Error {
+    napi::Error::from_reason(err.to_string())
+}
+
+/// A highlighter for a single document.
+/// The highlighter is shared with any work on it that is running on a worker thread,
+/// and can't be used until that work is done
+#[napi(js_name = "Highlighter")]
+#[derive(Debug)]
+pub struct JsHighlighter {
+    inner: Arc<Mutex<Highlighter>>,
+}
+
+impl Clone for JsHighlighter {
+    fn clone(&self) -> Self {
+        Self::from(lock(&self.inner).clone())
+    }
+}
+
+impl From<Highlighter> for JsHighlighter {
+    fn from(inner: Highlighter) -> Self {
+        Self { inner: Arc::new(Mutex::new(inner)) }
+    }
+}
+
+impl JsHighlighter {
+    /// Get hold of the highlighter, without blocking the event loop if a task is using it
+    fn lock(&self) -> napi::Result<MutexGuard<'_, Highlighter>> {
+        match self.inner.try_lock() {
+            Ok(highlighter) => Ok(highlighter),
+            Err(TryLockError::Poisoned(err)) => Ok(err.into_inner()),
+            Err(TryLockError::WouldBlock) => Err(napi::Error::from_reason(
+                "the highlighter is busy with a task running on a worker thread",
+            )),
+        }
+    }
+}
+
+fn lock(highlighter: &Mutex<Highlighter>) -> MutexGuard<'_, Highlighter> {
+    highlighter.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Work to do on a highlighter
+#[derive(Debug)]
+enum Job {
+    Run(Vec<String>),
+    Edit(usize, String),
+}
+
+/// Work on a highlighter that is done on a worker thread, resolving once it is finished
+#[derive(Debug)]
+pub struct HighlightTask {
+    inner: Arc<Mutex<Highlighter>>,
+    job: Job,
+}
+
+impl Task for HighlightTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> napi::Result<()> {
+        let mut highlighter = lock(&self.inner);
+        match &self.job {
+            Job::Run(lines) => highlighter.run(lines),
+            Job::Edit(y, line) => highlighter.edit(*y, line),
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, _env: Env, _output: ()) -> napi::Result<()> {
+        Ok(())
+    }
+}
+
+#[napi]
+impl JsHighlighter {
+    /// Creates a new highlighter
+    #[napi(constructor)]
+    pub fn new(tab_width: u32) -> Self {
+        Self::from(Highlighter::new(tab_width as usize))
+    }
+
+    /// Register a new keyword token
+    #[napi]
+    pub fn keyword(&mut self, name: String, exp: String) -> napi::Result<()> {
+        self.lock()?.try_keyword(name, &exp).map_err(to_js_err)
+    }
+
+    /// Register a new bounded token
+    #[napi]
+    pub fn bounded(&mut self, name: String, start: String, end: String, escapable: bool) -> napi::Result<()> {
+        self.lock()?.try_bounded(name, start, end, escapable).map_err(to_js_err)
+    }
+
+    /// Register a new interpolatable bounded token
+    #[napi]
+    pub fn bounded_interp(&mut self, name: String, start: String, end: String, i_start: String, i_end: String, escapable: bool) -> napi::Result<()> {
+        self.lock()?.try_bounded_interp(name, start, end, i_start, i_end, escapable).map_err(to_js_err)
+    }
+
+    /// Do an initial pass on a list of lines
+    #[napi]
+    pub fn run(&mut self, lines: Vec<String>) -> napi::Result<()> {
+        self.lock()?.run(&lines);
+        Ok(())
+    }
+
+    /// Do an initial pass on a list of lines on a worker thread,
+    /// returning a promise that resolves once it is done
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn run_async(&self, lines: Vec<String>) -> AsyncTask<HighlightTask> {
+        AsyncTask::new(HighlightTask { inner: self.inner.clone(), job: Job::Run(lines) })
+    }
+
+    /// Appends a line to the highlighter
+    #[napi]
+    pub fn append(&mut self, line: String) -> napi::Result<()> {
+        self.lock()?.append(&line);
+        Ok(())
+    }
+
+    /// Update the tokens after a line has been edited
+    #[napi]
+    pub fn edit(&mut self, y: u32, line: String) -> napi::Result<()> {
+        self.lock()?.edit(y as usize, &line);
+        Ok(())
+    }
+
+    /// Update the tokens after a line has been edited on a worker thread,
+    /// returning a promise that resolves once it is done
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn edit_async(&self, y: u32, line: String) -> AsyncTask<HighlightTask> {
+        AsyncTask::new(HighlightTask { inner: self.inner.clone(), job: Job::Edit(y as usize, line) })
+    }
+
+    /// Update the tokens after a line has been inserted
+    #[napi]
+    pub fn insert_line(&mut self, y: u32, line: String) -> napi::Result<()> {
+        self.lock()?.insert_line(y as usize, &line);
+        Ok(())
+    }
+
+    /// Update the tokens after a line has been removed
+    #[napi]
+    pub fn remove_line(&mut self, y: u32) -> napi::Result<()> {
+        self.lock()?.remove_line(y as usize);
+        Ok(())
+    }
+
+    /// Retrieve the tokens for a line
+    #[napi]
+    pub fn line(&self, y: u32, line: String) -> napi::Result<Vec<JsToken>> {
+        Ok(self.lock()?.line(y as usize, &line).into_iter().map(JsToken::from).collect())
+    }
+}
+
+/// Obtain a built-in highlighter based on a file extension
+#[napi]
+pub fn from_extension(ext: String, tab_width: u32) -> Option<JsHighlighter> {
+    crate::from_extension(&ext, tab_width as usize).map(JsHighlighter::from)
+}
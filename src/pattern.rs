@@ -0,0 +1,125 @@
+//! A small builder for patterns, as an alternative to writing regular expressions by hand.
+//!
+//! Patterns compile down to regular expressions, with any literal text escaped for you,
+//! which avoids common mistakes such as forgetting to escape a `.` or `*`.
+//!
+//! ```
+//! # use synoptic::{Highlighter, Pat};
+//! let mut h = Highlighter::new(4);
+//! // Highlight the name of a function that is being called
+//! h.keyword_pat("function", &Pat::ident().capture().then(Pat::whitespace()).then("("));
+//! // Highlight some keywords
+//! h.keyword_pat("keyword", &Pat::words(["fn", "let", "pub"]));
+//! // Highlight multiline comments
+//! h.bounded_pat("comment", &Pat::literal("/*"), &Pat::literal("*/"), false);
+//! ```
+
+use std::fmt;
+
+/// A pattern that compiles to a regular expression, see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pat(String);
+
+impl Pat {
+    /// Matches some text exactly
+    pub fn literal<S: AsRef<str>>(text: S) -> Self {
+        Self(regex::escape(text.as_ref()))
+    }
+
+    /// Matches some text exactly, but only as a whole word
+    pub fn word<S: AsRef<str>>(text: S) -> Self {
+        Self(format!(r"\b{}\b", regex::escape(text.as_ref())))
+    }
+
+    /// Matches any one of a list of pieces of text exactly
+    pub fn any_of<I: IntoIterator<Item = S>, S: AsRef<str>>(items: I) -> Self {
+        let mut items: Vec<String> = items.into_iter().map(|i| regex::escape(i.as_ref())).collect();
+        // Try longer items first, so that e.g. "==" is preferred over "="
+        items.sort_by_key(|i| std::cmp::Reverse(i.len()));
+        Self(format!("(?:{})", items.join("|")))
+    }
+
+    /// Matches any one of a list of words, but only as whole words
+    pub fn words<I: IntoIterator<Item = S>, S: AsRef<str>>(items: I) -> Self {
+        Self(format!(r"\b{}\b", Self::any_of(items).0))
+    }
+
+    /// Matches an identifier, such as a variable or function name
+    pub fn ident() -> Self {
+        Self("[A-Za-z_][A-Za-z0-9_]*".to_string())
+    }
+
+    /// Matches a whole or decimal number
+    pub fn number() -> Self {
+        Self(r"\b\d+(?:\.\d+)?\b".to_string())
+    }
+
+    /// Matches any amount of whitespace (including none at all)
+    pub fn whitespace() -> Self {
+        Self(r"\s*".to_string())
+    }
+
+    /// Matches everything up until the end of the line
+    pub fn rest_of_line() -> Self {
+        Self(".*$".to_string())
+    }
+
+    /// Matches only at the start of a line
+    pub fn line_start() -> Self {
+        Self("^".to_string())
+    }
+
+    /// Matches only at the end of a line
+    pub fn line_end() -> Self {
+        Self("$".to_string())
+    }
+
+    /// Matches this pattern followed by another one
+    pub fn then<P: Into<Pat>>(self, next: P) -> Self {
+        Self(self.0 + &next.into().0)
+    }
+
+    /// Matches either this pattern or another one
+    pub fn or<P: Into<Pat>>(self, other: P) -> Self {
+        Self(format!("(?:{}|{})", self.0, other.into().0))
+    }
+
+    /// Matches this pattern, or nothing at all
+    pub fn optional(self) -> Self {
+        Self(format!("(?:{})?", self.0))
+    }
+
+    /// Matches this pattern one or more times
+    pub fn repeated(self) -> Self {
+        Self(format!("(?:{})+", self.0))
+    }
+
+    /// Only highlight the part of the match covered by this pattern.
+    /// When a keyword has captures, the last capture is what gets highlighted
+    pub fn capture(self) -> Self {
+        Self(format!("({})", self.0))
+    }
+
+    /// Get the regular expression that this pattern compiles to
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Pat {
+    fn from(text: &str) -> Self {
+        Self::literal(text)
+    }
+}
+
+impl From<Pat> for String {
+    fn from(pat: Pat) -> Self {
+        pat.0
+    }
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,114 @@
+//! Python bindings for synoptic, enabled with the `python` feature.
+//!
+//! This exposes [Highlighter](crate::Highlighter), [from_extension](crate::from_extension)
+//! and the tokens produced by the highlighter as Python classes.
+//! To build an importable extension module, run `maturin build --release` from the root
+//! of the repository, which builds synoptic as a `cdylib` with this feature enabled.
+
+use crate::{Highlighter, SyntaxError, TokOpt};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A token produced by the highlighter, kind is None for plain text
+#[pyclass(name = "Token", get_all)]
+#[derive(Debug, Clone)]
+pub struct PyToken {
+    /// The text covered by this token
+    pub text: String,
+    /// The name of the token, or None for plain text
+    pub kind: Option<String>,
+}
+
+impl From<TokOpt> for PyToken {
+    fn from(tok: TokOpt) -> Self {
+        match tok {
+            TokOpt::Some(text, kind) => Self { text, kind: Some(kind) },
+            TokOpt::None(text) => Self { text, kind: None },
+        }
+    }
+}
+
+#[pymethods]
+impl PyToken {
+    fn __repr__(&self) -> String {
+        format!("Token({:?}, {:?})", self.text, self.kind)
+    }
+}
+
+/// Invalid rules are raised as a ValueError
+fn to_py_err(err: SyntaxError) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// A highlighter for a single document
+#[pyclass(name = "Highlighter")]
+#[derive(Debug, Clone)]
+pub struct PyHighlighter {
+    inner: Highlighter,
+}
+
+#[pymethods]
+impl PyHighlighter {
+    #[new]
+    fn new(tab_width: usize) -> Self {
+        Self { inner: Highlighter::new(tab_width) }
+    }
+
+    fn keyword(&mut self, name: &str, exp: &str) -> PyResult<()> {
+        self.inner.try_keyword(name, exp).map_err(to_py_err)
+    }
+
+    fn bounded(&mut self, name: &str, start: &str, end: &str, escapable: bool) -> PyResult<()> {
+        self.inner.try_bounded(name, start, end, escapable).map_err(to_py_err)
+    }
+
+    fn bounded_interp(&mut self, name: &str, start: &str, end: &str, i_start: &str, i_end: &str, escapable: bool) -> PyResult<()> {
+        self.inner.try_bounded_interp(name, start, end, i_start, i_end, escapable).map_err(to_py_err)
+    }
+
+    fn run(&mut self, lines: Vec<String>) {
+        self.inner.run(&lines);
+    }
+
+    fn append(&mut self, line: &str) {
+        self.inner.append(line);
+    }
+
+    fn edit(&mut self, y: usize, line: &str) {
+        self.inner.edit(y, line);
+    }
+
+    fn edit_batch(&mut self, edits: Vec<(usize, String)>) {
+        self.inner.edit_batch(&edits);
+    }
+
+    fn insert_line(&mut self, y: usize, line: &str) {
+        self.inner.insert_line(y, line);
+    }
+
+    fn remove_line(&mut self, y: usize) {
+        self.inner.remove_line(y);
+    }
+
+    fn replace_lines(&mut self, start: usize, end: usize, lines: Vec<String>) {
+        self.inner.replace_lines(start..end, &lines);
+    }
+
+    fn line(&self, y: usize, line: &str) -> Vec<PyToken> {
+        self.inner.line(y, line).into_iter().map(PyToken::from).collect()
+    }
+}
+
+/// Obtain a built-in highlighter based on a file extension
+#[pyfunction]
+fn from_extension(ext: &str, tab_width: usize) -> Option<PyHighlighter> {
+    crate::from_extension(ext, tab_width).map(|inner| PyHighlighter { inner })
+}
+
+#[pymodule]
+fn synoptic(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyHighlighter>()?;
+    m.add_class::<PyToken>()?;
+    m.add_function(wrap_pyfunction!(from_extension, m)?)?;
+    Ok(())
+}
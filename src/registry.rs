@@ -0,0 +1,450 @@
+//! Looking up syntax highlighters for files, with languages that can be added at runtime.
+//!
+//! A [Registry] comes with all of the provided languages already registered,
+//! and an editor (or its plugins) can register their own on top of them:
+//!
+//! ```rust
+//! use synoptic::{Highlighter, LanguageInfo, Registry};
+//!
+//! let mut jsonnet = Highlighter::new(4);
+//! jsonnet.keyword("comment", "(//.*)$");
+//! let mut info = LanguageInfo::new("jsonnet", "Jsonnet");
+//! info.extensions = vec!["jsonnet".to_string(), "libsonnet".to_string()];
+//! info.filenames = vec!["jsonnetfile.json".to_string()];
+//!
+//! let mut registry = Registry::new();
+//! registry.register(info, jsonnet);
+//!
+//! let (_, info) = registry.from_filename("lib/utils.libsonnet", 4).unwrap();
+//! assert_eq!(info.name, "Jsonnet");
+//! let (_, info) = registry.from_filename("jsonnetfile.json", 4).unwrap();
+//! assert_eq!(info.id, "jsonnet");
+//! let (_, info) = registry.from_extension("rs", 4).unwrap();
+//! assert_eq!(info.id, "rust");
+//! ```
+
+use crate::{compile, Highlighter, LazyRegex, Regex, SyntaxError, BUILTINS};
+use std::sync::OnceLock;
+#[cfg(feature = "watch")]
+use crate::definition::{LoadError, SyntaxFormat};
+#[cfg(feature = "watch")]
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+#[cfg(feature = "watch")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "watch")]
+use std::sync::mpsc;
+
+/// Information about a language that a highlighter was registered for,
+/// such as the name an editor might show in its status bar
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LanguageInfo {
+    /// A short identifier for the language, e.g. `"rust"` or `"cpp"`
+    pub id: String,
+    /// The name of the language, for showing to the user, e.g. `"Rust"` or `"C++"`
+    pub name: String,
+    /// The file extensions (in lower case, without the dot) used by the language
+    pub extensions: Vec<String>,
+    /// File names that are recognised as the language regardless of their extension.
+    /// A name ending in `*` matches any file name starting with what comes before it,
+    /// and a name containing `/` matches the end of the path (e.g. `.git/config`)
+    pub filenames: Vec<String>,
+    /// Regular expressions that recognise the language from the first line of a file
+    pub first_lines: Vec<String>,
+}
+
+impl LanguageInfo {
+    /// Creates information about a language with no extensions, file names or first lines
+    pub fn new<S: Into<String>>(id: S, name: S) -> Self {
+        Self { id: id.into(), name: name.into(), ..Self::default() }
+    }
+
+    /// Work out if a file name (or path, using `/` as the separator) belongs to this language
+    fn matches_filename(&self, path: &str, name: &str) -> bool {
+        self.filenames.iter().any(|pattern| {
+            if let Some(prefix) = pattern.strip_suffix('*') {
+                name.starts_with(prefix)
+            } else if pattern.contains('/') {
+                path == pattern || path.ends_with(&format!("/{pattern}"))
+            } else {
+                name == pattern
+            }
+        })
+    }
+}
+
+/// An error returned when no registered language is known for a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetectError {
+    /// No language uses this file extension
+    UnknownExtension(String),
+    /// The language couldn't be recognised from the contents of the file
+    Undetected,
+}
+
+impl std::fmt::Display for DetectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnknownExtension(ext) => write!(f, "no known language uses the extension {ext:?}"),
+            Self::Undetected => write!(f, "couldn't recognise the language from the contents of the file"),
+        }
+    }
+}
+
+impl std::error::Error for DetectError {}
+
+/// Where the highlighter for a registered language comes from
+#[derive(Debug, Clone)]
+enum Source {
+    /// A provided highlighter, which is only built when it is first needed
+    Builtin(fn() -> &'static Highlighter),
+    /// A highlighter that was registered at runtime
+    Custom(Box<Highlighter>),
+}
+
+/// A registered language
+#[derive(Debug, Clone)]
+struct Language {
+    info: LanguageInfo,
+    first_lines: Vec<LazyRegex>,
+    source: Source,
+}
+
+impl Language {
+    /// Create a highlighter for a new document in this language
+    fn load(&self, tab_width: usize) -> (Highlighter, LanguageInfo) {
+        let mut result = match &self.source {
+            Source::Builtin(highlighter) => highlighter().new_document(),
+            Source::Custom(highlighter) => highlighter.new_document(),
+        };
+        result.tab_width = tab_width;
+        (result, self.info.clone())
+    }
+}
+
+/// A collection of languages, used to pick a highlighter for a file.
+///
+/// When more than one language claims a file, the one registered last wins,
+/// so languages registered at runtime take priority over the provided ones.
+/// The highlighters handed out share their grammar with the registered one
+/// (see [Highlighter::new_document]), so looking up a language is cheap.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    languages: Vec<Language>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// Creates a registry with all of the provided languages registered
+    pub fn new() -> Self {
+        let languages = BUILTINS
+            .iter()
+            .map(|lang| {
+                let first_lines: Vec<String> = lang.interpreters.iter()
+                    .map(|program| shebang(program))
+                    .chain(lang.first_lines.iter().map(|exp| exp.to_string()))
+                    .collect();
+                Language {
+                    first_lines: first_lines.iter()
+                        .map(|exp| LazyRegex { source: exp.clone(), compiled: OnceLock::new() })
+                        .collect(),
+                    info: LanguageInfo {
+                        id: lang.id.to_string(),
+                        name: lang.name.to_string(),
+                        extensions: lang.extensions.iter().map(|ext| ext.to_string()).collect(),
+                        filenames: lang.filenames.iter().map(|name| name.to_string()).collect(),
+                        first_lines,
+                    },
+                    source: Source::Builtin(lang.highlighter),
+                }
+            })
+            .collect();
+        Self { languages }
+    }
+
+    /// Creates a registry with no languages registered
+    pub fn empty() -> Self {
+        Self { languages: vec![] }
+    }
+
+    /// Register a language, replacing any language already registered with the same id.
+    /// This will panic if one of the first line patterns is an invalid regex.
+    pub fn register(&mut self, info: LanguageInfo, highlighter: Highlighter) {
+        self.try_register(info, highlighter).expect("Invalid regex!");
+    }
+
+    /// Register a language, like [Registry::register],
+    /// but returns an error instead of panicking if a first line pattern is an invalid regex
+    pub fn try_register(&mut self, info: LanguageInfo, highlighter: Highlighter) -> Result<(), SyntaxError> {
+        let first_lines = info.first_lines.iter().map(|exp| compile(exp)).collect::<Result<_, _>>()?;
+        self.languages.retain(|lang| lang.info.id != info.id);
+        self.languages.push(Language { info, first_lines, source: Source::Custom(Box::new(highlighter)) });
+        Ok(())
+    }
+
+    /// The languages that are registered, in the order they were registered
+    pub fn languages(&self) -> impl Iterator<Item = &LanguageInfo> {
+        self.languages.iter().map(|lang| &lang.info)
+    }
+
+    /// Obtain a syntax highlighter for a language from its id
+    pub fn get(&self, id: &str, tab_width: usize) -> Option<(Highlighter, LanguageInfo)> {
+        self.find(|lang| lang.info.id == id).map(|lang| lang.load(tab_width))
+    }
+
+    /// Obtain a syntax highlighter based on a file extension (case insensitive)
+    pub fn from_extension(&self, ext: &str, tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+        let ext = ext.to_lowercase();
+        self.find(|lang| lang.info.extensions.contains(&ext))
+            .map(|lang| lang.load(tab_width))
+            .ok_or(DetectError::UnknownExtension(ext))
+    }
+
+    /// Obtain a syntax highlighter based on a file extension, using the contents of the file
+    /// to choose between languages that share the extension (e.g. `.m` for MATLAB and
+    /// Objective-C, `.pl` for Prolog and Perl, `.h` for C, C++ and Objective-C, and `.s` for
+    /// x86, RISC-V and ARM assembly).
+    /// Only the first few dozen lines need to be given.
+    ///
+    /// ```rust
+    /// use synoptic::Registry;
+    ///
+    /// let registry = Registry::new();
+    /// let id = |ext: &str, lines: &[&str]| {
+    ///     let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    ///     registry.from_extension_with_content(ext, &lines, 4).unwrap().1.id
+    /// };
+    /// assert_eq!(id("m", &["#import <Foundation/Foundation.h>", "@interface Foo : NSObject"]), "objc");
+    /// assert_eq!(id("m", &["function y = square(x)", "  y = x^2;", "end"]), "matlab");
+    /// assert_eq!(id("pl", &["use strict;", "my $x = 1;"]), "perl");
+    /// assert_eq!(id("pl", &["parent(tom, bob)."]), "prolog");
+    /// assert_eq!(id("h", &["namespace foo {", "class Bar {};", "}"]), "cpp");
+    /// assert_eq!(id("s", &["_start:", "    li a0, 42", "    ecall"]), "riscv");
+    /// assert_eq!(id("s", &["main:", "    stp x29, x30, [sp, #-16]!", "    ldr x1, =msg"]), "arm");
+    /// assert_eq!(id("s", &["main:", "    movq %rsp, %rbp", "    ret"]), "asm");
+    /// ```
+    pub fn from_extension_with_content(&self, ext: &str, lines: &[String], tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+        let ext = ext.to_lowercase();
+        let guess = heuristics()
+            .iter()
+            .filter(|(e, _, _)| *e == ext)
+            .find(|(_, _, exp)| lines.iter().any(|line| exp.is_match(line)))
+            .and_then(|(_, id, _)| self.get(id, tab_width));
+        match guess {
+            Some(result) => Ok(result),
+            None => self.from_extension(&ext, tab_width),
+        }
+    }
+
+    /// Obtain a syntax highlighter based on a file name, or a path to a file.
+    /// Falls back to [Registry::from_extension] if no language recognises the whole name.
+    pub fn from_filename(&self, name: &str, tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+        let path = name.replace('\\', "/");
+        let name = path.rsplit('/').next().unwrap_or(&path);
+        match self.find(|lang| lang.info.matches_filename(&path, name)) {
+            Some(lang) => Ok(lang.load(tab_width)),
+            None => {
+                let ext = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+                self.from_extension(ext, tab_width)
+            }
+        }
+    }
+
+    /// Obtain a syntax highlighter based on the first line of a file
+    pub fn from_first_line(&self, line: &str, tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+        self.find(|lang| lang.first_lines.iter().any(|exp| exp.get().is_match(line)))
+            .map(|lang| lang.load(tab_width))
+            .ok_or(DetectError::Undetected)
+    }
+
+    /// Obtain a syntax highlighter based on the first few lines of a file, for files
+    /// without a (known) extension. A vim or emacs modeline naming the language
+    /// (e.g. `# vim: set ft=python:` or `-*- mode: ruby -*-`) is used if there is one,
+    /// otherwise the first line is checked for things like shebangs and `<?php`.
+    ///
+    /// ```rust
+    /// use synoptic::Registry;
+    ///
+    /// let registry = Registry::new();
+    /// let id = |lines: &[&str]| {
+    ///     let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    ///     registry.detect(&lines, 4).ok().map(|(_, info)| info.id)
+    /// };
+    /// assert_eq!(id(&["#!/usr/bin/env python3", "print(1)"]).unwrap(), "python");
+    /// assert_eq!(id(&["#!/bin/bash -e"]).unwrap(), "shell");
+    /// assert_eq!(id(&["<?xml version=\"1.0\"?>"]).unwrap(), "xml");
+    /// assert_eq!(id(&["#!/bin/sh", "# vim: set ts=4 ft=zsh:"]).unwrap(), "zsh");
+    /// assert_eq!(id(&["// -*- mode: c++; tab-width: 4 -*-"]).unwrap(), "cpp");
+    /// assert_eq!(id(&["hello"]), None);
+    /// ```
+    pub fn detect(&self, first_lines: &[String], tab_width: usize) -> Result<(Highlighter, LanguageInfo), DetectError> {
+        let modeline = first_lines.iter().find_map(|line| modeline(line));
+        if let Some(result) = modeline.and_then(|name| self.by_name(&name, tab_width)) {
+            return Ok(result);
+        }
+        match first_lines.first() {
+            Some(line) => self.from_first_line(line, tab_width),
+            None => Err(DetectError::Undetected),
+        }
+    }
+
+    /// Obtain a syntax highlighter from a name given to a language by an editor,
+    /// which can be its id, its display name or one of its extensions
+    fn by_name(&self, name: &str, tab_width: usize) -> Option<(Highlighter, LanguageInfo)> {
+        let name = name.to_lowercase();
+        self.find(|lang| lang.info.id == name || lang.info.name.to_lowercase() == name)
+            .map(|lang| lang.load(tab_width))
+            .or_else(|| self.from_extension(&name, tab_width).ok())
+    }
+
+    /// Find the most recently registered language that satisfies a condition
+    fn find<F: Fn(&Language) -> bool>(&self, condition: F) -> Option<&Language> {
+        self.languages.iter().rev().find(|lang| condition(lang))
+    }
+}
+
+#[cfg(feature = "watch")]
+impl Registry {
+    /// Watch a directory of syntax definition files (see the [definition module](crate::definition))
+    /// so that they can be edited while an editor is running, enabled with the `watch` feature.
+    /// Each file is registered as a language with its file name (without the extension) as the id,
+    /// keeping the information of any language already registered with that id.
+    /// Changes are picked up by [GrammarWatcher::reload], and the first call to it
+    /// registers all of the definitions that are already in the directory.
+    ///
+    /// ```rust
+    /// use synoptic::Registry;
+    ///
+    /// let dir = std::env::temp_dir().join("synoptic-watch-doctest");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("ini.toml"), "[[keyword]]\nname = \"section\"\npattern = '^\\[.*\\]$'\n").unwrap();
+    ///
+    /// let mut registry = Registry::new();
+    /// let mut watcher = Registry::watch(&dir).unwrap();
+    /// let changed = watcher.reload(&mut registry);
+    /// assert_eq!(changed.len(), 1);
+    /// assert_eq!(changed[0].0, "ini");
+    /// assert!(changed[0].1.is_ok());
+    ///
+    /// let (mut highlighter, _) = registry.get("ini", 4).unwrap();
+    /// highlighter.run(&vec!["[core]".to_string()]);
+    /// let tokens = highlighter.line(0, "[core]");
+    /// assert!(matches!(&tokens[..], [synoptic::TokOpt::Some(text, name)] if text == "[core]" && name == "section"));
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn watch<P: AsRef<Path>>(dir: P) -> notify::Result<GrammarWatcher> {
+        let dir = dir.as_ref();
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        let mut pending = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            pending.push(entry?.path());
+        }
+        pending.sort();
+        Ok(GrammarWatcher { _watcher: watcher, events, pending })
+    }
+}
+
+/// Watches a directory of syntax definition files, created by [Registry::watch]
+#[cfg(feature = "watch")]
+pub struct GrammarWatcher {
+    _watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    pending: Vec<PathBuf>,
+}
+
+#[cfg(feature = "watch")]
+impl std::fmt::Debug for GrammarWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("GrammarWatcher").field("pending", &self.pending).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "watch")]
+impl GrammarWatcher {
+    /// Reload the definition files that were created or changed since this was last called,
+    /// and register them in a registry. This doesn't block, so it can be called regularly
+    /// from an editor's event loop. Returns the id of each language that changed
+    /// along with whether it loaded, so documents using those languages can fetch a new
+    /// highlighter and re-run it. A file that fails to load leaves the language as it was.
+    /// Deleting a file doesn't unregister its language.
+    pub fn reload(&mut self, registry: &mut Registry) -> Vec<(String, Result<(), LoadError>)> {
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else { continue };
+            if event.kind.is_create() || event.kind.is_modify() {
+                self.pending.extend(event.paths);
+            }
+        }
+        let mut result: Vec<(String, Result<(), LoadError>)> = vec![];
+        for path in std::mem::take(&mut self.pending) {
+            let is_definition = path.extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(SyntaxFormat::from_extension)
+                .is_some();
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+            if !is_definition || !path.is_file() || result.iter().any(|(done, _)| done == id) {
+                continue;
+            }
+            let loaded = Highlighter::from_file(&path).and_then(|highlighter| {
+                let info = registry.find(|lang| lang.info.id == id)
+                    .map(|lang| lang.info.clone())
+                    .unwrap_or_else(|| LanguageInfo::new(id, id));
+                registry.try_register(info, highlighter).map_err(LoadError::Syntax)
+            });
+            result.push((id.to_string(), loaded));
+        }
+        result
+    }
+}
+
+/// The registry of provided languages, used by [from_extension](crate::from_extension)
+/// and [from_filename](crate::from_filename)
+pub(crate) fn builtins() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::new)
+}
+
+/// Patterns that pick out a language from others sharing its extension, tried in order.
+/// Files that don't match any of these get the language the extension usually maps to.
+fn heuristics() -> &'static [(&'static str, &'static str, Regex)] {
+    static HEURISTICS: OnceLock<Vec<(&str, &str, Regex)>> = OnceLock::new();
+    HEURISTICS.get_or_init(|| {
+        [
+            ("m", "objc", r#"^\s*(?:@(?:interface|implementation|protocol|end|import|property|synthesize)\b|#\s*(?:import|include)\s*[<"])"#),
+            ("pl", "perl", r"^\s*(?:use\s+(?:strict|warnings|v?5)\b|my\s+[$@%]|sub\s+\w+|package\s+[\w:]+\s*;|#!.*\bperl)"),
+            ("h", "objc", r"^\s*@(?:interface|protocol|end)\b"),
+            ("h", "cpp", r"^\s*(?:class\s+\w+\s*[:{]|namespace\b[^;]*$|template\s*<|(?:public|private|protected)\s*:|using\s+namespace\b|#\s*include\s*<\w+>)"),
+            ("s", "riscv", r"^\s*(?:\w+:\s*)?(?:(?:addiw?|andi|ori|xori|sll?iw?|sr[la]iw?|auipc|lui|la|li|mv|l[bhwd]u?|s[bhwd]|beqz|bnez|jalr?)\s+(?:zero|ra|sp|gp|tp|[ast]\d+|f[ast]\d+)\b|e(?:call|break)\b)"),
+            ("s", "arm", r"^\s*(?:\w+:\s*)?(?:(?:ldr[bhd]?|str[bhd]?|ldp|stp|adrp?|mov[kz]?|cbn?z|bl)\s+\[?(?:[xw](?:[12]?\d|30)|r(?:1[0-5]|\d)|sp|lr|xzr|wzr)\b|push\s+\{)"),
+        ]
+        .into_iter()
+        .map(|(ext, id, exp)| (ext, id, Regex::new(exp).unwrap()))
+        .collect()
+    })
+}
+
+/// A regular expression that matches a shebang which runs a program (with any version number)
+fn shebang(program: &str) -> String {
+    let program = regex::escape(program);
+    format!(r"^#!\s*(?:\S*/)?(?:env\s+(?:-\S+\s+)*)?{program}[0-9.]*(?:\s|$)")
+}
+
+/// Find the language named in a vim or emacs modeline
+fn modeline(line: &str) -> Option<String> {
+    static VIM: OnceLock<Regex> = OnceLock::new();
+    static EMACS: OnceLock<Regex> = OnceLock::new();
+    let vim = VIM.get_or_init(|| {
+        Regex::new(r"(?:^|\s)(?:vim?\d*|ex):.*?[\s:](?:ft|filetype|syn|syntax)=([\w+#-]+)").unwrap()
+    });
+    let emacs = EMACS.get_or_init(|| {
+        Regex::new(r"-\*-\s*(?:(?:[^:;]*?;\s*)*mode:\s*([\w+#-]+)|([\w+#-]+)\s*-\*-)").unwrap()
+    });
+    let caps = vim.captures(line).or_else(|| emacs.captures(line))?;
+    let name = caps.iter().skip(1).flatten().next()?.as_str();
+    Some(name.strip_suffix("-mode").unwrap_or(name).to_string())
+}
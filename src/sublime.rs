@@ -0,0 +1,235 @@
+//! Importing Sublime Text's `.sublime-syntax` files, enabled with the `yaml` feature.
+//!
+//! Sublime syntaxes are stack-based, which is more expressive than synoptic's keyword and
+//! bounded token model, so only the common patterns are mapped across:
+//!
+//! - `match` rules reachable from the `main` context (following `include`s) become keywords
+//! - `match` rules that `push` a context become bounded tokens, ending at the first rule in
+//!   that context that pops it. The token is escapable if the context matches backslash escapes,
+//!   and nested if the context pushes itself again
+//! - `{{variables}}` are expanded
+//!
+//! Scopes are mapped onto synoptic's token names (e.g. `constant.numeric.integer` becomes `digit`).
+//! Rules that can't be mapped, or use regex features not supported by the regex crate
+//! (such as lookbehind), are skipped and listed in [SublimeSyntax::skipped].
+
+use crate::definition::LoadError;
+use crate::Highlighter;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+
+/// A syntax imported from a `.sublime-syntax` file
+#[derive(Debug, Clone)]
+pub struct SublimeSyntax {
+    /// The name of the syntax
+    pub name: String,
+    /// The file extensions the syntax is used for
+    pub file_extensions: Vec<String>,
+    /// A highlighter set up with the rules that could be imported
+    pub highlighter: Highlighter,
+    /// The patterns of rules that couldn't be imported
+    pub skipped: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    file_extensions: Vec<String>,
+    #[serde(default)]
+    variables: HashMap<String, String>,
+    contexts: HashMap<String, Vec<Value>>,
+}
+
+/// Import a `.sublime-syntax` file, see the [module documentation](self)
+pub fn import(source: &str, tab_width: usize) -> Result<SublimeSyntax, LoadError> {
+    let file: File = serde_yaml::from_str(source).map_err(LoadError::Yaml)?;
+    if !file.contexts.contains_key("main") {
+        return Err(LoadError::InvalidRule("main".to_string()));
+    }
+    let mut importer = Importer {
+        file: &file,
+        result: SublimeSyntax {
+            name: file.name.clone(),
+            file_extensions: file.file_extensions.clone(),
+            highlighter: Highlighter::new(tab_width),
+            skipped: vec![],
+        },
+    };
+    let mut visited = HashSet::new();
+    importer.context("main", &mut visited);
+    Ok(importer.result)
+}
+
+struct Importer<'a> {
+    file: &'a File,
+    result: SublimeSyntax,
+}
+
+impl Importer<'_> {
+    /// Import the rules of a context, and any contexts it includes
+    fn context(&mut self, name: &str, visited: &mut HashSet<String>) {
+        if !visited.insert(name.to_string()) { return; }
+        let Some(rules) = self.file.contexts.get(name) else { return };
+        for rule in rules.iter().filter_map(Value::as_mapping) {
+            if let Some(include) = rule.get("include").and_then(Value::as_str) {
+                self.context(include, visited);
+            } else if let Some(exp) = rule.get("match").and_then(Value::as_str) {
+                self.rule(rule, &self.expand(exp));
+            }
+        }
+    }
+
+    /// Import a single match rule
+    fn rule(&mut self, rule: &Mapping, exp: &str) {
+        let h = &mut self.result.highlighter;
+        let ok = if let Some(push) = rule.get("push") {
+            self.bounded(rule, push, exp)
+        } else if rule.contains_key("pop") || rule.contains_key("set") || rule.contains_key("embed") {
+            false
+        } else {
+            match rule_scope(rule, true) {
+                Some(scope) => h.try_keyword(token_name(&scope), &uncapture(exp)).is_ok(),
+                None => match rule_scope(rule, false) {
+                    Some(scope) => h.try_keyword(token_name(&scope), exp).is_ok(),
+                    None => true,
+                },
+            }
+        };
+        if !ok { self.result.skipped.push(exp.to_string()); }
+    }
+
+    /// Import a match rule that pushes a context as a bounded token
+    fn bounded(&mut self, rule: &Mapping, push: &Value, start: &str) -> bool {
+        // The pushed context can be given by name or written inline
+        let (name, rules) = match push {
+            Value::String(name) => match self.file.contexts.get(name) {
+                Some(rules) => (Some(name.as_str()), rules.as_slice()),
+                None => return false,
+            },
+            Value::Sequence(rules) => (None, rules.as_slice()),
+            _ => return false,
+        };
+        let rules: Vec<&Mapping> = rules.iter().filter_map(Value::as_mapping).collect();
+        let scope = rules.iter()
+            .find_map(|r| r.get("meta_scope").or_else(|| r.get("meta_content_scope")))
+            .and_then(Value::as_str)
+            .map(|s| s.split_whitespace().next().unwrap_or_default().to_string())
+            .or_else(|| rule_scope(rule, true));
+        let end = rules.iter()
+            .find(|r| r.get("pop").is_some_and(|p| p.as_bool().unwrap_or(true)))
+            .and_then(|r| r.get("match"))
+            .and_then(Value::as_str);
+        let (Some(scope), Some(end)) = (scope, end) else { return false };
+        let end = self.expand(end);
+        let escapable = rules.iter()
+            .filter_map(|r| r.get("match").and_then(Value::as_str))
+            .any(|m| m.starts_with(r"\\"));
+        let nested = name.is_some() && rules.iter()
+            .any(|r| r.get("push").and_then(Value::as_str) == name);
+        let (start, end) = (uncapture(start), uncapture(&end));
+        let h = &mut self.result.highlighter;
+        let name = token_name(&scope);
+        if nested {
+            h.try_bounded_nested(name, &start, &end, escapable).is_ok()
+        } else {
+            h.try_bounded(name, &start, &end, escapable).is_ok()
+        }
+    }
+
+    /// Expand any {{variables}} within a pattern
+    fn expand(&self, exp: &str) -> String {
+        let mut exp = exp.to_string();
+        // Variables can refer to other variables, but give up on cycles
+        for _ in 0..16 {
+            if !exp.contains("{{") { break; }
+            for (name, value) in &self.file.variables {
+                exp = exp.replace(&format!("{{{{{name}}}}}"), value);
+            }
+        }
+        exp
+    }
+}
+
+/// The scope of a rule, either the scope of the whole match,
+/// or of the last capture group (which is what synoptic highlights)
+fn rule_scope(rule: &Mapping, whole: bool) -> Option<String> {
+    let scope = if whole {
+        rule.get("scope")?.as_str()?.to_string()
+    } else {
+        let captures = rule.get("captures")?.as_mapping()?;
+        let last = captures.iter().max_by_key(|(k, _)| k.as_u64().unwrap_or(0))?;
+        last.1.as_str()?.to_string()
+    };
+    scope.split_whitespace().next().map(str::to_string)
+}
+
+/// Map a Sublime scope onto the names used by synoptic's built-in languages
+fn token_name(scope: &str) -> &str {
+    const MAPPING: [(&str, &str); 20] = [
+        ("keyword.operator", "operator"),
+        ("constant.numeric", "digit"),
+        ("constant.language", "boolean"),
+        ("constant.character", "character"),
+        ("entity.name.function", "function"),
+        ("support.function", "function"),
+        ("variable.function", "function"),
+        ("entity.name.type", "struct"),
+        ("entity.name.class", "struct"),
+        ("entity.name.struct", "struct"),
+        ("support.type", "struct"),
+        ("storage.type", "keyword"),
+        ("storage.modifier", "keyword"),
+        ("entity.name.tag", "tag"),
+        ("entity.other.attribute-name", "attribute"),
+        ("entity.name.namespace", "namespace"),
+        ("markup.heading", "heading"),
+        ("markup.bold", "bold"),
+        ("markup.italic", "italic"),
+        ("markup.quote", "quote"),
+    ];
+    for (prefix, name) in MAPPING {
+        if scope.starts_with(prefix) { return name; }
+    }
+    // Otherwise use the most general part of the scope
+    match scope.split('.').next().unwrap_or(scope) {
+        "punctuation" => "operator",
+        general => general,
+    }
+}
+
+/// Turn every capture group in a regex into a non-capturing group,
+/// so that synoptic highlights the whole match
+fn uncapture(exp: &str) -> String {
+    let mut result = String::with_capacity(exp.len());
+    let mut chars = exp.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        result.push(c);
+        match c {
+            '\\' => if let Some(c) = chars.next() { result.push(c) },
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => {
+                if chars.peek() != Some(&'?') {
+                    result.push_str("?:");
+                } else {
+                    // Named groups, e.g. (?<name>...) or (?P<name>...)
+                    let rest: String = chars.clone().take(3).collect();
+                    if rest.starts_with("?<") && !rest.starts_with("?<=") && !rest.starts_with("?<!")
+                        || rest.starts_with("?P<")
+                    {
+                        for c in chars.by_ref() {
+                            if c == '>' { break; }
+                        }
+                        result.push_str("?:");
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    result
+}
@@ -0,0 +1,237 @@
+//! Themes, which decide how each kind of token should be displayed.
+//!
+//! Token names can be made more specific using dots, e.g. `string.interpolated`.
+//! When a theme has no style for a name, the last part is removed and the lookup is tried
+//! again (so `string.interpolated` falls back to `string`), until the theme's default is reached.
+//!
+//! ```
+//! # use synoptic::theme::{Colour, Style, Theme};
+//! let theme = Theme::new("mine")
+//!     .with("string", Style::fg(Colour::Rgb(0, 200, 0)))
+//!     .with("string.interpolated", Style::fg(Colour::Rgb(0, 200, 0)).italic());
+//! assert!(theme.style("string.interpolated").italic);
+//! assert_eq!(theme.style("string.escape"), theme.style("string"));
+//! assert_eq!(theme.style("unknown"), &theme.default);
+//! ```
+
+use std::collections::HashMap;
+
+/// A colour that a token can be displayed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Colour {
+    /// A 24-bit colour, made up of red, green and blue components
+    Rgb(u8, u8, u8),
+    /// A colour from the 256-colour palette supported by most terminals
+    Ansi256(u8),
+    /// A colour from the 16 basic terminal colours (0-7 are normal, 8-15 are bright)
+    Ansi16(u8),
+}
+
+/// How a token should be displayed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Style {
+    /// The foreground (text) colour, None to leave it as it is
+    pub fg: Option<Colour>,
+    /// The background colour, None to leave it as it is
+    pub bg: Option<Colour>,
+    /// Whether the text is bold
+    pub bold: bool,
+    /// Whether the text is italic
+    pub italic: bool,
+    /// Whether the text is underlined
+    pub underline: bool,
+}
+
+impl Style {
+    /// Creates a style with just a foreground colour
+    pub fn fg(colour: Colour) -> Self {
+        Self { fg: Some(colour), ..Self::default() }
+    }
+
+    /// Give this style a background colour
+    pub fn on(mut self, colour: Colour) -> Self {
+        self.bg = Some(colour);
+        self
+    }
+
+    /// Make this style bold
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Make this style italic
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    /// Make this style underlined
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Whether this style doesn't change how text is displayed at all
+    pub fn is_plain(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// A mapping of token names to styles, see the [module documentation](self)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Theme {
+    /// The name of the theme
+    pub name: String,
+    /// The style used for plain text, and tokens that the theme has no style for
+    pub default: Style,
+    /// The styles for each token name
+    pub styles: HashMap<String, Style>,
+}
+
+impl Theme {
+    /// Creates a new, empty theme
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self { name: name.into(), ..Self::default() }
+    }
+
+    /// Set the style for a token name
+    pub fn set<S: Into<String>>(&mut self, name: S, style: Style) {
+        self.styles.insert(name.into(), style);
+    }
+
+    /// Set the style for a token name, for use when building up a theme
+    pub fn with<S: Into<String>>(mut self, name: S, style: Style) -> Self {
+        self.set(name, style);
+        self
+    }
+
+    /// Find the style for a token name, falling back to less specific names
+    /// and then to the default style
+    pub fn style(&self, name: &str) -> &Style {
+        let mut name = name;
+        loop {
+            if let Some(style) = self.styles.get(name) {
+                return style;
+            }
+            match name.rfind('.') {
+                Some(idx) => name = &name[..idx],
+                None => return &self.default,
+            }
+        }
+    }
+
+    /// The names of the built-in themes
+    pub fn builtin_names() -> &'static [&'static str] {
+        &["dark", "light"]
+    }
+
+    /// Get a built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// A theme for use on dark backgrounds
+    pub fn dark() -> Self {
+        let c = |r, g, b| Style::fg(Colour::Rgb(r, g, b));
+        Self::new("dark")
+            .with("comment", c(106, 115, 125).italic())
+            .with("string", c(152, 195, 121))
+            .with("character", c(152, 195, 121))
+            .with("digit", c(209, 154, 102))
+            .with("boolean", c(209, 154, 102))
+            .with("keyword", c(198, 120, 221))
+            .with("operator", c(86, 182, 194))
+            .with("function", c(97, 175, 239))
+            .with("macro", c(86, 182, 194))
+            .with("macros", c(86, 182, 194))
+            .with("struct", c(229, 192, 123))
+            .with("type", c(229, 192, 123))
+            .with("table", c(229, 192, 123))
+            .with("variable", c(224, 108, 117))
+            .with("field", c(224, 108, 117))
+            .with("register", c(224, 108, 117))
+            .with("builtin", c(86, 182, 194))
+            .with("mapping", c(86, 182, 194))
+            .with("escape", c(86, 182, 194))
+            .with("pattern", c(198, 120, 221).bold())
+            .with("regex", c(152, 195, 121))
+            .with("anchor", c(198, 120, 221))
+            .with("class", c(152, 195, 121))
+            .with("flag", c(209, 154, 102))
+            .with("group", c(86, 182, 194))
+            .with("quantifier", c(209, 154, 102))
+            .with("warning", c(224, 108, 117).bold())
+            .with("namespace", c(229, 192, 123))
+            .with("attribute", c(209, 154, 102))
+            .with("tag", c(224, 108, 117))
+            .with("key", c(224, 108, 117))
+            .with("reference", c(224, 108, 117))
+            .with("header", c(224, 108, 117).bold())
+            .with("heading", c(224, 108, 117).bold())
+            .with("bold", Style::default().bold())
+            .with("italic", Style::default().italic())
+            .with("link", c(97, 175, 239).underline())
+            .with("image", c(97, 175, 239).underline())
+            .with("quote", c(106, 115, 125).italic())
+            .with("list", c(209, 154, 102))
+            .with("info", c(229, 192, 123))
+            .with("math", c(86, 182, 194))
+            .with("insertion", c(152, 195, 121))
+            .with("deletion", c(224, 108, 117))
+    }
+
+    /// A theme for use on light backgrounds
+    pub fn light() -> Self {
+        let c = |r, g, b| Style::fg(Colour::Rgb(r, g, b));
+        Self::new("light")
+            .with("comment", c(160, 161, 167).italic())
+            .with("string", c(80, 161, 79))
+            .with("character", c(80, 161, 79))
+            .with("digit", c(152, 104, 1))
+            .with("boolean", c(152, 104, 1))
+            .with("keyword", c(166, 38, 164))
+            .with("operator", c(1, 132, 188))
+            .with("function", c(64, 120, 242))
+            .with("macro", c(1, 132, 188))
+            .with("macros", c(1, 132, 188))
+            .with("struct", c(193, 132, 1))
+            .with("type", c(193, 132, 1))
+            .with("table", c(193, 132, 1))
+            .with("variable", c(228, 86, 73))
+            .with("field", c(228, 86, 73))
+            .with("register", c(228, 86, 73))
+            .with("builtin", c(1, 132, 188))
+            .with("mapping", c(1, 132, 188))
+            .with("escape", c(1, 132, 188))
+            .with("pattern", c(166, 38, 164).bold())
+            .with("regex", c(80, 161, 79))
+            .with("anchor", c(166, 38, 164))
+            .with("class", c(80, 161, 79))
+            .with("flag", c(152, 104, 1))
+            .with("group", c(1, 132, 188))
+            .with("quantifier", c(152, 104, 1))
+            .with("warning", c(228, 86, 73).bold())
+            .with("namespace", c(193, 132, 1))
+            .with("attribute", c(152, 104, 1))
+            .with("tag", c(228, 86, 73))
+            .with("key", c(228, 86, 73))
+            .with("reference", c(228, 86, 73))
+            .with("header", c(228, 86, 73).bold())
+            .with("heading", c(228, 86, 73).bold())
+            .with("bold", Style::default().bold())
+            .with("italic", Style::default().italic())
+            .with("link", c(64, 120, 242).underline())
+            .with("image", c(64, 120, 242).underline())
+            .with("quote", c(160, 161, 167).italic())
+            .with("list", c(152, 104, 1))
+            .with("info", c(193, 132, 1))
+            .with("math", c(1, 132, 188))
+            .with("insertion", c(80, 161, 79))
+            .with("deletion", c(228, 86, 73))
+    }
+}